@@ -1,4 +1,7 @@
-use std::{env, fs, path::PathBuf};
+use std::{
+    env, fs,
+    path::{Path, PathBuf}
+};
 
 use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
 
@@ -98,8 +101,52 @@ enum Commands
     List
 }
 
+/// Collects all files below a directory, sorted by path
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>)
+{
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir).unwrap().map(|entry| entry.unwrap().path()).collect();
+    entries.sort();
+
+    for path in entries
+    {
+        if path.is_dir() == true
+        {
+            collect_files(&path, files);
+        }
+        else
+        {
+            files.push(path);
+        }
+    }
+}
+
+/// Generates the list of embedded default templates (`templates/v2`) for `src/embedded_templates.rs`
+fn embed_templates(out_dir: &Path)
+{
+    let template_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()).join("templates/v2");
+    println!("cargo:rerun-if-changed={}", template_dir.display());
+
+    let mut files = Vec::new();
+    collect_files(&template_dir, &mut files);
+
+    let mut code = String::from("/// Embedded template files as (path relative to the template root, contents)\npub const EMBEDDED_FILES: &[(&str, &[u8])] = &[\n");
+    for file in &files
+    {
+        let relative = file.strip_prefix(&template_dir).unwrap().components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+        code.push_str(&format!("    ({:?}, include_bytes!({:?})),\n", relative, file.display().to_string()));
+    }
+    code.push_str("];\n");
+
+    fs::write(out_dir.join("embedded_templates.rs"), code).unwrap();
+}
+
 fn main()
 {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    embed_templates(&out_dir);
+
     // Only generate man pages for release builds
     let profile = env::var("PROFILE").unwrap_or_default();
     if profile != "release"
@@ -107,7 +154,6 @@ fn main()
         return;
     }
 
-    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
     let man_dir = out_dir.join("man");
     fs::create_dir_all(&man_dir).unwrap();
 
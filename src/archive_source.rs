@@ -0,0 +1,301 @@
+//! Archive template sources for vibe-check
+//!
+//! Installs templates from versioned bundles shipped as `.tar.gz`, `.tar.zst`,
+//! or `.zip` archives, either from a local path or a URL. The template root
+//! (the directory containing templates.yml) is located at the archive root,
+//! inside a single top-level directory, or at an explicit `#path=<subpath>`.
+
+use std::{
+    fs,
+    io::{BufReader, Read},
+    path::{Path, PathBuf}
+};
+
+use owo_colors::OwoColorize;
+
+use crate::{Result, bom::check_relative_path, utils::split_source_options};
+
+/// Supported archive formats
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveFormat
+{
+    TarGz,
+    TarZst,
+    Zip
+}
+
+impl ArchiveFormat
+{
+    /// Detects the archive format from a file name or URL
+    ///
+    /// Query strings are ignored so that URLs such as signed release links work.
+    pub fn detect(location: &str) -> Option<Self>
+    {
+        let name = location.split('?').next().unwrap_or(location).to_lowercase();
+
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz")
+        {
+            Some(Self::TarGz)
+        }
+        else if name.ends_with(".tar.zst") || name.ends_with(".tzst")
+        {
+            Some(Self::TarZst)
+        }
+        else if name.ends_with(".zip")
+        {
+            Some(Self::Zip)
+        }
+        else
+        {
+            None
+        }
+    }
+}
+
+/// A template source packaged as an archive
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveSource
+{
+    /// Local path or URL of the archive (without options)
+    pub location: String,
+    /// Archive format detected from the file extension
+    pub format:   ArchiveFormat,
+    /// Template directory within the archive (empty to auto-detect)
+    pub path:     String
+}
+
+impl ArchiveSource
+{
+    /// Parses an archive source specification
+    ///
+    /// Returns None if the source does not refer to a supported archive.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - Path or URL, optionally followed by `#path=<subpath>`
+    pub fn parse(source: &str) -> Option<Self>
+    {
+        let (location, options) = split_source_options(source);
+        let format = ArchiveFormat::detect(location)?;
+
+        Some(Self { location: location.to_string(), format, path: options.get("path").map(|p| p.trim_matches('/').to_string()).unwrap_or_default() })
+    }
+
+    /// Returns true if the archive has to be downloaded first
+    pub fn is_remote(&self) -> bool
+    {
+        self.location.starts_with("http://") || self.location.starts_with("https://")
+    }
+
+    /// Unpacks a local archive file and returns the template root within it
+    ///
+    /// # Arguments
+    ///
+    /// * `archive_path` - Path to the archive file on disk
+    /// * `work_dir` - Empty directory to unpack into
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive cannot be read or no templates.yml is found
+    pub fn unpack(&self, archive_path: &Path, work_dir: &Path) -> Result<PathBuf>
+    {
+        fs::create_dir_all(work_dir)?;
+        let file = BufReader::new(fs::File::open(archive_path).map_err(|e| format!("Failed to open archive {}: {}", archive_path.display(), e))?);
+
+        if self.path.is_empty() == false &&
+            let Some(reason) = check_relative_path(&self.path)
+        {
+            return Err(format!("Invalid archive path '{}': {}", self.path, reason).into());
+        }
+
+        // Entries that would escape work_dir (absolute paths, "..") are rejected by both unpackers
+        match self.format
+        {
+            | ArchiveFormat::TarGz => unpack_tar(flate2::read::GzDecoder::new(file), work_dir)?,
+            | ArchiveFormat::TarZst => unpack_tar(zstd::stream::read::Decoder::new(file)?, work_dir)?,
+            | ArchiveFormat::Zip => zip::ZipArchive::new(file)?.extract(work_dir)?
+        }
+
+        // Symbolic links could point anywhere on disk and would be followed when copying into the store
+        let removed = remove_symlinks(work_dir)?;
+        if removed > 0
+        {
+            println!("{} Warning: Skipped {} symbolic link(s) in archive {}", "!".yellow(), removed, self.location);
+        }
+
+        self.find_template_root(work_dir)
+    }
+
+    /// Locates the directory containing templates.yml in an unpacked archive
+    ///
+    /// Uses the explicit subpath if given. Otherwise checks the archive root,
+    /// then a single top-level directory (the usual layout of release tarballs).
+    fn find_template_root(&self, work_dir: &Path) -> Result<PathBuf>
+    {
+        if self.path.is_empty() == false
+        {
+            let root = work_dir.join(&self.path);
+            if root.join("templates.yml").exists() == false
+            {
+                return Err(format!("templates.yml not found at '{}' in archive {}", self.path, self.location).into());
+            }
+            return Ok(root);
+        }
+
+        if work_dir.join("templates.yml").exists() == true
+        {
+            return Ok(work_dir.to_path_buf());
+        }
+
+        let entries: Vec<PathBuf> = fs::read_dir(work_dir)?.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+        if entries.len() == 1 && entries[0].is_dir() == true && entries[0].join("templates.yml").exists() == true
+        {
+            return Ok(entries[0].clone());
+        }
+
+        Err(format!("templates.yml not found at the root of archive {} (use #path=<subpath> to select a directory)", self.location).into())
+    }
+}
+
+/// Removes all symbolic links below a directory
+///
+/// Returns the number of links removed.
+fn remove_symlinks(dir: &Path) -> Result<usize>
+{
+    let mut removed = 0;
+    for entry in fs::read_dir(dir)?
+    {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+
+        if file_type.is_symlink() == true
+        {
+            // Directory links on Windows have to be removed as directories
+            fs::remove_file(entry.path()).or_else(|_| fs::remove_dir(entry.path()))?;
+            removed += 1;
+        }
+        else if file_type.is_dir() == true
+        {
+            removed += remove_symlinks(&entry.path())?;
+        }
+    }
+    Ok(removed)
+}
+
+/// Unpacks a tar stream into a directory
+fn unpack_tar(reader: impl Read, work_dir: &Path) -> Result<()>
+{
+    tar::Archive::new(reader).unpack(work_dir)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::io::Write;
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// Builds a tar archive of `dir` with all entries below `prefix`
+    fn build_tar(dir: &Path, prefix: &str) -> Vec<u8>
+    {
+        let mut builder = tar::Builder::new(Vec::new());
+        builder.append_dir_all(prefix, dir).unwrap();
+        builder.into_inner().unwrap()
+    }
+
+    #[test]
+    fn test_parse_archive_sources()
+    {
+        let source = ArchiveSource::parse("https://example.com/releases/templates-7.1.0.tar.gz?token=x#path=templates/v2").unwrap();
+        assert_eq!(source.format, ArchiveFormat::TarGz);
+        assert_eq!(source.location, "https://example.com/releases/templates-7.1.0.tar.gz?token=x");
+        assert_eq!(source.path, "templates/v2");
+        assert!(source.is_remote());
+
+        assert_eq!(ArchiveSource::parse("/tmp/bundle.tar.zst").unwrap().format, ArchiveFormat::TarZst);
+        assert_eq!(ArchiveSource::parse("bundle.ZIP").unwrap().format, ArchiveFormat::Zip);
+        assert_eq!(ArchiveSource::parse("/path/to/templates"), None);
+    }
+
+    #[test]
+    fn test_unpack_archive_formats() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let content = temp_dir.path().join("content");
+        fs::create_dir_all(content.join("v2/docs"))?;
+        fs::write(content.join("v2/templates.yml"), "version: 2\n")?;
+        fs::write(content.join("v2/docs/rust.md"), "## Rust")?;
+
+        // .tar.gz with a single top-level directory and the templates in a subdirectory
+        let tar_gz = temp_dir.path().join("bundle.tar.gz");
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&build_tar(&content, "bundle-7.1.0"))?;
+        fs::write(&tar_gz, encoder.finish()?)?;
+
+        let source = ArchiveSource::parse(&format!("{}#path=bundle-7.1.0/v2", tar_gz.display())).unwrap();
+        let root = source.unpack(&tar_gz, &temp_dir.path().join("out-gz"))?;
+        assert_eq!(fs::read_to_string(root.join("docs/rust.md"))?, "## Rust");
+
+        // .tar.zst with templates inside a single top-level directory (auto-detected)
+        let tar_zst = temp_dir.path().join("bundle.tar.zst");
+        fs::write(&tar_zst, zstd::encode_all(build_tar(&content.join("v2"), "bundle").as_slice(), 0)?)?;
+
+        let source = ArchiveSource::parse(&tar_zst.to_string_lossy()).unwrap();
+        let root = source.unpack(&tar_zst, &temp_dir.path().join("out-zst"))?;
+        assert!(root.ends_with("bundle"));
+        assert!(root.join("templates.yml").exists());
+
+        // .zip with templates.yml at the archive root
+        let zip_path = temp_dir.path().join("bundle.zip");
+        let mut writer = zip::ZipWriter::new(fs::File::create(&zip_path)?);
+        writer.start_file("templates.yml", zip::write::SimpleFileOptions::default())?;
+        writer.write_all(b"version: 2\n")?;
+        writer.start_file("docs/rust.md", zip::write::SimpleFileOptions::default())?;
+        writer.write_all(b"## Rust")?;
+        writer.finish()?;
+
+        let source = ArchiveSource::parse(&zip_path.to_string_lossy()).unwrap();
+        let root = source.unpack(&zip_path, &temp_dir.path().join("out-zip"))?;
+        assert_eq!(fs::read_to_string(root.join("docs/rust.md"))?, "## Rust");
+
+        // Missing templates.yml is reported
+        let source = ArchiveSource::parse(&format!("{}#path=missing", zip_path.display())).unwrap();
+        assert!(source.unpack(&zip_path, &temp_dir.path().join("out-missing")).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unpack_rejects_symlinks_and_traversal() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let secret = temp_dir.path().join("secret.txt");
+        fs::write(&secret, "outside the archive")?;
+
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(11);
+        header.set_mode(0o644);
+        builder.append_data(&mut header, "templates.yml", "version: 2\n".as_bytes())?;
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        builder.append_link(&mut header, "docs/leak.md", &secret)?;
+
+        let tar_zst = temp_dir.path().join("bundle.tar.zst");
+        fs::write(&tar_zst, zstd::encode_all(builder.into_inner()?.as_slice(), 0)?)?;
+
+        let source = ArchiveSource::parse(&tar_zst.to_string_lossy()).unwrap();
+        let root = source.unpack(&tar_zst, &temp_dir.path().join("out"))?;
+        assert!(root.join("templates.yml").exists());
+        assert!(root.join("docs/leak.md").symlink_metadata().is_err());
+
+        let source = ArchiveSource::parse(&format!("{}#path=../..", tar_zst.display())).unwrap();
+        assert!(source.unpack(&tar_zst, &temp_dir.path().join("out-traversal")).is_err());
+
+        Ok(())
+    }
+}
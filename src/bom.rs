@@ -2,81 +2,595 @@
 
 use std::{
     collections::HashMap,
-    fs,
+    error::Error,
+    fmt, fs,
     path::{Path, PathBuf}
 };
 
+use schemars::{JsonSchema, Schema};
 use serde::{Deserialize, Serialize};
 
 use crate::Result;
 
+/// How a template file is written when the target already exists
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileMode
+{
+    /// Replace the file (modified files are confirmed first)
+    #[default]
+    Replace,
+    /// Merge the template into the file (.gitignore, .gitattributes, .editorconfig, TOML, YAML, JSON)
+    Merge,
+    /// Only write the file if it does not exist yet
+    CreateOnly
+}
+
+/// Which side wins when a merged key is set in both the project file and the template
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum MergePreference
+{
+    /// Keep the value of the project file
+    #[default]
+    Local,
+    /// Use the value of the template
+    Template
+}
+
 /// File mapping with source and target paths
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct FileMapping
 {
+    /// Path of the template file, relative to templates.yml
     pub source: String,
-    pub target: String
+    /// `$instructions`, or a path below `$workspace/` or `$userprofile/`
+    pub target: String,
+    /// How the target is written if it exists (default: replace); ignored for `$instructions`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode:   Option<FileMode>,
+    /// Which side wins for keys set in both files with `mode: merge` (default: local)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefer: Option<MergePreference>,
+    /// Render the file with the template language (default: false, the file is copied as it is);
+    /// `$instructions` fragments are always rendered
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub render: Option<bool>
 }
 
 /// Agent configuration with instructions, prompts, and skills
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct AgentConfig
 {
+    /// Agent instruction files (e.g. CLAUDE.md referencing AGENTS.md)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub instructions: Option<Vec<FileMapping>>,
+    /// Prompts and commands
     #[serde(skip_serializing_if = "Option::is_none")]
     pub prompts:      Option<Vec<FileMapping>>,
+    /// Agent skills
     #[serde(skip_serializing_if = "Option::is_none")]
     pub skills:       Option<Vec<FileMapping>>
 }
 
 /// Language configuration with files
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct LanguageConfig
 {
     pub files: Vec<FileMapping>
 }
 
 /// Integration configuration with files
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct IntegrationConfig
 {
     pub files: Vec<FileMapping>
 }
 
 /// Main file configuration
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct MainConfig
 {
+    /// Path of the main template (AGENTS.md), relative to templates.yml
     pub source: String,
+    /// Where the merged main file is written (e.g. `$workspace/AGENTS.md`)
     pub target: String
 }
 
-/// Default version for templates.yml (used when version field is missing)
+/// How a fragment list of a child templates.yml is combined with its parent's
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeMode
+{
+    /// Add the child's entries after the parent's
+    #[default]
+    Append,
+    /// Use only the child's entries
+    Replace
+}
+
+/// Merge directives for fragment lists (the `merge:` key in templates.yml)
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct MergeDirectives
+{
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub principles: Option<MergeMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mission:    Option<MergeMode>
+}
+
+/// templates.yml format versions supported by this release
 ///
-/// Switched to version 2 in v7.0.0 (agents.md standard)
-fn default_version() -> u32
+/// Version 2 (agents.md standard) is the default since v7.0.0.
+pub const SUPPORTED_VERSIONS: &[u32] = &[1, 2];
+
+/// Kind of problem found while parsing templates.yml
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigErrorKind
+{
+    /// Not valid YAML, or a value of the wrong type
+    Syntax,
+    /// A key that is not part of the template format
+    UnknownField,
+    /// The `version` key is missing
+    MissingVersion,
+    /// The `version` is not supported by this release
+    UnsupportedVersion
+}
+
+/// Error parsing templates.yml, with the position of the problem
+#[derive(Debug)]
+pub struct ConfigError
+{
+    pub kind:    ConfigErrorKind,
+    /// 1-based line of the problem (if known)
+    pub line:    Option<usize>,
+    /// 1-based column of the problem (if known)
+    pub column:  Option<usize>,
+    /// Description without position
+    pub message: String,
+    /// Full diagnostic: `<origin>:<line>:<column>: <message>` followed by the offending line
+    rendered:    String
+}
+
+impl ConfigError
+{
+    /// Creates an error and renders its diagnostic
+    fn new(kind: ConfigErrorKind, message: String, position: Option<(usize, usize)>, content: &str, origin: &str) -> Self
+    {
+        let rendered = match position
+        {
+            | Some((line, column)) =>
+            {
+                let source_line = content.lines().nth(line.saturating_sub(1)).unwrap_or_default();
+                let gutter = " ".repeat(line.to_string().len());
+                format!(
+                    "{}:{}:{}: {}\n{} |\n{} | {}\n{} | {}^",
+                    origin,
+                    line,
+                    column,
+                    message,
+                    gutter,
+                    line,
+                    source_line,
+                    gutter,
+                    " ".repeat(column.saturating_sub(1))
+                )
+            }
+            | None => format!("{}: {}", origin, message)
+        };
+
+        Self { kind, line: position.map(|p| p.0), column: position.map(|p| p.1), message, rendered }
+    }
+}
+
+impl fmt::Display for ConfigError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        f.write_str(&self.rendered)
+    }
+}
+
+impl Error for ConfigError
 {
-    2
 }
 
 /// Template configuration structure parsed from templates.yml
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct TemplateConfig
 {
-    #[serde(default = "default_version")]
+    /// templates.yml format version (1 or 2)
     pub version:     u32,
+    /// Parent template source this configuration builds on (resolved on update)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extends:     Option<String>,
+    /// How fragment lists are combined with the parent or base templates
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merge:       Option<MergeDirectives>,
+    /// Main instruction file that fragments are merged into
     #[serde(skip_serializing_if = "Option::is_none")]
     pub main:        Option<MainConfig>,
+    /// Agent-specific files, keyed by agent name
     #[serde(skip_serializing_if = "Option::is_none")]
     pub agents:      Option<HashMap<String, AgentConfig>>,
+    /// Language-specific files, keyed by language name
     pub languages:   HashMap<String, LanguageConfig>,
+    /// Tool and workflow files, keyed by integration name
     #[serde(skip_serializing_if = "Option::is_none")]
     pub integration: Option<HashMap<String, IntegrationConfig>>,
+    /// Principle fragments
     #[serde(skip_serializing_if = "Option::is_none")]
     pub principles:  Option<Vec<FileMapping>>,
+    /// Mission fragments
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub mission:     Option<Vec<FileMapping>>
+    pub mission:     Option<Vec<FileMapping>>,
+    /// Shared files that templates insert with `{{> path}}` (installed with the templates but not copied on their own)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub includes:    Option<Vec<String>>
+}
+
+impl TemplateConfig
+{
+    /// Parses templates.yml strictly
+    ///
+    /// The `version` key is required and must be supported, and unknown keys are
+    /// rejected. Errors point at the line and column of the problem.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - Contents of templates.yml
+    /// * `origin` - File name or path used in diagnostics
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ConfigError` describing the first problem found
+    pub fn parse(content: &str, origin: &str) -> std::result::Result<Self, ConfigError>
+    {
+        let position = |e: &serde_yaml::Error| e.location().map(|l| (l.line(), l.column()));
+        let message = |e: &serde_yaml::Error| match e.location()
+        {
+            | Some(l) => e.to_string().trim_end_matches(&format!(" at line {} column {}", l.line(), l.column())).to_string(),
+            | None => e.to_string()
+        };
+
+        let document: serde_yaml::Value =
+            serde_yaml::from_str(content).map_err(|e| ConfigError::new(ConfigErrorKind::Syntax, message(&e), position(&e), content, origin))?;
+
+        match document.get("version")
+        {
+            | None =>
+                return Err(ConfigError::new(ConfigErrorKind::MissingVersion, "missing `version` key (add `version: 2`)".to_string(), Some((1, 1)), content, origin)),
+            | Some(version) if version.as_u64().is_none_or(|v| SUPPORTED_VERSIONS.contains(&(v as u32)) == false) =>
+            {
+                let line = content.lines().position(|l| l.starts_with("version:")).map_or(1, |index| index + 1);
+                return Err(ConfigError::new(
+                    ConfigErrorKind::UnsupportedVersion,
+                    format!("unsupported templates.yml version {} (supported: {})", serde_yaml::to_string(version).unwrap_or_default().trim(), supported_versions()),
+                    Some((line, 10)),
+                    content,
+                    origin
+                ));
+            }
+            | Some(_) =>
+            {}
+        }
+
+        serde_yaml::from_str(content).map_err(|e| {
+            let kind = if e.to_string().starts_with("unknown field") || e.to_string().contains(": unknown field")
+            {
+                ConfigErrorKind::UnknownField
+            }
+            else
+            {
+                ConfigErrorKind::Syntax
+            };
+            ConfigError::new(kind, message(&e), position(&e), content, origin)
+        })
+    }
+
+    /// Generates the JSON Schema of templates.yml for a format version
+    ///
+    /// The schema is derived from the Rust types, so it always matches the parser.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the version is not supported
+    pub fn schema_for_version(version: u32) -> Result<Schema>
+    {
+        if SUPPORTED_VERSIONS.contains(&version) == false
+        {
+            return Err(format!("Unsupported templates.yml version {} (supported: {})", version, supported_versions()).into());
+        }
+
+        let mut schema = schemars::schema_for!(TemplateConfig);
+        schema
+            .insert("$id".to_string(), format!("https://raw.githubusercontent.com/heikopanjas/vibe-check/develop/schemas/templates.v{}.schema.json", version).into());
+        schema.insert("title".to_string(), format!("vibe-check templates.yml (version {})", version).into());
+        if let Some(property) = schema.pointer_mut("/properties/version")
+        {
+            *property = serde_json::json!({ "description": "templates.yml format version", "const": version });
+        }
+        Ok(schema)
+    }
+
+    /// Returns all file mappings (excluding main) in templates.yml order of sections
+    ///
+    /// Covers principles, mission, languages, integration, and agent
+    /// instructions/prompts/skills.
+    pub fn file_mappings(&self) -> Vec<&FileMapping>
+    {
+        let mut mappings: Vec<&FileMapping> = Vec::new();
+
+        if let Some(principles) = &self.principles
+        {
+            mappings.extend(principles);
+        }
+
+        if let Some(mission) = &self.mission
+        {
+            mappings.extend(mission);
+        }
+
+        for lang_config in self.languages.values()
+        {
+            mappings.extend(&lang_config.files);
+        }
+
+        if let Some(integration_map) = &self.integration
+        {
+            for integration_config in integration_map.values()
+            {
+                mappings.extend(&integration_config.files);
+            }
+        }
+
+        if let Some(agents) = &self.agents
+        {
+            for agent_config in agents.values()
+            {
+                for list in [&agent_config.instructions, &agent_config.prompts, &agent_config.skills].into_iter().flatten()
+                {
+                    mappings.extend(list);
+                }
+            }
+        }
+
+        mappings
+    }
+
+    /// Returns all source file paths referenced by templates.yml (deduplicated, sorted)
+    pub fn source_files(&self) -> Vec<&str>
+    {
+        let mut sources: Vec<&str> = self.file_mappings().into_iter().map(|m| m.source.as_str()).collect();
+
+        if let Some(main) = &self.main
+        {
+            sources.push(main.source.as_str());
+        }
+
+        sources.extend(self.includes.iter().flatten().map(String::as_str));
+
+        sources.sort();
+        sources.dedup();
+        sources
+    }
+
+    /// Returns the referenced source files that do not exist below `template_dir`
+    ///
+    /// # Arguments
+    ///
+    /// * `template_dir` - Directory containing templates.yml and its source files
+    pub fn missing_sources(&self, template_dir: &Path) -> Vec<String>
+    {
+        self.source_files().into_iter().filter(|source| template_dir.join(source).is_file() == false).map(|s| s.to_string()).collect()
+    }
+
+    /// Validates all source and target paths in templates.yml
+    ///
+    /// Templates may come from remote sources, so paths are untrusted. Sources and
+    /// includes must be relative paths inside the template directory. Targets must be `$instructions` or
+    /// start with `$workspace/` or `$userprofile/` and must not escape that directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error listing every rejected path
+    pub fn validate_paths(&self) -> Result<()>
+    {
+        let mut entries: Vec<(&str, &str)> = self.file_mappings().into_iter().map(|m| (m.source.as_str(), m.target.as_str())).collect();
+        if let Some(main) = &self.main
+        {
+            entries.push((main.source.as_str(), main.target.as_str()));
+        }
+
+        let mut problems = Vec::new();
+        for include in self.includes.iter().flatten()
+        {
+            if let Some(reason) = check_relative_path(include)
+            {
+                problems.push(format!("include '{}': {}", include, reason));
+            }
+        }
+
+        for (source, target) in entries
+        {
+            if let Some(reason) = check_relative_path(source)
+            {
+                problems.push(format!("source '{}': {}", source, reason));
+            }
+            if let Some(reason) = check_target_path(target)
+            {
+                problems.push(format!("target '{}': {}", target, reason));
+            }
+        }
+
+        if problems.is_empty() == false
+        {
+            problems.sort();
+            problems.dedup();
+            return Err(format!("templates.yml contains unsafe paths:\n  {}", problems.join("\n  ")).into());
+        }
+
+        Ok(())
+    }
+
+    /// Merges the templates.yml of an overlay layer or child template into this configuration
+    ///
+    /// Agents, languages, and integrations defined by the overlay are added, or
+    /// replace the entry of the same name. Principles and mission fragments are
+    /// appended (mappings that are already present are skipped), or replace the
+    /// existing list if the overlay's `merge:` directive says `replace`. A `main`
+    /// entry in the overlay replaces the base one. Includes are added to the base ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `overlay` - Parsed templates.yml of the overlay or child
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the overlay uses a different templates.yml version
+    pub fn merge_overlay(&mut self, overlay: TemplateConfig) -> Result<()>
+    {
+        if overlay.version != self.version
+        {
+            return Err(format!("Overlay uses templates.yml version {} but the base templates use version {}", overlay.version, self.version).into());
+        }
+
+        if overlay.main.is_some()
+        {
+            self.main = overlay.main;
+        }
+
+        if let Some(agents) = overlay.agents
+        {
+            self.agents.get_or_insert_with(HashMap::new).extend(agents);
+        }
+
+        self.languages.extend(overlay.languages);
+
+        if let Some(integration) = overlay.integration
+        {
+            self.integration.get_or_insert_with(HashMap::new).extend(integration);
+        }
+
+        for include in overlay.includes.unwrap_or_default()
+        {
+            let includes = self.includes.get_or_insert_with(Vec::new);
+            if includes.contains(&include) == false
+            {
+                includes.push(include);
+            }
+        }
+
+        let directives = overlay.merge.unwrap_or_default();
+        for (fragments, additions, mode) in
+            [(&mut self.principles, overlay.principles, directives.principles), (&mut self.mission, overlay.mission, directives.mission)]
+        {
+            let list = fragments.get_or_insert_with(Vec::new);
+            if mode == Some(MergeMode::Replace)
+            {
+                list.clear();
+            }
+
+            for mapping in additions.unwrap_or_default()
+            {
+                if list.contains(&mapping) == false
+                {
+                    list.push(mapping);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Serializes the configuration as templates.yml with map keys in sorted order
+    ///
+    /// Sorting keeps the output (and its checksum in templates.lock) stable across runs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization fails
+    pub fn to_yaml(&self) -> Result<String>
+    {
+        Ok(serde_yaml::to_string(&sort_mappings(serde_yaml::to_value(self)?))?)
+    }
+}
+
+/// Returns the supported versions as a comma-separated list
+fn supported_versions() -> String
+{
+    SUPPORTED_VERSIONS.iter().map(u32::to_string).collect::<Vec<_>>().join(", ")
+}
+
+/// Recursively sorts the keys of every mapping in a YAML value
+fn sort_mappings(value: serde_yaml::Value) -> serde_yaml::Value
+{
+    match value
+    {
+        | serde_yaml::Value::Mapping(mapping) =>
+        {
+            let mut entries: Vec<(serde_yaml::Value, serde_yaml::Value)> = mapping.into_iter().map(|(key, value)| (key, sort_mappings(value))).collect();
+            entries.sort_by(|a, b| a.0.as_str().cmp(&b.0.as_str()));
+            serde_yaml::Value::Mapping(entries.into_iter().collect())
+        }
+        | serde_yaml::Value::Sequence(items) => serde_yaml::Value::Sequence(items.into_iter().map(sort_mappings).collect()),
+        | other => other
+    }
+}
+
+/// Checks that a path is relative and stays below its base directory
+///
+/// Returns the reason the path is rejected, or None if it is safe.
+pub(crate) fn check_relative_path(path: &str) -> Option<&'static str>
+{
+    if path.trim().is_empty() == true
+    {
+        return Some("path is empty");
+    }
+
+    // Reject both Unix and Windows absolute forms regardless of the current platform
+    let bytes = path.as_bytes();
+    if path.starts_with('/') || path.starts_with('\\') || (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')
+    {
+        return Some("absolute paths are not allowed");
+    }
+
+    if path.split(['/', '\\']).any(|component| component == "..")
+    {
+        return Some("path traversal ('..') is not allowed");
+    }
+
+    None
+}
+
+/// Checks that a target is `$instructions` or a safe path below `$workspace` or `$userprofile`
+///
+/// Returns the reason the target is rejected, or None if it is safe.
+pub(crate) fn check_target_path(target: &str) -> Option<&'static str>
+{
+    if target == "$instructions"
+    {
+        return None;
+    }
+
+    for placeholder in ["$workspace", "$userprofile"]
+    {
+        if let Some(suffix) = target.strip_prefix(placeholder) &&
+            (suffix.starts_with('/') || suffix.starts_with('\\'))
+        {
+            return check_relative_path(suffix.trim_start_matches(['/', '\\']));
+        }
+    }
+
+    Some("target must be '$instructions' or start with '$workspace/' or '$userprofile/'")
 }
 
 /// Bill of Materials - maps agent names to their target file paths
@@ -118,7 +632,8 @@ pub fn new() -> Self
     pub fn from_config(config_path: &Path) -> Result<Self>
     {
         let config_content = fs::read_to_string(config_path)?;
-        let template_config: TemplateConfig = serde_yaml::from_str(&config_content)?;
+        let template_config = TemplateConfig::parse(&config_content, &config_path.display().to_string())?;
+        template_config.validate_paths()?;
 
         let mut bom = Self::new();
 
@@ -250,3 +765,149 @@ pub fn has_agent(&self, agent_name: &str) -> bool
         self.agent_files.contains_key(agent_name)
     }
 }
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    /// Parses a templates.yml with a single principles entry
+    fn config_with_entry(source: &str, target: &str) -> TemplateConfig
+    {
+        serde_yaml::from_str(&format!(
+            "version: 2\nmain:\n  source: AGENTS.md\n  target: '$workspace/AGENTS.md'\nlanguages: {{}}\nprinciples:\n  - source: '{}'\n    target: '{}'\n",
+            source, target
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validate_paths_accepts_safe_paths()
+    {
+        for (source, target) in [
+            ("principles/core.md", "$instructions"),
+            ("rust/.rustfmt.toml", "$workspace/.rustfmt.toml"),
+            ("claude/commands/init-session.md", "$workspace/.claude/commands/init-session.md"),
+            ("codex/prompts/init-session.md", "$userprofile/.codex/prompts/init-session.md"),
+            ("./docs/..hidden.md", "$workspace/docs/..hidden.md")
+        ]
+        {
+            assert!(config_with_entry(source, target).validate_paths().is_ok(), "{} -> {} should be accepted", source, target);
+        }
+    }
+
+    #[test]
+    fn test_validate_paths_rejects_malicious_paths()
+    {
+        for (source, target) in [
+            ("../../.bashrc", "$instructions"),
+            ("docs/../../../etc/passwd", "$instructions"),
+            ("/etc/passwd", "$instructions"),
+            ("C:\\Windows\\win.ini", "$instructions"),
+            ("..\\..\\secrets.txt", "$instructions"),
+            ("", "$instructions"),
+            ("principles.md", "$workspace/../../.bashrc"),
+            ("principles.md", "$userprofile/../other-user/.bashrc"),
+            ("principles.md", "$workspace\\..\\..\\evil.ps1"),
+            ("principles.md", "/etc/cron.d/evil"),
+            ("principles.md", "~/.bashrc"),
+            ("principles.md", "$workspaceevil/file"),
+            ("principles.md", "$workspace"),
+            ("principles.md", "$instructions/../../x")
+        ]
+        {
+            assert!(config_with_entry(source, target).validate_paths().is_err(), "{} -> {} should be rejected", source, target);
+        }
+
+        let mut config = config_with_entry("principles.md", "$instructions");
+        config.includes = Some(vec!["../shared/build.md".to_string()]);
+        assert!(config.validate_paths().is_err());
+    }
+
+    #[test]
+    fn test_merge_overlay()
+    {
+        let mut base: TemplateConfig = serde_yaml::from_str(
+            "version: 2\nmain:\n  source: AGENTS.md\n  target: '$workspace/AGENTS.md'\nlanguages:\n  rust:\n    files:\n      - source: rust.md\n        target: \
+             '$instructions'\n  go:\n    files: []\nprinciples:\n  - source: core.md\n    target: '$instructions'\n"
+        )
+        .unwrap();
+        let overlay: TemplateConfig =
+            serde_yaml::from_str(
+                "version: 2\nlanguages:\n  rust:\n    files:\n      - source: team/rust.md\n        target: '$instructions'\n  zig:\n    files: []\nagents:\n  \
+                 claude:\n    instructions:\n      - source: CLAUDE.md\n        target: '$workspace/CLAUDE.md'\nprinciples:\n  - source: core.md\n    target: \
+                 '$instructions'\n  - source: team/security.md\n    target: '$instructions'\n"
+            )
+            .unwrap();
+
+        base.merge_overlay(overlay).unwrap();
+
+        // Languages are added or replaced by name, the base main entry is kept
+        assert_eq!(base.languages["rust"].files[0].source, "team/rust.md");
+        assert!(base.languages.contains_key("go") && base.languages.contains_key("zig"));
+        assert!(base.agents.as_ref().unwrap().contains_key("claude"));
+        assert_eq!(base.main.as_ref().unwrap().source, "AGENTS.md");
+
+        // Fragments are appended without duplicates
+        let principles: Vec<&str> = base.principles.as_ref().unwrap().iter().map(|m| m.source.as_str()).collect();
+        assert_eq!(principles, vec!["core.md", "team/security.md"]);
+
+        // Output is stable regardless of map iteration order
+        assert_eq!(base.to_yaml().unwrap(), base.to_yaml().unwrap());
+        assert!(base.to_yaml().unwrap().find("go:").unwrap() < base.to_yaml().unwrap().find("rust:").unwrap());
+
+        // A replace directive drops the parent's fragments
+        let child: TemplateConfig = serde_yaml::from_str(
+            "version: 2\nextends: ../base\nmerge:\n  principles: replace\nlanguages: {}\nprinciples:\n  - source: team/core.md\n    target: '$instructions'\n"
+        )
+        .unwrap();
+        base.merge_overlay(child).unwrap();
+        assert_eq!(base.principles.as_ref().unwrap().len(), 1);
+        assert_eq!(base.principles.as_ref().unwrap()[0].source, "team/core.md");
+
+        let other_version: TemplateConfig = serde_yaml::from_str("version: 1\nlanguages: {}\n").unwrap();
+        assert!(base.merge_overlay(other_version).is_err());
+    }
+
+    #[test]
+    fn test_validate_paths_reports_all_problems()
+    {
+        let error = config_with_entry("../evil.md", "/tmp/evil").validate_paths().unwrap_err().to_string();
+        assert!(error.contains("source '../evil.md'"));
+        assert!(error.contains("target '/tmp/evil'"));
+    }
+
+    #[test]
+    fn test_parse_reports_line_and_column()
+    {
+        let error = TemplateConfig::parse("version: 2\nlangauges: {}\n", "templates.yml").unwrap_err();
+        assert_eq!(error.kind, ConfigErrorKind::UnknownField);
+        assert_eq!((error.line, error.column), (Some(2), Some(1)));
+        assert!(error.to_string().starts_with("templates.yml:2:1: unknown field `langauges`"), "{}", error);
+        assert!(error.to_string().ends_with("2 | langauges: {}\n  | ^"), "{}", error);
+
+        let error = TemplateConfig::parse("languages: {}\n", "templates.yml").unwrap_err();
+        assert_eq!(error.kind, ConfigErrorKind::MissingVersion);
+
+        let error = TemplateConfig::parse("# comment\nversion: 7\nlanguages: {}\n", "templates.yml").unwrap_err();
+        assert_eq!(error.kind, ConfigErrorKind::UnsupportedVersion);
+        assert_eq!(error.line, Some(2));
+
+        assert!(TemplateConfig::parse("version: 1\nlanguages: {}\n", "templates.yml").is_ok());
+    }
+
+    #[test]
+    fn test_published_schemas_are_current() -> Result<()>
+    {
+        // Regenerate with: vibe-check schema --version <n> > schemas/templates.v<n>.schema.json
+        for version in SUPPORTED_VERSIONS
+        {
+            let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(format!("schemas/templates.v{}.schema.json", version));
+            let published: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path)?)?;
+            assert_eq!(&published, TemplateConfig::schema_for_version(*version)?.as_value(), "{} is out of date", path.display());
+        }
+
+        assert!(TemplateConfig::schema_for_version(3).is_err());
+        Ok(())
+    }
+}
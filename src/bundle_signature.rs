@@ -0,0 +1,194 @@
+//! Template bundle signature verification for vibe-check
+//!
+//! A signed bundle ships two extra files next to templates.yml:
+//! - `templates.manifest` - SHA-256 of templates.yml and every template file, one `<sha256>  <path>` line per file (the `sha256sum` output format)
+//! - `templates.manifest.minisig` - detached minisign signature over the manifest
+//!
+//! Publishers sign the manifest with their minisign (ed25519) key; users list
+//! the trusted public keys in the `verify.trusted_keys` config key.
+
+use std::{collections::HashMap, fs, path::Path};
+
+use minisign_verify::{PublicKey, Signature};
+
+use crate::{Result, bom::TemplateConfig, file_tracker::FileTracker};
+
+/// File name of the file manifest inside a template bundle
+pub const MANIFEST_FILE_NAME: &str = "templates.manifest";
+
+/// File name of the detached manifest signature inside a template bundle
+pub const SIGNATURE_FILE_NAME: &str = "templates.manifest.minisig";
+
+/// Parses a trusted public key (base64, as printed by `minisign -G`)
+///
+/// # Errors
+///
+/// Returns an error if the key is not a valid minisign public key
+pub fn parse_public_key(key: &str) -> Result<PublicKey>
+{
+    PublicKey::from_base64(key.trim()).map_err(|e| format!("Invalid minisign public key '{}': {}", key.trim(), e).into())
+}
+
+/// Verifies the signature and file manifest of a template bundle
+///
+/// The manifest signature must verify against one of the trusted keys, every
+/// file listed in the manifest must match its checksum, and templates.yml plus
+/// every file it references must be listed.
+///
+/// # Arguments
+///
+/// * `template_dir` - Directory containing the bundle
+/// * `config` - Parsed templates.yml from that directory
+/// * `trusted_keys` - Trusted minisign public keys (base64)
+///
+/// # Returns
+///
+/// The trusted comment of the signature
+///
+/// # Errors
+///
+/// Returns an error if the bundle is unsigned, the signature is invalid or made
+/// by an untrusted key, or the files do not match the manifest
+pub fn verify_bundle(template_dir: &Path, config: &TemplateConfig, trusted_keys: &[String]) -> Result<String>
+{
+    if trusted_keys.is_empty() == true
+    {
+        return Err("Signature verification is enabled but no trusted keys are configured (set verify.trusted_keys)".into());
+    }
+
+    let manifest_path = template_dir.join(MANIFEST_FILE_NAME);
+    let signature_path = template_dir.join(SIGNATURE_FILE_NAME);
+    if manifest_path.exists() == false || signature_path.exists() == false
+    {
+        return Err(format!("Template bundle is not signed ({} and {} are required)", MANIFEST_FILE_NAME, SIGNATURE_FILE_NAME).into());
+    }
+
+    let manifest = fs::read(&manifest_path)?;
+    let signature = Signature::decode(&fs::read_to_string(&signature_path)?).map_err(|e| format!("Invalid signature file {}: {}", SIGNATURE_FILE_NAME, e))?;
+
+    let mut verified = false;
+    for key in trusted_keys
+    {
+        if parse_public_key(key)?.verify(&manifest, &signature, false).is_ok()
+        {
+            verified = true;
+            break;
+        }
+    }
+
+    if verified == false
+    {
+        return Err("Template bundle signature is invalid or was not made by a trusted key".into());
+    }
+
+    let entries = parse_manifest(&String::from_utf8(manifest)?)?;
+
+    for (file, expected) in &entries
+    {
+        let path = template_dir.join(file);
+        if path.exists() == false
+        {
+            return Err(format!("File listed in {} is missing: {}", MANIFEST_FILE_NAME, file).into());
+        }
+        if FileTracker::calculate_sha256(&path)? != *expected
+        {
+            return Err(format!("Checksum mismatch for {} (file does not match the signed manifest)", file).into());
+        }
+    }
+
+    // A template that extends another only ships its own files; inherited files
+    // are covered by the parent's signature
+    let mut required = vec!["templates.yml"];
+    required.extend(config.source_files().into_iter().filter(|file| config.extends.is_none() || template_dir.join(file).exists()));
+    let unlisted: Vec<&str> = required.into_iter().filter(|file| entries.contains_key(*file) == false).collect();
+
+    if unlisted.is_empty() == false
+    {
+        return Err(format!("Files not covered by the signed manifest:\n  {}", unlisted.join("\n  ")).into());
+    }
+
+    Ok(signature.trusted_comment().to_string())
+}
+
+/// Parses a manifest in `sha256sum` format into a map of path to checksum
+fn parse_manifest(content: &str) -> Result<HashMap<String, String>>
+{
+    let mut entries = HashMap::new();
+
+    for line in content.lines().map(str::trim_end).filter(|l| l.is_empty() == false)
+    {
+        // "<hex>  <path>" (text mode) or "<hex> *<path>" (binary mode)
+        let (sha, file) = line.split_once(' ').ok_or_else(|| format!("Invalid line in {}: {}", MANIFEST_FILE_NAME, line))?;
+        let file = file.trim_start_matches([' ', '*']).trim_start_matches("./");
+
+        if sha.len() != 64 || sha.chars().all(|c| c.is_ascii_hexdigit()) == false
+        {
+            return Err(format!("Invalid checksum in {}: {}", MANIFEST_FILE_NAME, line).into());
+        }
+
+        entries.insert(file.to_string(), sha.to_lowercase());
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::io::Cursor;
+
+    use minisign::KeyPair;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// Writes a manifest for the given files and signs it with the key pair
+    fn sign_bundle(dir: &Path, files: &[&str], key_pair: &KeyPair) -> Result<()>
+    {
+        let mut manifest = String::new();
+        for file in files
+        {
+            manifest.push_str(&format!("{}  {}\n", FileTracker::calculate_sha256(&dir.join(file))?, file));
+        }
+        fs::write(dir.join(MANIFEST_FILE_NAME), &manifest)?;
+
+        let signature = minisign::sign(Some(&key_pair.pk), &key_pair.sk, Cursor::new(manifest.as_bytes()), Some("test bundle"), None)?;
+        fs::write(dir.join(SIGNATURE_FILE_NAME), signature.to_string())?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_bundle() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let dir = temp_dir.path();
+        fs::write(dir.join("templates.yml"), "version: 2\nmain:\n  source: AGENTS.md\n  target: '$workspace/AGENTS.md'\nlanguages: {}\n")?;
+        fs::write(dir.join("AGENTS.md"), "# Agents")?;
+        let config: TemplateConfig = serde_yaml::from_str(&fs::read_to_string(dir.join("templates.yml"))?)?;
+
+        let publisher = KeyPair::generate_unencrypted_keypair()?;
+        let other = KeyPair::generate_unencrypted_keypair()?;
+        let trusted = vec![publisher.pk.to_base64()];
+
+        // Unsigned bundle is refused
+        assert!(verify_bundle(dir, &config, &trusted).is_err());
+
+        // Valid signature from a trusted key
+        sign_bundle(dir, &["templates.yml", "AGENTS.md"], &publisher)?;
+        assert_eq!(verify_bundle(dir, &config, &trusted)?, "test bundle");
+
+        // Signed by an untrusted key
+        assert!(verify_bundle(dir, &config, &[other.pk.to_base64()]).is_err());
+
+        // File modified after signing
+        fs::write(dir.join("AGENTS.md"), "# Injected instructions")?;
+        let error = verify_bundle(dir, &config, &trusted).unwrap_err();
+        assert!(error.to_string().contains("Checksum mismatch for AGENTS.md"));
+
+        // Referenced file left out of the manifest
+        sign_bundle(dir, &["templates.yml"], &publisher)?;
+        assert!(verify_bundle(dir, &config, &trusted).is_err());
+
+        Ok(())
+    }
+}
@@ -4,30 +4,174 @@
 //! - `$XDG_CONFIG_HOME/vibe-check/config.yml` (if XDG_CONFIG_HOME is set)
 //! - `$HOME/.config/vibe-check/config.yml` (fallback)
 
-use std::{collections::HashMap, env, fs, path::PathBuf};
+use std::{
+    collections::{BTreeMap, HashMap},
+    env, fs,
+    path::{Path, PathBuf}
+};
 
 use serde::{Deserialize, Serialize};
 
-use crate::Result;
+use crate::{Result, bundle_signature, credentials, download_manager, source_provider::ProviderKind, template_variables};
 
 /// Configuration structure for vibe-check
 ///
 /// Uses a nested HashMap to support dotted key access (e.g., "source.url")
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Config
 {
     #[serde(default)]
-    pub source: SourceConfig
+    pub source:  SourceConfig,
+    #[serde(default)]
+    pub verify:  VerifyConfig,
+    #[serde(default)]
+    pub network: NetworkConfig,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub vars:    BTreeMap<String, String>
 }
 
 /// Source-related configuration
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct SourceConfig
 {
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub url:      Option<String>,
+    pub url:       Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fallback:  Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider:  Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_env: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub overlays:  Option<Vec<String>>
+}
+
+/// Template signature verification configuration
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct VerifyConfig
+{
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled:      Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trusted_keys: Option<Vec<String>>
+}
+
+/// Network configuration for template downloads
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct NetworkConfig
+{
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout:   Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retries:   Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parallel:  Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offline:   Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub fallback: Option<String>
+    pub proxy:     Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_proxy:  Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ca_bundle: Option<String>
+}
+
+impl NetworkConfig
+{
+    /// Default number of concurrent downloads
+    pub const DEFAULT_PARALLEL: usize = 8;
+    /// Default number of retries for transient failures
+    pub const DEFAULT_RETRIES: u32 = 3;
+    /// Default request timeout in seconds
+    pub const DEFAULT_TIMEOUT: u64 = 30;
+
+    /// Returns the request timeout in seconds
+    pub fn timeout(&self) -> u64
+    {
+        self.timeout.unwrap_or(Self::DEFAULT_TIMEOUT)
+    }
+
+    /// Returns the number of retries for transient failures
+    pub fn retries(&self) -> u32
+    {
+        self.retries.unwrap_or(Self::DEFAULT_RETRIES)
+    }
+
+    /// Returns the number of concurrent downloads (at least 1)
+    pub fn parallel(&self) -> usize
+    {
+        self.parallel.unwrap_or(Self::DEFAULT_PARALLEL).max(1)
+    }
+
+    /// Returns true if network access is disabled
+    pub fn offline(&self) -> bool
+    {
+        self.offline == Some(true)
+    }
+
+    /// Returns the environment for git commands that applies the proxy and CA settings
+    ///
+    /// git reads the same proxy variables as curl. `GIT_SSL_CAINFO` replaces the
+    /// CA store git uses instead of adding to it, so it points to a copy of the
+    /// system CA certificates with the configured bundle appended. If no system
+    /// CA file is found, the configured bundle is used alone.
+    pub fn git_env(&self) -> Vec<(&'static str, String)>
+    {
+        let mut vars = Vec::new();
+
+        if let Some(proxy) = &self.proxy
+        {
+            vars.push(("http_proxy", proxy.clone()));
+            vars.push(("HTTPS_PROXY", proxy.clone()));
+        }
+
+        if let Some(no_proxy) = &self.no_proxy
+        {
+            vars.push(("no_proxy", no_proxy.clone()));
+            vars.push(("NO_PROXY", no_proxy.clone()));
+        }
+
+        if let Some(ca_bundle) = &self.ca_bundle
+        {
+            let combined = openssl_probe::probe()
+                .cert_file
+                .zip(dirs::cache_dir())
+                .and_then(|(system, cache_dir)| combine_ca_bundles(&system, Path::new(ca_bundle), &cache_dir.join("vibe-check")).ok());
+            vars.push(("GIT_SSL_CAINFO", combined.map_or_else(|| ca_bundle.clone(), |path| path.to_string_lossy().to_string())));
+        }
+
+        vars
+    }
+}
+
+/// Writes the system CA certificates followed by the configured bundle into one file
+///
+/// # Arguments
+///
+/// * `system` - System CA file
+/// * `ca_bundle` - Configured CA bundle (`network.ca_bundle`)
+/// * `dir` - Directory to write `ca-bundle.pem` into
+///
+/// # Errors
+///
+/// Returns an error if a bundle cannot be read or the combined file cannot be written
+fn combine_ca_bundles(system: &Path, ca_bundle: &Path, dir: &Path) -> Result<PathBuf>
+{
+    let mut combined = fs::read(system)?;
+    if combined.ends_with(b"\n") == false
+    {
+        combined.push(b'\n');
+    }
+    combined.extend(fs::read(ca_bundle)?);
+
+    // Written next to the target and renamed, so concurrent git commands never read a partial file
+    fs::create_dir_all(dir)?;
+    let path = dir.join("ca-bundle.pem");
+    let partial = dir.join(format!("ca-bundle.pem.{}", std::process::id()));
+    fs::write(&partial, combined)?;
+    fs::rename(&partial, &path)?;
+
+    Ok(path)
 }
 
 impl Config
@@ -97,7 +241,19 @@ pub fn get(&self, key: &str) -> Option<String>
         {
             | "source.url" => self.source.url.clone(),
             | "source.fallback" => self.source.fallback.clone(),
-            | _ => None
+            | "source.provider" => self.source.provider.clone(),
+            | "source.token_env" => self.source.token_env.clone(),
+            | "source.overlays" => self.source.overlays.as_ref().map(|overlays| overlays.join(",")),
+            | "verify.enabled" => self.verify.enabled.map(|v| v.to_string()),
+            | "verify.trusted_keys" => self.verify.trusted_keys.as_ref().map(|keys| keys.join(",")),
+            | "network.timeout" => self.network.timeout.map(|v| v.to_string()),
+            | "network.retries" => self.network.retries.map(|v| v.to_string()),
+            | "network.parallel" => self.network.parallel.map(|v| v.to_string()),
+            | "network.offline" => self.network.offline.map(|v| v.to_string()),
+            | "network.proxy" => self.network.proxy.clone(),
+            | "network.no_proxy" => self.network.no_proxy.clone(),
+            | "network.ca_bundle" => self.network.ca_bundle.clone(),
+            | _ => key.strip_prefix("vars.").and_then(|name| self.vars.get(name).cloned())
         }
     }
 
@@ -118,6 +274,98 @@ pub fn set(&mut self, key: &str, value: &str) -> Result<()>
                 self.source.fallback = Some(value.to_string());
                 Ok(())
             }
+            | "source.provider" =>
+            {
+                // Validate provider name before persisting it
+                ProviderKind::from_name(value)?;
+                self.source.provider = Some(value.to_lowercase());
+                Ok(())
+            }
+            | "source.token_env" =>
+            {
+                // Only the variable name is stored; the token stays in the environment
+                credentials::validate_token_env(value)?;
+                self.source.token_env = Some(value.to_string());
+                Ok(())
+            }
+            | "source.overlays" =>
+            {
+                // Comma-separated sources, applied in order on top of the base source
+                let overlays: Vec<String> = value.split(',').map(|o| o.trim().to_string()).filter(|o| o.is_empty() == false).collect();
+                self.source.overlays = Some(overlays);
+                Ok(())
+            }
+            | "verify.enabled" =>
+            {
+                let enabled = value.parse::<bool>().map_err(|_| format!("Invalid value for verify.enabled: {} (expected true or false)", value))?;
+                self.verify.enabled = Some(enabled);
+                Ok(())
+            }
+            | "verify.trusted_keys" =>
+            {
+                // Comma-separated list of minisign public keys; validate each before persisting
+                let keys: Vec<String> = value.split(',').map(|k| k.trim().to_string()).filter(|k| k.is_empty() == false).collect();
+                for key in &keys
+                {
+                    bundle_signature::parse_public_key(key)?;
+                }
+                self.verify.trusted_keys = Some(keys);
+                Ok(())
+            }
+            | "network.timeout" =>
+            {
+                let timeout =
+                    value.parse::<u64>().ok().filter(|t| *t > 0).ok_or_else(|| format!("Invalid value for network.timeout: {} (expected seconds > 0)", value))?;
+                self.network.timeout = Some(timeout);
+                Ok(())
+            }
+            | "network.retries" =>
+            {
+                let retries = value.parse::<u32>().map_err(|_| format!("Invalid value for network.retries: {} (expected a number)", value))?;
+                self.network.retries = Some(retries);
+                Ok(())
+            }
+            | "network.parallel" =>
+            {
+                let parallel =
+                    value.parse::<usize>().ok().filter(|p| *p > 0).ok_or_else(|| format!("Invalid value for network.parallel: {} (expected a number > 0)", value))?;
+                self.network.parallel = Some(parallel);
+                Ok(())
+            }
+            | "network.offline" =>
+            {
+                let offline = value.parse::<bool>().map_err(|_| format!("Invalid value for network.offline: {} (expected true or false)", value))?;
+                self.network.offline = Some(offline);
+                Ok(())
+            }
+            | "network.proxy" =>
+            {
+                reqwest::Proxy::all(value).map_err(|e| format!("Invalid value for network.proxy: {} ({})", value, e))?;
+                self.network.proxy = Some(value.to_string());
+                Ok(())
+            }
+            | "network.no_proxy" =>
+            {
+                // Comma-separated hosts, domains, or IP ranges that bypass the proxy
+                let hosts: Vec<&str> = value.split(',').map(str::trim).filter(|h| h.is_empty() == false).collect();
+                self.network.no_proxy = Some(hosts.join(","));
+                Ok(())
+            }
+            | "network.ca_bundle" =>
+            {
+                // Store an absolute path so the setting works from any directory
+                let path = fs::canonicalize(value).map_err(|e| format!("Invalid value for network.ca_bundle: {} ({})", value, e))?;
+                download_manager::load_ca_bundle(&path)?;
+                self.network.ca_bundle = Some(path.to_string_lossy().to_string());
+                Ok(())
+            }
+            | _ if key.starts_with("vars.") == true =>
+            {
+                let name = &key["vars.".len()..];
+                template_variables::validate_variable_name(name)?;
+                self.vars.insert(name.to_string(), value.to_string());
+                Ok(())
+            }
             | _ => Err(format!("Unknown config key: {}", key).into())
         }
     }
@@ -139,6 +387,71 @@ pub fn unset(&mut self, key: &str) -> Result<()>
                 self.source.fallback = None;
                 Ok(())
             }
+            | "source.provider" =>
+            {
+                self.source.provider = None;
+                Ok(())
+            }
+            | "source.token_env" =>
+            {
+                self.source.token_env = None;
+                Ok(())
+            }
+            | "source.overlays" =>
+            {
+                self.source.overlays = None;
+                Ok(())
+            }
+            | "verify.enabled" =>
+            {
+                self.verify.enabled = None;
+                Ok(())
+            }
+            | "verify.trusted_keys" =>
+            {
+                self.verify.trusted_keys = None;
+                Ok(())
+            }
+            | "network.timeout" =>
+            {
+                self.network.timeout = None;
+                Ok(())
+            }
+            | "network.retries" =>
+            {
+                self.network.retries = None;
+                Ok(())
+            }
+            | "network.parallel" =>
+            {
+                self.network.parallel = None;
+                Ok(())
+            }
+            | "network.offline" =>
+            {
+                self.network.offline = None;
+                Ok(())
+            }
+            | "network.proxy" =>
+            {
+                self.network.proxy = None;
+                Ok(())
+            }
+            | "network.no_proxy" =>
+            {
+                self.network.no_proxy = None;
+                Ok(())
+            }
+            | "network.ca_bundle" =>
+            {
+                self.network.ca_bundle = None;
+                Ok(())
+            }
+            | _ if key.starts_with("vars.") == true =>
+            {
+                self.vars.remove(&key["vars.".len()..]);
+                Ok(())
+            }
             | _ => Err(format!("Unknown config key: {}", key).into())
         }
     }
@@ -160,12 +473,107 @@ pub fn list(&self) -> HashMap<String, String>
             values.insert("source.fallback".to_string(), fallback.clone());
         }
 
+        if let Some(provider) = &self.source.provider
+        {
+            values.insert("source.provider".to_string(), provider.clone());
+        }
+
+        if let Some(token_env) = &self.source.token_env
+        {
+            values.insert("source.token_env".to_string(), token_env.clone());
+        }
+
+        if let Some(overlays) = &self.source.overlays
+        {
+            values.insert("source.overlays".to_string(), overlays.join(","));
+        }
+
+        if let Some(enabled) = self.verify.enabled
+        {
+            values.insert("verify.enabled".to_string(), enabled.to_string());
+        }
+
+        if let Some(keys) = &self.verify.trusted_keys
+        {
+            values.insert("verify.trusted_keys".to_string(), keys.join(","));
+        }
+
+        if let Some(timeout) = self.network.timeout
+        {
+            values.insert("network.timeout".to_string(), timeout.to_string());
+        }
+
+        if let Some(retries) = self.network.retries
+        {
+            values.insert("network.retries".to_string(), retries.to_string());
+        }
+
+        if let Some(parallel) = self.network.parallel
+        {
+            values.insert("network.parallel".to_string(), parallel.to_string());
+        }
+
+        if let Some(offline) = self.network.offline
+        {
+            values.insert("network.offline".to_string(), offline.to_string());
+        }
+
+        if let Some(proxy) = &self.network.proxy
+        {
+            values.insert("network.proxy".to_string(), proxy.clone());
+        }
+
+        if let Some(no_proxy) = &self.network.no_proxy
+        {
+            values.insert("network.no_proxy".to_string(), no_proxy.clone());
+        }
+
+        if let Some(ca_bundle) = &self.network.ca_bundle
+        {
+            values.insert("network.ca_bundle".to_string(), ca_bundle.clone());
+        }
+
+        for (name, value) in &self.vars
+        {
+            values.insert(format!("vars.{}", name), value.clone());
+        }
+
         values
     }
 
     /// Get list of all valid config keys
     pub fn valid_keys() -> Vec<&'static str>
     {
-        vec!["source.url", "source.fallback"]
+        vec![
+            "source.url", "source.fallback", "source.provider", "source.token_env", "source.overlays", "verify.enabled", "verify.trusted_keys", "network.timeout",
+            "network.retries", "network.parallel", "network.offline", "network.proxy", "network.no_proxy", "network.ca_bundle", "vars.<name>",
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_git_ca_bundle_extends_system_store() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let system = temp_dir.path().join("system.pem");
+        let bundle = temp_dir.path().join("corporate.pem");
+        fs::write(&system, "-----BEGIN CERTIFICATE-----\nsystem\n-----END CERTIFICATE-----")?;
+        fs::write(&bundle, "-----BEGIN CERTIFICATE-----\ncorporate\n-----END CERTIFICATE-----\n")?;
+
+        let combined = combine_ca_bundles(&system, &bundle, &temp_dir.path().join("cache"))?;
+        assert_eq!(
+            fs::read_to_string(combined)?,
+            "-----BEGIN CERTIFICATE-----\nsystem\n-----END CERTIFICATE-----\n-----BEGIN CERTIFICATE-----\ncorporate\n-----END CERTIFICATE-----\n"
+        );
+        assert!(combine_ca_bundles(&temp_dir.path().join("missing.pem"), &bundle, &temp_dir.path().join("cache")).is_err());
+
+        Ok(())
     }
 }
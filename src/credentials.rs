@@ -0,0 +1,239 @@
+//! Access tokens for private template repositories
+//!
+//! Tokens are looked up, in order, from:
+//! 1. The environment variable named by the `source.token_env` config key
+//! 2. The provider's well-known environment variables (e.g. `GITHUB_TOKEN`, `GITLAB_TOKEN`)
+//! 3. A netrc file (`$NETRC` or `~/.netrc`), using the password of the matching `machine`
+//!
+//! Only the name of the variable or file a token came from is ever printed;
+//! the config file stores the variable name, never the token itself.
+
+use std::{
+    collections::HashMap,
+    env, fmt, fs,
+    path::{Path, PathBuf}
+};
+
+use crate::{Result, source_provider::SourceProvider};
+
+/// An access token and where it was found
+pub struct Credential
+{
+    /// Human-readable origin of the token (e.g. `$GITHUB_TOKEN` or `~/.netrc`)
+    pub origin: String,
+    token:      String
+}
+
+impl Credential
+{
+    /// Returns the token value (only to be placed in request headers)
+    pub fn token(&self) -> &str
+    {
+        &self.token
+    }
+}
+
+impl fmt::Debug for Credential
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        f.debug_struct("Credential").field("origin", &self.origin).field("token", &"<redacted>").finish()
+    }
+}
+
+/// Resolves the access token for a template source
+///
+/// # Arguments
+///
+/// * `provider` - Hosting provider of the source (selects the well-known variables)
+/// * `hosts` - Host names to look up in the netrc file
+/// * `token_env` - Value of the `source.token_env` config key
+///
+/// # Returns
+///
+/// The credential, or None if no token is available (anonymous access)
+///
+/// # Errors
+///
+/// Returns an error if `source.token_env` names a variable that is not set
+pub fn resolve_credential(provider: &dyn SourceProvider, hosts: &[String], token_env: Option<&str>) -> Result<Option<Credential>>
+{
+    resolve_with(provider, hosts, token_env, |name| env::var(name).ok(), netrc_path().as_deref())
+}
+
+/// Validates a `source.token_env` value
+///
+/// The value must be an environment variable name. Values that look like
+/// tokens are rejected so they are not stored in the config file in plaintext.
+///
+/// # Errors
+///
+/// Returns an error if the value is not a valid variable name or looks like a token
+pub fn validate_token_env(name: &str) -> Result<()>
+{
+    let looks_like_token = ["ghp_", "gho_", "ghs_", "github_pat_", "glpat-", "ATBB"].iter().any(|prefix| name.starts_with(prefix));
+
+    if looks_like_token == true
+    {
+        return Err("source.token_env expects the name of an environment variable, not the token itself".into());
+    }
+
+    if name.is_empty() == true || name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') == false || name.starts_with(|c: char| c.is_ascii_digit())
+    {
+        return Err(format!("Invalid environment variable name for source.token_env: {}", name).into());
+    }
+
+    Ok(())
+}
+
+/// Resolves a credential using the given environment lookup and netrc file
+fn resolve_with(
+    provider: &dyn SourceProvider, hosts: &[String], token_env: Option<&str>, env: impl Fn(&str) -> Option<String>, netrc: Option<&Path>
+) -> Result<Option<Credential>>
+{
+    let lookup = |name: &str| env(name).filter(|value| value.trim().is_empty() == false).map(|value| value.trim().to_string());
+
+    if let Some(name) = token_env
+    {
+        return match lookup(name)
+        {
+            | Some(token) => Ok(Some(Credential { origin: format!("${}", name), token })),
+            | None => Err(format!("source.token_env is set to {} but that environment variable is empty or not set", name).into())
+        };
+    }
+
+    for name in provider.token_env_vars()
+    {
+        if let Some(token) = lookup(name)
+        {
+            return Ok(Some(Credential { origin: format!("${}", name), token }));
+        }
+    }
+
+    if let Some(path) = netrc &&
+        let Ok(content) = fs::read_to_string(path)
+    {
+        let machines = parse_netrc(&content);
+        for host in hosts
+        {
+            // netrc machine names carry no port
+            let name = host.split(':').next().unwrap_or(host);
+            if let Some(token) = machines.get(name)
+            {
+                return Ok(Some(Credential { origin: path.display().to_string(), token: token.clone() }));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Returns the netrc file location (`$NETRC`, otherwise `~/.netrc` or `~/_netrc` on Windows)
+fn netrc_path() -> Option<PathBuf>
+{
+    if let Ok(path) = env::var("NETRC")
+    {
+        return Some(PathBuf::from(path));
+    }
+
+    let file_name = if cfg!(windows)
+    {
+        "_netrc"
+    }
+    else
+    {
+        ".netrc"
+    };
+    dirs::home_dir().map(|home| home.join(file_name))
+}
+
+/// Parses a netrc file into a map of machine name to password
+///
+/// `default` entries are ignored so tokens are never sent to arbitrary hosts.
+/// Parsing stops at the first `macdef`, since macro bodies are free-form text.
+fn parse_netrc(content: &str) -> HashMap<String, String>
+{
+    let mut machines = HashMap::new();
+    let mut current: Option<String> = None;
+    let mut tokens = content.lines().filter(|line| line.trim_start().starts_with('#') == false).flat_map(str::split_whitespace);
+
+    while let Some(token) = tokens.next()
+    {
+        match token
+        {
+            | "machine" => current = tokens.next().map(|name| name.to_lowercase()),
+            | "default" => current = None,
+            | "password" =>
+            {
+                if let (Some(machine), Some(password)) = (&current, tokens.next())
+                {
+                    machines.insert(machine.clone(), password.to_string());
+                }
+            }
+            | "login" | "account" =>
+            {
+                tokens.next();
+            }
+            | "macdef" => break,
+            | _ =>
+            {}
+        }
+    }
+
+    machines
+}
+
+#[cfg(test)]
+mod tests
+{
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::source_provider::ProviderKind;
+
+    #[test]
+    fn test_resolve_credential_order() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let netrc = temp_dir.path().join("netrc");
+        fs::write(&netrc, "# team credentials\nmachine gitlab.example.com login ci password netrc-token\ndefault login anonymous password everywhere\n")?;
+
+        let github = ProviderKind::GitHub.provider();
+        let gitlab = ProviderKind::GitLab.provider();
+        let hosts = vec!["gitlab.example.com:8443".to_string()];
+        let env = |name: &str| match name
+        {
+            | "GITHUB_TOKEN" => Some("env-token".to_string()),
+            | "TEAM_TOKEN" => Some("team-token".to_string()),
+            | _ => None
+        };
+
+        // Configured variable wins over the provider default
+        let credential = resolve_with(github, &hosts, Some("TEAM_TOKEN"), env, Some(&netrc))?.unwrap();
+        assert_eq!(credential.token(), "team-token");
+        assert_eq!(credential.origin, "$TEAM_TOKEN");
+
+        // Provider default variable
+        assert_eq!(resolve_with(github, &hosts, None, env, Some(&netrc))?.unwrap().token(), "env-token");
+
+        // netrc entry for the host (the `default` entry is never used)
+        assert_eq!(resolve_with(gitlab, &hosts, None, env, Some(&netrc))?.unwrap().token(), "netrc-token");
+        assert!(resolve_with(gitlab, &["other.example.com".to_string()], None, env, Some(&netrc))?.is_none());
+
+        // A configured variable that is not set is an error
+        assert!(resolve_with(gitlab, &hosts, Some("MISSING_TOKEN"), env, None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_tokens_are_not_exposed()
+    {
+        let credential = Credential { origin: "$GITHUB_TOKEN".to_string(), token: "ghp_secret".to_string() };
+        assert!(format!("{:?}", credential).contains("ghp_secret") == false);
+
+        assert!(validate_token_env("GITHUB_TOKEN").is_ok());
+        assert!(validate_token_env("ghp_1234567890abcdef").is_err());
+        assert!(validate_token_env("glpat-abcdef").is_err());
+        assert!(validate_token_env("").is_err());
+    }
+}
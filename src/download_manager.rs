@@ -1,24 +1,100 @@
 //! Download management functionality for vibe-check
 //!
-//! Handles downloading templates from GitHub repositories.
+//! Handles downloading templates from hosted git repositories (GitHub, GitLab,
+//! Gitea/Forgejo, Bitbucket) using raw file URLs. Files are downloaded by a
+//! bounded pool of worker threads; transient failures (connection errors,
+//! timeouts, HTTP 5xx/429) are retried with exponential backoff. When a cached
+//! store is given, conditional requests (ETag/Last-Modified) skip unchanged files.
+//! Access tokens for private repositories are sent only to the raw file host.
+//! Proxy and extra CA certificates come from the `network.*` config keys; without
+//! a configured proxy the `HTTPS_PROXY`/`HTTP_PROXY`/`NO_PROXY` variables apply.
 
 use std::{
-    fs,
+    env, fs,
     io::{self, Write},
-    path::{Path, PathBuf}
+    path::{Path, PathBuf},
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering}
+    },
+    thread,
+    time::{Duration, Instant}
 };
 
 use owo_colors::OwoColorize;
+use reqwest::{
+    Certificate, NoProxy, Proxy, StatusCode,
+    blocking::Client,
+    header::{ETAG, HeaderValue, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+    redirect::Policy
+};
 
-use crate::{Result, bom::TemplateConfig};
+use crate::{
+    Result,
+    bom::TemplateConfig,
+    bundle_signature::{MANIFEST_FILE_NAME, SIGNATURE_FILE_NAME},
+    config::{Config, NetworkConfig},
+    credentials, git_source,
+    http_cache::{self, CacheEntry, HttpCache},
+    source_provider::{ProviderKind, SourceLocation, SourceProvider, url_host}
+};
 
 /// Manages downloading templates from remote sources
 ///
 /// The `DownloadManager` handles all operations related to downloading
-/// templates from GitHub repositories.
+/// templates from hosted git repositories.
 pub struct DownloadManager
 {
-    config_dir: PathBuf
+    config_dir:     PathBuf,
+    config:         Config,
+    client:         Client,
+    /// Client for authenticated requests (only follows redirects on the same host)
+    auth_client:    Client,
+    backoff:        Duration,
+    retried:        AtomicUsize,
+    not_modified:   AtomicUsize,
+    cached_store:   Option<PathBuf>,
+    previous_cache: HttpCache,
+    updated_cache:  Mutex<HttpCache>
+}
+
+/// Base delay before the first retry (doubled for every further attempt)
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Upper bound for the delay between two attempts
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Authentication header for requests to a single host
+struct RequestAuth
+{
+    /// Host (including port) the header may be sent to
+    host:   String,
+    /// Name of the header (`Authorization`, or `PRIVATE-TOKEN` for GitLab)
+    name:   &'static str,
+    /// Value of the header
+    header: String
+}
+
+/// Result of a successful download attempt
+enum Fetched
+{
+    /// New content with the validators returned by the server
+    Content(Vec<u8>, CacheEntry),
+    /// Server confirmed the cached copy is current (HTTP 304)
+    NotModified
+}
+
+/// Failure of a single download attempt
+enum AttemptError
+{
+    /// Worth retrying (connection error, timeout, HTTP 5xx/429/408)
+    Transient(String),
+    /// Retrying will not help (HTTP 4xx, invalid URL)
+    Permanent(String),
+    /// The server could not be reached (DNS or connection failure); worth retrying
+    Unreachable(String),
+    /// The file does not exist on the server (HTTP 404)
+    NotFound(String)
 }
 
 impl DownloadManager
@@ -28,139 +104,212 @@ impl DownloadManager
     /// # Arguments
     ///
     /// * `config_dir` - Path to the global template storage directory
-    pub fn new(config_dir: PathBuf) -> Self
+    /// * `config` - User configuration (source provider selection, network settings)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if network access is disabled (`network.offline`), the
+    /// proxy URL or CA bundle is invalid, or the HTTP client cannot be created
+    pub fn new(config_dir: PathBuf, config: Config) -> Result<Self>
     {
-        Self { config_dir }
+        if config.network.offline() == true
+        {
+            return Err("Network access is disabled (network.offline is set)".into());
+        }
+
+        let client = build_client(&config.network, Policy::default())?;
+        let auth_client = build_client(&config.network, same_host_redirects())?;
+
+        Ok(Self {
+            config_dir,
+            config,
+            client,
+            auth_client,
+            backoff: RETRY_BACKOFF,
+            retried: AtomicUsize::new(0),
+            not_modified: AtomicUsize::new(0),
+            cached_store: None,
+            previous_cache: HttpCache::default(),
+            updated_cache: Mutex::new(HttpCache::default())
+        })
+    }
+
+    /// Enables conditional requests against an existing template store
+    ///
+    /// Files below `config_dir` that the server reports as unchanged (HTTP 304)
+    /// are copied from the cached store instead of being transferred again.
+    /// The validators of the new download are saved into `config_dir`.
+    ///
+    /// # Arguments
+    ///
+    /// * `store` - Existing template store holding `http_cache.json` and the previous files
+    pub fn with_cache(mut self, store: &Path) -> Self
+    {
+        self.previous_cache = HttpCache::load(store);
+        self.cached_store = Some(store.to_path_buf());
+        self
     }
 
-    /// Downloads templates from a GitHub URL
+    /// Downloads templates from a repository browse URL
     ///
-    /// Downloads template files from a GitHub repository based on templates.yml configuration.
+    /// Downloads template files from a hosted repository based on templates.yml configuration.
+    /// The hosting provider is taken from the `source.provider` config key, or detected
+    /// from the URL when it is unset or `auto`.
     ///
     /// # Arguments
     ///
-    /// * `url` - GitHub URL to download from
+    /// * `url` - Browse URL of the template directory
+    ///
+    /// A branch or tag is first resolved to its current commit (with `git ls-remote`),
+    /// and all files are downloaded from that commit, so the returned revision
+    /// identifies exactly the downloaded content. If the ref cannot be resolved
+    /// (e.g. git cannot access the repository), the files are downloaded from the ref.
+    ///
+    /// # Returns
+    ///
+    /// The commit the templates were downloaded from, or the branch or tag if it could not be resolved
     ///
     /// # Errors
     ///
     /// Returns an error if URL parsing or download fails
-    pub fn download_templates_from_url(&self, url: &str) -> Result<()>
+    pub fn download_templates_from_url(&self, url: &str) -> Result<String>
     {
-        let (owner, repo, branch, path) = self.parse_github_url(url).ok_or("Invalid GitHub URL format. Expected: https://github.com/owner/repo/tree/branch/path")?;
+        let kind = self.resolve_provider(url)?;
+        let provider = kind.provider();
+        let mut location = provider.parse_url(url).ok_or_else(|| format!("Invalid {} URL format. Expected: {}", kind, provider.url_format()))?;
 
-        println!("{} Repository: {}/{} (branch: {})", "→".blue(), owner.green(), repo.green(), branch.yellow());
+        println!("{} Repository: {}/{} (ref: {}, provider: {})", "→".blue(), location.owner.green(), location.repo.green(), location.refname.yellow(), kind);
 
-        // Build base raw URL
-        let base_url = format!("https://raw.githubusercontent.com/{}/{}/{}", owner, repo, branch);
-        let url_path = if path.is_empty() == false
+        let refname = provider.ref_name(&location.refname).to_string();
+        if git_source::is_commit_sha(&refname) == false
         {
-            format!("/{}", path)
+            match git_source::resolve_remote_ref(&provider.clone_url(&location), &refname, &self.config.network)
+            {
+                | Ok(commit) =>
+                {
+                    println!("{} Resolved {} to commit {}", "→".blue(), refname.yellow(), commit.green());
+                    location.refname = provider.commit_refname(&commit);
+                }
+                | Err(e) => println!("{} Warning: {}; downloading from {} (templates.lock will record the ref, not a commit)", "!".yellow(), e, refname.yellow())
+            }
         }
-        else
-        {
-            String::new()
-        };
 
         fs::create_dir_all(&self.config_dir)?;
 
+        // Tokens are only sent to the host serving the raw files
+        let raw_host = url_host(&provider.raw_file_url(&location, "templates.yml")).unwrap_or_default();
+        let hosts: Vec<String> = url_host(&location.base).into_iter().chain([raw_host.clone()]).collect();
+        let auth = credentials::resolve_credential(provider, &hosts, self.config.source.token_env.as_deref())?.map(|credential| {
+            println!("{} Authenticating with token from {}", "→".blue(), credential.origin.yellow());
+            let (name, header) = provider.auth_header(credential.token());
+            RequestAuth { host: raw_host, name, header }
+        });
+
         // Load template configuration
-        let config = self.load_template_config(&base_url, &url_path)?;
+        let config = self.load_template_config(provider, &location, auth.as_ref())?;
 
-        // Helper closure to download a file entry
-        let download_entry = |source: &str| -> Result<()> {
-            let file_url = format!("{}{}/{}", base_url, url_path, source);
-            let dest_path = self.config_dir.join(source);
+        // Download every source file referenced by templates.yml (main, fragments, agent files).
+        // Templates that extend a parent may leave files out; those are inherited on update.
+        let sources = config.source_files();
+        let workers = self.config.network.parallel().min(sources.len()).max(1);
+        let next = AtomicUsize::new(0);
+        let failed = Mutex::new(Vec::new());
+        let inherited = AtomicUsize::new(0);
+        let started = Instant::now();
 
-            print!("{} Downloading {}... ", "→".blue(), source.yellow());
-            io::stdout().flush()?;
+        println!("{} Downloading {} file(s) ({} parallel)...", "→".blue(), sources.len(), workers);
 
-            match self.download_file(&file_url, &dest_path)
+        thread::scope(|scope| {
+            for _ in 0..workers
             {
-                | Ok(_) => println!("{}", "✓".green()),
-                | Err(_) => println!("{} (skipped)", "✗".red())
+                scope.spawn(|| {
+                    while let Some(source) = sources.get(next.fetch_add(1, Ordering::SeqCst))
+                    {
+                        match self.download_file_as(
+                            &provider.raw_file_url(&location, source),
+                            &http_cache::repository_key(&location, source),
+                            &self.config_dir.join(source),
+                            auth.as_ref()
+                        )
+                        {
+                            | Ok(()) => println!("  {} {}", "✓".green(), source),
+                            | Err(e) if config.extends.is_some() && e.downcast_ref::<io::Error>().is_some_and(|e| e.kind() == io::ErrorKind::NotFound) =>
+                            {
+                                println!("  {} {} (inherited from parent)", "○".blue(), source);
+                                inherited.fetch_add(1, Ordering::SeqCst);
+                            }
+                            | Err(e) =>
+                            {
+                                println!("  {} {} ({})", "✗".red(), source, e);
+                                failed.lock().unwrap_or_else(|e| e.into_inner()).push(source.to_string());
+                            }
+                        }
+                    }
+                });
             }
-            Ok(())
-        };
-
-        // Download main AGENTS.md template if present
-        if let Some(main) = &config.main
-        {
-            download_entry(&main.source)?;
-        }
+        });
 
-        // Download principles templates if present
-        if let Some(principles_entries) = &config.principles
+        // Signature files are optional; they are only required when verification is enabled
+        for file in [MANIFEST_FILE_NAME, SIGNATURE_FILE_NAME]
         {
-            for entry in principles_entries
+            if self
+                .download_file_as(&provider.raw_file_url(&location, file), &http_cache::repository_key(&location, file), &self.config_dir.join(file), auth.as_ref())
+                .is_ok()
             {
-                download_entry(&entry.source)?;
+                println!("{} Downloaded {}", "✓".green(), file.yellow());
             }
         }
 
-        // Download mission templates if present
-        if let Some(mission_entries) = &config.mission
+        if self.cached_store.is_some()
         {
-            for entry in mission_entries
-            {
-                download_entry(&entry.source)?;
-            }
+            self.updated_cache.lock().unwrap_or_else(|e| e.into_inner()).save(&self.config_dir)?;
         }
 
-        // Download language templates
-        for lang_config in config.languages.values()
+        // Progress summary
+        let failed = failed.into_inner().unwrap_or_else(|e| e.into_inner());
+        let inherited = inherited.into_inner();
+        let mut summary = format!("{} of {} file(s) downloaded in {:.1}s", sources.len() - failed.len() - inherited, sources.len(), started.elapsed().as_secs_f64());
+
+        if inherited > 0
         {
-            for file_entry in &lang_config.files
-            {
-                download_entry(&file_entry.source)?;
-            }
+            summary.push_str(&format!(", {} inherited", inherited));
         }
 
-        // Download integration templates
-        if let Some(integration_map) = &config.integration
+        let not_modified = self.not_modified.load(Ordering::SeqCst);
+        if not_modified > 0
         {
-            for integration_config in integration_map.values()
-            {
-                for file_entry in &integration_config.files
-                {
-                    download_entry(&file_entry.source)?;
-                }
-            }
+            summary.push_str(&format!(", {} unchanged", not_modified));
         }
 
-        // Download agent templates (if agents section exists)
-        if let Some(agents) = &config.agents
+        let retried = self.retried.load(Ordering::SeqCst);
+        if retried > 0
         {
-            for agent_config in agents.values()
-            {
-                if let Some(instructions) = &agent_config.instructions
-                {
-                    for instruction in instructions
-                    {
-                        download_entry(&instruction.source)?;
-                    }
-                }
-
-                if let Some(prompts) = &agent_config.prompts
-                {
-                    for prompt in prompts
-                    {
-                        download_entry(&prompt.source)?;
-                    }
-                }
+            summary.push_str(&format!(", {} retried", retried));
+        }
 
-                if let Some(skills) = &agent_config.skills
-                {
-                    for skill in skills
-                    {
-                        download_entry(&skill.source)?;
-                    }
-                }
-            }
+        if failed.is_empty() == true
+        {
+            println!("{} {}", "✓".green(), summary);
+        }
+        else
+        {
+            println!("{} {}, {} failed: {}", "!".yellow(), summary, failed.len(), failed.join(", "));
         }
 
-        println!("{} Templates downloaded successfully", "✓".green());
+        Ok(provider.ref_name(&location.refname).to_string())
+    }
 
-        Ok(())
+    /// Determines the source provider for a URL
+    ///
+    /// Uses the `source.provider` config key if set to a specific provider,
+    /// otherwise detects the provider from the URL.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configured provider is invalid or detection fails
+    fn resolve_provider(&self, url: &str) -> Result<ProviderKind>
+    {
+        ProviderKind::resolve(url, self.config.source.provider.as_deref())
     }
 
     /// Loads template configuration from templates.yml
@@ -169,23 +318,39 @@ pub fn download_templates_from_url(&self, url: &str) -> Result<()>
     ///
     /// # Arguments
     ///
-    /// * `base_url` - Base URL for downloading templates.yml from GitHub
-    /// * `url_path` - Path within the repository
+    /// * `provider` - Source provider used to build the raw URL
+    /// * `location` - Parsed location of the template directory
+    /// * `auth` - Authorization for private repositories (None for anonymous access)
     ///
     /// # Errors
     ///
     /// Returns an error if templates.yml cannot be loaded or parsed
-    fn load_template_config(&self, base_url: &str, url_path: &str) -> Result<TemplateConfig>
+    fn load_template_config(&self, provider: &dyn SourceProvider, location: &SourceLocation, auth: Option<&RequestAuth>) -> Result<TemplateConfig>
     {
         let config_path = self.config_dir.join("templates.yml");
-        let config_url = format!("{}{}/templates.yml", base_url, url_path);
+        let config_url = provider.raw_file_url(location, "templates.yml");
 
         print!("{} Downloading templates.yml... ", "→".blue());
         io::stdout().flush()?;
 
-        match self.download_file(&config_url, &config_path)
+        match self.download_file_as(&config_url, &http_cache::repository_key(location, "templates.yml"), &config_path, auth)
         {
             | Ok(_) => println!("{}", "✓".green()),
+            | Err(e) if is_network_unavailable(e.as_ref()) == true =>
+            {
+                println!("{}", "✗".red());
+                return Err(e);
+            }
+            | Err(e) if auth.is_none() =>
+            {
+                println!("{}", "✗".red());
+                return Err(format!(
+                    "Failed to download templates.yml: {} (for a private repository, provide a token via {}, source.token_env, or ~/.netrc)",
+                    e,
+                    provider.token_env_vars().join("/")
+                )
+                .into());
+            }
             | Err(e) =>
             {
                 println!("{}", "✗".red());
@@ -194,92 +359,548 @@ fn load_template_config(&self, base_url: &str, url_path: &str) -> Result<Templat
         }
 
         let content = fs::read_to_string(&config_path)?;
-        let config: TemplateConfig = serde_yaml::from_str(&content)?;
+        let config = TemplateConfig::parse(&content, "templates.yml")?;
+
+        // Validate before any file is written based on the (untrusted) remote paths
+        config.validate_paths()?;
         Ok(config)
     }
 
-    /// Converts a GitHub tree URL to raw content URLs
+    /// Downloads a file from a URL
     ///
-    /// Converts URLs like:
-    /// `https://github.com/owner/repo/tree/branch/path`
-    /// to:
-    /// `https://raw.githubusercontent.com/owner/repo/branch/path`
+    /// Transient failures are retried up to `network.retries` times, waiting
+    /// twice as long before every further attempt. With a cached store, a
+    /// conditional request is sent and an unchanged file is copied from the store.
     ///
     /// # Arguments
     ///
-    /// * `url` - GitHub tree URL
+    /// * `url` - URL to download from
+    /// * `dest_path` - Destination file path
     ///
-    /// # Returns
+    /// # Errors
+    ///
+    /// Returns an error if download or file write fails
+    pub(crate) fn download_file(&self, url: &str, dest_path: &Path) -> Result<()>
+    {
+        self.download_file_with_auth(url, dest_path, None)
+    }
+
+    /// Downloads a file from a URL, authorizing the request if it targets the authorized host
+    fn download_file_with_auth(&self, url: &str, dest_path: &Path, auth: Option<&RequestAuth>) -> Result<()>
+    {
+        self.download_file_as(url, url, dest_path, auth)
+    }
+
+    /// Downloads a file, keeping its validators under a cache key instead of its URL
     ///
-    /// Returns base raw URL and path components, or None if URL is not a GitHub tree URL
-    fn parse_github_url(&self, url: &str) -> Option<(String, String, String, String)>
+    /// Files of hosted repositories use a key without the ref (see
+    /// `http_cache::repository_key`), so a file that did not change when the
+    /// branch moved to a new commit is still not transferred again.
+    fn download_file_as(&self, url: &str, cache_key: &str, dest_path: &Path, auth: Option<&RequestAuth>) -> Result<()>
     {
-        // Parse URLs like: https://github.com/owner/repo/tree/branch/path
-        if url.contains("github.com") == false
+        let retries = self.config.network.retries();
+        let mut attempt = 0;
+
+        // Only send validators if the cached copy of this file is still available
+        let cached_file = self.cached_store.as_ref().zip(dest_path.strip_prefix(&self.config_dir).ok()).map(|(store, relative)| store.join(relative));
+        let validators = cached_file.as_ref().filter(|f| f.is_file()).and_then(|_| self.previous_cache.validators(cache_key, url));
+
+        let fetched = loop
         {
-            return None;
-        }
+            match self.fetch_once(url, validators.as_ref(), auth)
+            {
+                | Ok(fetched) => break fetched,
+                | Err(AttemptError::Transient(_)) | Err(AttemptError::Unreachable(_)) if attempt < retries =>
+                {
+                    thread::sleep(self.backoff.saturating_mul(2u32.saturating_pow(attempt)).min(MAX_RETRY_BACKOFF));
+                    attempt += 1;
+                    self.retried.fetch_add(1, Ordering::SeqCst);
+                }
+                | Err(AttemptError::Transient(message)) | Err(AttemptError::Permanent(message)) => return Err(message.into()),
+                | Err(AttemptError::NotFound(message)) => return Err(io::Error::new(io::ErrorKind::NotFound, message).into()),
+                | Err(AttemptError::Unreachable(message)) => return Err(io::Error::new(io::ErrorKind::NetworkUnreachable, message).into())
+            }
+        };
 
-        let parts: Vec<&str> = url.split('/').collect();
+        if let Some(parent) = dest_path.parent()
+        {
+            fs::create_dir_all(parent)?;
+        }
 
-        // Find the indices for owner, repo, tree, branch
-        let github_idx = parts.iter().position(|&p| p == "github.com")?;
+        let entry = match fetched
+        {
+            | Fetched::Content(content, entry) =>
+            {
+                fs::write(dest_path, content)?;
+                entry
+            }
+            | Fetched::NotModified =>
+            {
+                // Validators (and therefore 304 responses) only occur when the cached file exists
+                fs::copy(cached_file.as_ref().ok_or("Missing cached file")?, dest_path)?;
+                self.not_modified.fetch_add(1, Ordering::SeqCst);
+                validators.unwrap_or_default()
+            }
+        };
 
-        if parts.len() < github_idx + 5
+        if self.cached_store.is_some()
         {
-            return None;
+            self.updated_cache.lock().unwrap_or_else(|e| e.into_inner()).insert(cache_key, url, entry);
         }
 
-        let owner = parts[github_idx + 1];
-        let repo = parts[github_idx + 2];
-        let tree_or_blob = parts[github_idx + 3];
+        Ok(())
+    }
 
-        if tree_or_blob != "tree" && tree_or_blob != "blob"
+    /// Performs a single download attempt and classifies failures
+    ///
+    /// Sends `If-None-Match`/`If-Modified-Since` when validators are given, and the
+    /// authentication header when the URL's host matches the authorized host.
+    fn fetch_once(&self, url: &str, validators: Option<&CacheEntry>, auth: Option<&RequestAuth>) -> std::result::Result<Fetched, AttemptError>
+    {
+        let mut request = self.client.get(url);
+        if let Some(auth) = auth.filter(|a| url_host(url).as_deref() == Some(a.host.as_str()))
         {
-            return None;
+            let mut value = HeaderValue::from_str(&auth.header).map_err(|_| AttemptError::Permanent("Access token contains invalid characters".to_string()))?;
+            // Keeps the token out of debug output
+            value.set_sensitive(true);
+            // reqwest only strips `Authorization` on cross-host redirects, so authenticated
+            // requests use a client that does not follow redirects to other hosts
+            request = self.auth_client.get(url).header(auth.name, value);
         }
+        if let Some(entry) = validators
+        {
+            if let Some(etag) = &entry.etag
+            {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &entry.last_modified
+            {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let response = request.send().map_err(|e| {
+            if e.is_builder() == true
+            {
+                AttemptError::Permanent(format!("Invalid URL {}: {}", url, e))
+            }
+            else if e.is_connect() == true
+            {
+                AttemptError::Unreachable(format!("Failed to connect to {}: {}", url, e))
+            }
+            else
+            {
+                AttemptError::Transient(format!("Failed to download {}: {}", url, e))
+            }
+        })?;
 
-        let branch = parts[github_idx + 4];
-        let path = if parts.len() > github_idx + 5
+        let status = response.status();
+        if status == StatusCode::NOT_MODIFIED && validators.is_some()
         {
-            parts[github_idx + 5..].join("/")
+            return Ok(Fetched::NotModified);
         }
-        else
+
+        if status.is_success() == false
         {
-            String::new()
+            let message = format!("Failed to download {}: HTTP {}", url, status);
+            return Err(
+                if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::REQUEST_TIMEOUT
+                {
+                    AttemptError::Transient(message)
+                }
+                else if status == StatusCode::NOT_FOUND
+                {
+                    AttemptError::NotFound(message)
+                }
+                else
+                {
+                    AttemptError::Permanent(message)
+                }
+            );
+        }
+
+        let headers = response.headers();
+        let entry = CacheEntry {
+            url:           None,
+            etag:          headers.get(ETAG).and_then(|v| v.to_str().ok()).map(str::to_string),
+            last_modified: headers.get(LAST_MODIFIED).and_then(|v| v.to_str().ok()).map(str::to_string)
         };
 
-        Some((owner.to_string(), repo.to_string(), branch.to_string(), path))
+        response.bytes().map(|b| Fetched::Content(b.to_vec(), entry)).map_err(|e| AttemptError::Transient(format!("Failed to download {}: {}", url, e)))
     }
+}
 
-    /// Downloads a file from a URL
-    ///
-    /// # Arguments
-    ///
-    /// * `url` - URL to download from
-    /// * `dest_path` - Destination file path
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if download or file write fails
-    fn download_file(&self, url: &str, dest_path: &Path) -> Result<()>
-    {
-        let response = reqwest::blocking::get(url)?;
+/// Returns true if an error means the template host could not be reached at all
+///
+/// Only DNS and connection failures count; HTTP errors, invalid URLs, and
+/// verification failures mean the network is available.
+pub fn is_network_unavailable(error: &(dyn std::error::Error + 'static)) -> bool
+{
+    error.downcast_ref::<io::Error>().is_some_and(|e| e.kind() == io::ErrorKind::NetworkUnreachable)
+}
 
-        if response.status().is_success() == false
+/// Redirect policy that only follows redirects to the host of the original request
+fn same_host_redirects() -> Policy
+{
+    Policy::custom(|attempt| {
+        let same_host = attempt.previous().first().is_some_and(|first| first.host_str() == attempt.url().host_str() && first.port() == attempt.url().port());
+        if same_host == false || attempt.previous().len() > 10
+        {
+            attempt.stop()
+        }
+        else
         {
-            return Err(format!("Failed to download {}: HTTP {}", url, response.status()).into());
+            attempt.follow()
         }
+    })
+}
+
+/// Creates the HTTP client for the network configuration
+///
+/// A configured `network.proxy` is used for all requests. If only `network.no_proxy`
+/// is configured, the proxy is taken from the environment. Without either key the
+/// client reads `HTTPS_PROXY`, `HTTP_PROXY`, `ALL_PROXY` and `NO_PROXY` itself.
+fn build_client(network: &NetworkConfig, redirect: Policy) -> Result<Client>
+{
+    let timeout = Duration::from_secs(network.timeout());
+    let mut builder = Client::builder().timeout(timeout).connect_timeout(timeout).redirect(redirect).user_agent(concat!("vibe-check/", env!("CARGO_PKG_VERSION")));
 
-        let content = response.bytes()?;
+    let proxy_url = network
+        .proxy
+        .clone()
+        .or_else(|| network.no_proxy.as_ref().and_then(|_| env_var(&["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy", "HTTP_PROXY", "http_proxy"])));
 
-        if let Some(parent) = dest_path.parent()
+    if let Some(url) = proxy_url
+    {
+        let no_proxy = network.no_proxy.clone().or_else(|| env_var(&["NO_PROXY", "no_proxy"]));
+        let proxy = Proxy::all(&url).map_err(|e| format!("Invalid proxy URL {}: {}", url, e))?;
+        builder = builder.proxy(proxy.no_proxy(no_proxy.as_deref().and_then(NoProxy::from_string)));
+    }
+
+    if let Some(ca_bundle) = &network.ca_bundle
+    {
+        for certificate in load_ca_bundle(Path::new(ca_bundle))?
         {
-            fs::create_dir_all(parent)?;
+            builder = builder.add_root_certificate(certificate);
         }
+    }
+
+    Ok(builder.build()?)
+}
+
+/// Loads the certificates of a PEM bundle (`network.ca_bundle`)
+///
+/// The certificates are trusted in addition to the system CA store.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read or contains no valid certificate
+pub(crate) fn load_ca_bundle(path: &Path) -> Result<Vec<Certificate>>
+{
+    let pem = fs::read(path).map_err(|e| format!("Failed to read CA bundle {}: {}", path.display(), e))?;
+    let certificates = Certificate::from_pem_bundle(&pem).map_err(|e| format!("Invalid CA bundle {}: {}", path.display(), e))?;
+
+    if certificates.is_empty() == true
+    {
+        return Err(format!("CA bundle {} contains no PEM certificates", path.display()).into());
+    }
+
+    Ok(certificates)
+}
+
+/// Returns the first non-empty environment variable of the given names
+fn env_var(names: &[&str]) -> Option<String>
+{
+    names.iter().filter_map(|name| env::var(name).ok()).find(|value| value.trim().is_empty() == false)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::{net::TcpListener, sync::Arc};
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::test_support::{TestResponse, TestServer};
+
+    #[test]
+    fn test_download_from_gitlab_stand_in() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let served = temp_dir.path().join("served/team/repo/-/raw/main/templates");
+        fs::create_dir_all(served.join("docs"))?;
+        fs::write(
+            served.join("templates.yml"),
+            "version: 2\nmain:\n  source: AGENTS.md\n  target: '$workspace/AGENTS.md'\nlanguages:\n  rust:\n    files:\n      - source: docs/rust.md\n        \
+             target: '$instructions'\n"
+        )?;
+        fs::write(served.join("AGENTS.md"), "# Agents")?;
+        fs::write(served.join("docs/rust.md"), "## Rust")?;
+
+        let server = TestServer::serve_dir(temp_dir.path().join("served"));
+        let store = temp_dir.path().join("store");
+        let manager = DownloadManager::new(store.clone(), Config::default())?;
+
+        let refname = manager.download_templates_from_url(&format!("{}/team/repo/-/tree/main/templates", server.url()))?;
+        assert_eq!(refname, "main");
+
+        assert_eq!(fs::read_to_string(store.join("AGENTS.md"))?, "# Agents");
+        assert_eq!(fs::read_to_string(store.join("docs/rust.md"))?, "## Rust");
+        Ok(())
+    }
+
+    #[test]
+    fn test_download_at_commit() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let commit = "0123456789abcdef0123456789abcdef01234567";
+        let served = temp_dir.path().join(format!("served/owner/repo/raw/commit/{}", commit));
+        fs::create_dir_all(&served)?;
+        fs::write(served.join("templates.yml"), "version: 2\nmain:\n  source: AGENTS.md\n  target: '$workspace/AGENTS.md'\nlanguages: {}\n")?;
+        fs::write(served.join("AGENTS.md"), "# Agents")?;
+
+        let server = TestServer::serve_dir(temp_dir.path().join("served"));
+        let mut config = Config::default();
+        config.set("source.provider", "gitea")?;
+        let store = temp_dir.path().join("store");
+
+        // Commits are used as they are, without resolving them against the repository
+        let revision = DownloadManager::new(store.clone(), config)?.download_templates_from_url(&format!("{}/owner/repo/src/commit/{}", server.url(), commit))?;
+        assert_eq!(revision, commit);
+        assert_eq!(fs::read_to_string(store.join("AGENTS.md"))?, "# Agents");
+        Ok(())
+    }
+
+    #[test]
+    fn test_configured_provider_overrides_detection() -> Result<()>
+    {
+        let mut config = Config::default();
+        config.set("source.provider", "gitea")?;
+        let manager = DownloadManager::new(PathBuf::from("unused"), config)?;
+
+        // Looks like a GitHub URL, but the configured provider wins and rejects it
+        let result = manager.download_templates_from_url("https://git.example.com/owner/repo/tree/main");
+        assert!(result.is_err());
+        assert_eq!(manager.resolve_provider("https://git.example.com/owner/repo/tree/main")?, ProviderKind::Gitea);
+        Ok(())
+    }
+
+    #[test]
+    fn test_download_file_retries_transient_failures() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let requests = Arc::new(AtomicUsize::new(0));
+
+        // Fails twice with 503, then succeeds; /missing always returns 404
+        let counter = requests.clone();
+        let server = TestServer::start(move |request| {
+            if request.path == "/missing"
+            {
+                counter.fetch_add(1, Ordering::SeqCst);
+                return TestResponse::new(404, "not found");
+            }
+            match counter.fetch_add(1, Ordering::SeqCst)
+            {
+                | 0 | 1 => TestResponse::new(503, "unavailable"),
+                | _ => TestResponse::new(200, "content")
+            }
+        });
+
+        let mut manager = DownloadManager::new(temp_dir.path().to_path_buf(), Config::default())?;
+        manager.backoff = Duration::from_millis(1);
+
+        manager.download_file(&format!("{}/file.md", server.url()), &temp_dir.path().join("file.md"))?;
+        assert_eq!(fs::read_to_string(temp_dir.path().join("file.md"))?, "content");
+        assert_eq!(manager.retried.load(Ordering::SeqCst), 2);
+
+        // Client errors are not retried
+        requests.store(0, Ordering::SeqCst);
+        assert!(manager.download_file(&format!("{}/missing", server.url()), &temp_dir.path().join("missing")).is_err());
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        // HTTP errors are not network failures, unreachable hosts are
+        let error = manager.download_file(&format!("{}/missing", server.url()), &temp_dir.path().join("missing")).unwrap_err();
+        assert!(is_network_unavailable(error.as_ref()) == false);
+        let closed = TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+        let error = manager.download_file(&format!("http://{}/file.md", closed), &temp_dir.path().join("closed")).unwrap_err();
+        assert!(is_network_unavailable(error.as_ref()) == true, "{}", error);
+
+        // Retries are bounded by network.retries
+        let mut config = Config::default();
+        config.set("network.retries", "1")?;
+        let mut manager = DownloadManager::new(temp_dir.path().to_path_buf(), config)?;
+        manager.backoff = Duration::from_millis(1);
+        requests.store(0, Ordering::SeqCst);
+        assert!(manager.download_file(&format!("{}/file.md", server.url()), &temp_dir.path().join("file.md")).is_err());
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_proxy_and_ca_bundle_settings() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+
+        // The proxy receives the absolute URL of the requested file
+        let proxy = TestServer::start(|request| TestResponse::new(200, format!("proxied {}", request.path)));
+        let mut config = Config::default();
+        config.set("network.proxy", &proxy.url())?;
+        config.set("network.retries", "0")?;
+
+        let manager = DownloadManager::new(temp_dir.path().to_path_buf(), config.clone())?;
+        manager.download_file("http://templates.invalid/AGENTS.md", &temp_dir.path().join("AGENTS.md"))?;
+        assert_eq!(fs::read_to_string(temp_dir.path().join("AGENTS.md"))?, "proxied http://templates.invalid/AGENTS.md");
+
+        // Hosts on the no-proxy list are contacted directly (and do not resolve here)
+        config.set("network.no_proxy", "example.com, templates.invalid")?;
+        let manager = DownloadManager::new(temp_dir.path().to_path_buf(), config.clone())?;
+        assert!(manager.download_file("http://templates.invalid/AGENTS.md", &temp_dir.path().join("direct.md")).is_err());
+
+        // CA bundles must contain PEM certificates
+        let bundle = temp_dir.path().join("ca.pem");
+        fs::write(&bundle, "not a certificate")?;
+        assert!(config.set("network.ca_bundle", &bundle.to_string_lossy()).is_err());
+        assert!(config.set("network.ca_bundle", &temp_dir.path().join("missing.pem").to_string_lossy()).is_err());
+
+        config.network.ca_bundle = Some(bundle.to_string_lossy().to_string());
+        assert!(DownloadManager::new(temp_dir.path().to_path_buf(), config).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_conditional_requests_skip_unchanged_files() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path().join("served");
+        let first = "1111111111111111111111111111111111111111";
+        let second = "2222222222222222222222222222222222222222";
+
+        // `main` resolves through the dumb HTTP protocol (info/refs), raw files are served per commit
+        let publish = |commit: &str, rust: &str| -> Result<()> {
+            let served = root.join("team/repo/-/raw").join(commit);
+            fs::create_dir_all(&served)?;
+            fs::write(
+                served.join("templates.yml"),
+                "version: 2\nmain:\n  source: AGENTS.md\n  target: '$workspace/AGENTS.md'\nlanguages:\n  rust:\n    files:\n      - source: rust.md\n        target: \
+                 '$instructions'\n"
+            )?;
+            fs::write(served.join("AGENTS.md"), "# Agents")?;
+            fs::write(served.join("rust.md"), rust)?;
+            fs::create_dir_all(root.join("team/repo.git/info"))?;
+            fs::write(root.join("team/repo.git/info/refs"), format!("{}\trefs/heads/main\n", commit))?;
+            Ok(())
+        };
+        publish(first, "## Rust")?;
+
+        // Serves files with a content-based ETag and answers matching If-None-Match with 304
+        let transferred = Arc::new(Mutex::new(Vec::new()));
+        let log = transferred.clone();
+        let served_root = root.clone();
+        let server = TestServer::start(move |request| {
+            let Ok(content) = fs::read(served_root.join(request.path.split('?').next().unwrap_or("").trim_start_matches('/')))
+            else
+            {
+                return TestResponse::new(404, "not found");
+            };
+            let etag = format!("\"{}\"", content.len());
+            if request.headers.get("if-none-match") == Some(&etag)
+            {
+                return TestResponse::new(304, "");
+            }
+            log.lock().unwrap().push(request.path.clone());
+            TestResponse::new(200, content).with_header("ETag", &etag)
+        });
+        let url = format!("{}/team/repo/-/tree/main", server.url());
+        let raw_files = |log: &[String]| log.iter().filter(|path| path.contains("/-/raw/")).cloned().collect::<Vec<_>>();
+
+        // First update transfers everything and records the validators
+        let live = temp_dir.path().join("live");
+        let revision = DownloadManager::new(live.clone(), Config::default())?.with_cache(&temp_dir.path().join("missing")).download_templates_from_url(&url)?;
+        assert_eq!(revision, first);
+        assert_eq!(raw_files(&transferred.lock().unwrap()).len(), 3);
+
+        // The branch moves on: only the changed file is transferred, the rest is copied from the store
+        transferred.lock().unwrap().clear();
+        publish(second, "## Rust 2024")?;
+        let staging = temp_dir.path().join("staging");
+        let manager = DownloadManager::new(staging.clone(), Config::default())?.with_cache(&live);
+        assert_eq!(manager.download_templates_from_url(&url)?, second);
+
+        assert_eq!(raw_files(&transferred.lock().unwrap()), vec![format!("/team/repo/-/raw/{}/rust.md", second)]);
+        assert_eq!(manager.not_modified.load(Ordering::SeqCst), 2);
+        assert_eq!(fs::read_to_string(staging.join("AGENTS.md"))?, "# Agents");
+        assert_eq!(fs::read_to_string(staging.join("rust.md"))?, "## Rust 2024");
+        Ok(())
+    }
+
+    #[test]
+    fn test_token_is_only_sent_to_authorized_host() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+
+        // Private repository stand-in: 404 unless the expected token is sent
+        let server = TestServer::start(|request| match request.headers.get("authorization").map(String::as_str)
+        {
+            | Some("Bearer secret") => TestResponse::new(200, "private"),
+            | _ => TestResponse::new(404, "not found")
+        });
+        let manager = DownloadManager::new(temp_dir.path().to_path_buf(), Config::default())?;
+        let url = format!("{}/file.md", server.url());
+        let dest = temp_dir.path().join("file.md");
+
+        assert!(manager.download_file(&url, &dest).is_err());
+
+        let auth = RequestAuth { host: url_host(&server.url()).unwrap(), name: "Authorization", header: "Bearer secret".to_string() };
+        manager.download_file_with_auth(&url, &dest, Some(&auth))?;
+        assert_eq!(fs::read_to_string(&dest)?, "private");
+
+        // A different host never receives the token
+        let other = RequestAuth { host: "other.example.com".to_string(), name: "Authorization", header: "Bearer secret".to_string() };
+        assert!(manager.download_file_with_auth(&url, &dest, Some(&other)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_gitlab_receives_private_token_header() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+
+        // Another host the private GitLab instance redirects to records every token it receives
+        let leaked = Arc::new(Mutex::new(Vec::new()));
+        let log = leaked.clone();
+        let other = TestServer::start(move |request| {
+            log.lock().unwrap().extend(request.headers.get("private-token").cloned());
+            TestResponse::new(200, "elsewhere")
+        });
+        let other_url = other.url();
+
+        // Raw files are only served with a PRIVATE-TOKEN header, bearer tokens are rejected
+        let server = TestServer::start(move |request| {
+            if request.path == "/redirect"
+            {
+                return TestResponse::new(302, "").with_header("Location", &format!("{}/file.md", other_url));
+            }
+            match (request.headers.get("private-token").map(String::as_str), request.headers.get("authorization"))
+            {
+                | (Some("secret"), None) => TestResponse::new(200, "private"),
+                | _ => TestResponse::new(404, "not found")
+            }
+        });
+
+        let (name, header) = ProviderKind::GitLab.provider().auth_header("secret");
+        let auth = RequestAuth { host: url_host(&server.url()).unwrap(), name, header };
+        let manager = DownloadManager::new(temp_dir.path().to_path_buf(), Config::default())?;
+        let dest = temp_dir.path().join("file.md");
 
-        fs::write(dest_path, content)?;
+        manager.download_file_with_auth(&format!("{}/team/repo/-/raw/main/file.md", server.url()), &dest, Some(&auth))?;
+        assert_eq!(fs::read_to_string(&dest)?, "private");
 
+        // Redirects to another host are not followed with the token
+        assert!(manager.download_file_with_auth(&format!("{}/redirect", server.url()), &dest, Some(&auth)).is_err());
+        assert!(leaked.lock().unwrap().is_empty());
         Ok(())
     }
 }
@@ -0,0 +1,77 @@
+//! Default template set embedded into the binary for vibe-check
+//!
+//! build.rs embeds the `templates/v2` tree, so a first run works without
+//! network access. The snapshot is only installed as a last resort, when no
+//! template store exists and the template source cannot be reached.
+
+use std::{fs, path::Path};
+
+use crate::Result;
+
+/// Source recorded in templates.lock for the embedded snapshot
+pub const EMBEDDED_SOURCE: &str = "embedded";
+
+include!(concat!(env!("OUT_DIR"), "/embedded_templates.rs"));
+
+/// Returns the revision recorded for the snapshot (the vibe-check version it was built into)
+pub fn revision() -> String
+{
+    format!("vibe-check {}", env!("CARGO_PKG_VERSION"))
+}
+
+/// Writes a template snapshot (normally `EMBEDDED_FILES`) into a directory
+///
+/// # Arguments
+///
+/// * `files` - Files as (path relative to the template root, contents)
+/// * `dest` - Directory to write the files into
+///
+/// # Errors
+///
+/// Returns an error if a file cannot be written
+pub fn extract(files: &[(&str, &[u8])], dest: &Path) -> Result<()>
+{
+    for (file, content) in files
+    {
+        let path = dest.join(file);
+        if let Some(parent) = path.parent()
+        {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, content)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::path::Path;
+
+    use super::*;
+    use crate::template_engine;
+
+    #[test]
+    fn test_embedded_templates_match_the_tree() -> Result<()>
+    {
+        let template_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("templates/v2");
+        let config = template_engine::load_template_config(&template_dir)?;
+        assert_eq!(config.version, 2);
+
+        // Every file templates.yml references is in the tree and embedded unchanged
+        assert_eq!(config.missing_sources(&template_dir), Vec::<String>::new());
+        for source in config.source_files()
+        {
+            let embedded = EMBEDDED_FILES.iter().find(|(file, _)| *file == source).map(|(_, content)| *content);
+            assert_eq!(embedded, Some(fs::read(template_dir.join(source))?.as_slice()), "{}", source);
+        }
+
+        // Nothing is embedded that is not in the tree
+        for (file, content) in EMBEDDED_FILES
+        {
+            assert_eq!(fs::read(template_dir.join(file))?, *content, "{}", file);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,840 @@
+//! Format-aware merging of template files into existing project files
+//!
+//! File mappings with `mode: merge` do not replace a project's own version of a
+//! file. Ignore and attribute files (.gitignore, .gitattributes) get the
+//! template's missing patterns appended inside a managed block, .editorconfig is
+//! merged by section and key, and TOML, YAML, and JSON files are deep-merged by
+//! key. Keys set on both sides keep the project's value unless the mapping sets
+//! `prefer: template`.
+
+use std::{collections::HashSet, path::Path};
+
+use crate::{Result, bom::MergePreference};
+
+/// Line that opens the managed block in line-set files
+const BLOCK_BEGIN: &str = "# >>> vibe-check >>>";
+
+/// Line that closes the managed block in line-set files
+const BLOCK_END: &str = "# <<< vibe-check <<<";
+
+/// File formats that can be merged
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeFormat
+{
+    /// One pattern per line (.gitignore, .gitattributes)
+    LineSet,
+    /// INI-style sections with `key = value` pairs (.editorconfig)
+    EditorConfig,
+    /// TOML documents (e.g. .rustfmt.toml), merged with comments and formatting preserved
+    Toml,
+    /// YAML documents (e.g. .clang-format)
+    Yaml,
+    /// JSON documents (e.g. .swift-format)
+    Json
+}
+
+impl MergeFormat
+{
+    /// Detects the merge format from the target file name
+    ///
+    /// # Returns
+    ///
+    /// None if files of this name cannot be merged
+    pub fn detect(target: &Path) -> Option<Self>
+    {
+        match target.file_name()?.to_str()?
+        {
+            | ".gitignore" | ".gitattributes" => Some(Self::LineSet),
+            | ".editorconfig" => Some(Self::EditorConfig),
+            | ".clang-format" | ".clang-tidy" => Some(Self::Yaml),
+            | ".swift-format" => Some(Self::Json),
+            | name => match Path::new(name).extension()?.to_str()?
+            {
+                | "toml" => Some(Self::Toml),
+                | "yml" | "yaml" => Some(Self::Yaml),
+                | "json" => Some(Self::Json),
+                | _ => None
+            }
+        }
+    }
+
+    /// Merges a rendered template into the existing content of a file
+    ///
+    /// # Arguments
+    ///
+    /// * `existing` - Current content of the project file
+    /// * `template` - Rendered template
+    /// * `prefer` - Which side wins for keys set in both (not used for line sets)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either side is not a valid document of the format
+    pub fn merge(self, existing: &str, template: &str, prefer: MergePreference) -> Result<String>
+    {
+        if existing.trim().is_empty() == true
+        {
+            return Ok(template.to_string());
+        }
+
+        match self
+        {
+            | Self::LineSet => Ok(merge_line_set(existing, template)),
+            | Self::EditorConfig => Ok(merge_editorconfig(existing, template, prefer)),
+            | Self::Toml => merge_toml(existing, template, prefer),
+            | Self::Yaml => merge_yaml(existing, template, prefer),
+            | Self::Json => merge_json(existing, template, prefer)
+        }
+    }
+}
+
+/// Normalizes a pattern line for comparison (whitespace between fields does not matter)
+fn pattern_key(line: &str) -> String
+{
+    line.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Merges a line-set template (.gitignore, .gitattributes) into an existing file
+///
+/// Template patterns that the file does not contain yet are written to a
+/// managed block, which is replaced in place on later merges (or appended at
+/// the end the first time). Comments and blank lines of the template are not
+/// copied, and patterns are never removed from the user's part of the file.
+fn merge_line_set(existing: &str, template: &str) -> String
+{
+    let lines: Vec<&str> = existing.lines().collect();
+    let begin = lines.iter().position(|line| line.trim() == BLOCK_BEGIN);
+    let end = begin.and_then(|b| lines[b..].iter().position(|line| line.trim() == BLOCK_END).map(|e| b + e));
+    let (before, after) = match (begin, end)
+    {
+        | (Some(b), Some(e)) => (&lines[..b], &lines[e + 1..]),
+        | _ => (&lines[..], &lines[lines.len()..])
+    };
+
+    let mut present: HashSet<String> = before.iter().chain(after).map(|line| pattern_key(line)).collect();
+    let mut missing = Vec::new();
+    for line in template.lines().map(str::trim)
+    {
+        if line.is_empty() == true || line.starts_with('#') == true
+        {
+            continue;
+        }
+        if present.insert(pattern_key(line)) == true
+        {
+            missing.push(line);
+        }
+    }
+
+    let mut output: Vec<&str> = before.to_vec();
+    if missing.is_empty() == false
+    {
+        if end.is_none() == true
+        {
+            while output.last().is_some_and(|line| line.trim().is_empty()) == true
+            {
+                output.pop();
+            }
+            if output.is_empty() == false
+            {
+                output.push("");
+            }
+        }
+        output.push(BLOCK_BEGIN);
+        output.extend(missing);
+        output.push(BLOCK_END);
+    }
+    output.extend(after);
+
+    if output.is_empty() == true
+    {
+        return String::new();
+    }
+    output.join("\n") + "\n"
+}
+
+/// A section of an .editorconfig file
+struct IniSection
+{
+    /// Glob of the section (None for the preamble before the first section)
+    name:    Option<String>,
+    /// Lower-cased keys with their (trimmed) lines and line indices
+    entries: Vec<(String, String, usize)>,
+    /// Line index of the section header (0 for the preamble)
+    start:   usize,
+    /// Line index after which missing keys are inserted
+    end:     usize
+}
+
+/// Splits an .editorconfig file into its preamble and sections
+fn parse_ini(lines: &[&str]) -> Vec<IniSection>
+{
+    let mut sections = vec![IniSection { name: None, entries: Vec::new(), start: 0, end: 0 }];
+
+    for (index, line) in lines.iter().enumerate()
+    {
+        let line = line.trim();
+        if line.starts_with('[') == true && line.ends_with(']') == true
+        {
+            sections.push(IniSection { name: Some(line[1..line.len() - 1].trim().to_string()), entries: Vec::new(), start: index, end: index + 1 });
+        }
+        else if let Some((key, _)) = line.split_once('=') &&
+            line.starts_with('#') == false &&
+            line.starts_with(';') == false &&
+            let Some(section) = sections.last_mut()
+        {
+            section.entries.push((key.trim().to_lowercase(), line.to_string(), index));
+            section.end = index + 1;
+        }
+    }
+
+    sections
+}
+
+/// Merges an .editorconfig template into an existing file by section and key
+///
+/// Keys missing from an existing section are added at its end. Missing sections
+/// are inserted before the first existing section, with `[*]` leading, so the
+/// sections already in the file still override them. Values already set in the
+/// file are kept unless the template is preferred.
+fn merge_editorconfig(existing: &str, template: &str, prefer: MergePreference) -> String
+{
+    let mut lines: Vec<String> = existing.lines().map(str::to_string).collect();
+    let existing_sections = parse_ini(&existing.lines().collect::<Vec<_>>());
+    let template_sections = parse_ini(&template.lines().collect::<Vec<_>>());
+
+    let mut insertions: Vec<(usize, Vec<String>)> = Vec::new();
+    let mut new_sections: Vec<IniSection> = Vec::new();
+
+    for section in template_sections
+    {
+        match existing_sections.iter().find(|s| s.name == section.name)
+        {
+            | Some(current) =>
+            {
+                let mut missing = Vec::new();
+                for (key, line, _) in section.entries
+                {
+                    match current.entries.iter().find(|(k, ..)| *k == key)
+                    {
+                        | Some((_, _, index)) if prefer == MergePreference::Template => lines[*index] = line,
+                        | Some(_) =>
+                        {}
+                        | None => missing.push(line)
+                    }
+                }
+                if missing.is_empty() == false
+                {
+                    insertions.push((current.end, missing));
+                }
+            }
+            | None if section.entries.is_empty() == false => new_sections.push(section),
+            | None =>
+            {}
+        }
+    }
+
+    // Later sections override earlier ones, so the missing sections go before the
+    // existing ones; a file without sections gets them appended
+    new_sections.sort_by_key(|section| section.name.as_deref() != Some("*"));
+    let first_section = existing_sections.iter().find(|section| section.name.is_some()).map(|section| section.start);
+    if let Some(position) = first_section &&
+        new_sections.is_empty() == false
+    {
+        let mut block = Vec::new();
+        if position > 0 && lines[position - 1].trim().is_empty() == false
+        {
+            block.push(String::new());
+        }
+        for section in new_sections.drain(..)
+        {
+            block.push(format!("[{}]", section.name.unwrap_or_default()));
+            block.extend(section.entries.into_iter().map(|(_, line, _)| line));
+            block.push(String::new());
+        }
+        insertions.push((position, block));
+    }
+
+    // Insert from the bottom so earlier positions stay valid
+    insertions.sort_by_key(|(position, _)| *position);
+    for (position, mut missing) in insertions.into_iter().rev()
+    {
+        // Keys added to the preamble of a file that starts with a section need a separator
+        if lines.get(position).is_some_and(|line| line.trim().starts_with('[')) == true && missing.last().is_some_and(|line| line.is_empty()) == false
+        {
+            missing.push(String::new());
+        }
+        lines.splice(position..position, missing);
+    }
+
+    // Append the missing sections to a file without sections, separated by a blank line
+    if new_sections.is_empty() == false
+    {
+        while lines.last().is_some_and(|line| line.trim().is_empty()) == true
+        {
+            lines.pop();
+        }
+        for section in new_sections
+        {
+            if lines.is_empty() == false
+            {
+                lines.push(String::new());
+            }
+            lines.push(format!("[{}]", section.name.unwrap_or_default()));
+            lines.extend(section.entries.into_iter().map(|(_, line, _)| line));
+        }
+    }
+
+    if lines.is_empty() == true
+    {
+        return String::new();
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Deep-merges a TOML template into an existing document
+///
+/// Comments and formatting of the existing document are preserved; keys added
+/// from the template keep their comments, and added tables go to the end.
+fn merge_toml(existing: &str, template: &str, prefer: MergePreference) -> Result<String>
+{
+    let mut document: toml_edit::DocumentMut = existing.parse().map_err(|e| format!("invalid TOML: {}", e))?;
+    let template: toml_edit::DocumentMut = template.parse().map_err(|e| format!("invalid TOML template: {}", e))?;
+
+    // Added tables are placed after all existing ones, in template order
+    let mut next_position = document.iter().filter_map(|(_, item)| item.as_table().and_then(toml_edit::Table::position)).max().unwrap_or(0) + 1;
+    merge_toml_table(document.as_table_mut(), template.as_table(), prefer, &mut next_position);
+    Ok(document.to_string())
+}
+
+/// Merges the keys of a template table into a table of the existing document
+fn merge_toml_table(local: &mut dyn toml_edit::TableLike, template: &dyn toml_edit::TableLike, prefer: MergePreference, next_position: &mut usize)
+{
+    for (key, item) in template.iter()
+    {
+        if let Some(current) = local.get_mut(key)
+        {
+            match (current.as_table_like_mut(), item.as_table_like())
+            {
+                | (Some(current), Some(item)) => merge_toml_table(current, item, prefer, next_position),
+                | _ if prefer == MergePreference::Template =>
+                {
+                    // Keep the comments around the existing value
+                    match (current.as_value_mut(), item.as_value())
+                    {
+                        | (Some(value), Some(new_value)) =>
+                        {
+                            let decor = value.decor().clone();
+                            *value = new_value.clone();
+                            *value.decor_mut() = decor;
+                        }
+                        | _ => *current = item.clone()
+                    }
+                }
+                | _ =>
+                {}
+            }
+            continue;
+        }
+
+        let mut item = item.clone();
+        place_toml_tables(&mut item, next_position);
+        match template.key(key)
+        {
+            | Some(formatted) => local.entry_format(formatted).or_insert(item),
+            | None => local.entry(key).or_insert(item)
+        };
+    }
+}
+
+/// Assigns document positions to a table added from the template and its subtables
+fn place_toml_tables(item: &mut toml_edit::Item, next_position: &mut usize)
+{
+    if let Some(table) = item.as_table_mut()
+    {
+        table.set_position(*next_position);
+        *next_position += 1;
+        for (_, child) in table.iter_mut()
+        {
+            place_toml_tables(child, next_position);
+        }
+    }
+}
+
+/// Change to an existing YAML document, applied to its text
+enum YamlEdit
+{
+    /// Add a key to the mapping at a key path (empty for the top level)
+    Insert
+    {
+        parent: Vec<String>, key: String, value: serde_yaml::Value
+    },
+    /// Replace the value of the key at a key path
+    Replace
+    {
+        path: Vec<String>, value: serde_yaml::Value
+    }
+}
+
+/// Deep-merges a YAML template into an existing document
+///
+/// The file is edited in place, so comments and formatting survive: missing keys
+/// are inserted at the end of their block mapping, and with `prefer: template`
+/// single-line values are replaced on their line. Documents that cannot be edited
+/// this way (flow mappings, multi-line values, several documents) are refused
+/// instead of being rewritten without their comments.
+fn merge_yaml(existing: &str, template: &str, prefer: MergePreference) -> Result<String>
+{
+    let local: serde_yaml::Value = serde_yaml::from_str(existing).map_err(|e| format!("invalid YAML: {}", e))?;
+    let template: serde_yaml::Value = serde_yaml::from_str(template).map_err(|e| format!("invalid YAML template: {}", e))?;
+
+    let mut merged = local.clone();
+    merge_value(&mut merged, &template, prefer);
+    if merged == local
+    {
+        return Ok(existing.to_string());
+    }
+
+    let mut edits = Vec::new();
+    collect_yaml_edits(&local, &template, prefer, &mut Vec::new(), &mut edits)?;
+
+    let mut lines: Vec<String> = existing.lines().map(str::to_string).collect();
+    for edit in edits
+    {
+        apply_yaml_edit(&mut lines, edit)?;
+    }
+    let newline = if existing.contains("\r\n") == true
+    {
+        "\r\n"
+    }
+    else
+    {
+        "\n"
+    };
+    let output = lines.join(newline) + newline;
+
+    // The edited text must mean exactly the merged document
+    let check: serde_yaml::Value = serde_yaml::from_str(&output).map_err(|e| format!("editing the file in place failed ({}); add the template's keys by hand", e))?;
+    if check != merged
+    {
+        return Err("merging would rewrite the file and drop its comments; add the template's keys by hand".into());
+    }
+    Ok(output)
+}
+
+/// Collects the edits that merge a template value into an existing YAML value (see `merge_value`)
+///
+/// # Errors
+///
+/// Returns an error if a key that needs merging is not a string
+fn collect_yaml_edits(
+    local: &serde_yaml::Value, template: &serde_yaml::Value, prefer: MergePreference, path: &mut Vec<String>, edits: &mut Vec<YamlEdit>
+) -> Result<()>
+{
+    match (local, template)
+    {
+        | (serde_yaml::Value::Mapping(local), serde_yaml::Value::Mapping(template)) =>
+        {
+            for (key, value) in template
+            {
+                let name = key.as_str().ok_or_else(|| format!("the non-string key {:?} cannot be merged", key))?;
+                match local.get(key)
+                {
+                    | Some(current) =>
+                    {
+                        path.push(name.to_string());
+                        collect_yaml_edits(current, value, prefer, path, edits)?;
+                        path.pop();
+                    }
+                    | None => edits.push(YamlEdit::Insert { parent: path.clone(), key: name.to_string(), value: value.clone() })
+                }
+            }
+        }
+        | (local, template) if prefer == MergePreference::Template && local != template =>
+            edits.push(YamlEdit::Replace { path: path.clone(), value: template.clone() }),
+        | _ =>
+        {}
+    }
+    Ok(())
+}
+
+/// Returns true if a YAML line holds content (not blank and not a comment)
+fn is_yaml_content(line: &str) -> bool
+{
+    let trimmed = line.trim();
+    trimmed.is_empty() == false && trimmed.starts_with('#') == false
+}
+
+/// Returns the indentation of a YAML line
+fn yaml_indent(line: &str) -> usize
+{
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// Splits a block mapping line into its (unquoted) key and the text after the colon
+fn yaml_key(line: &str) -> Option<(&str, &str)>
+{
+    let trimmed = line.trim_start();
+    let (key, rest) = match trimmed.chars().next()?
+    {
+        | quote @ ('"' | '\'') =>
+        {
+            let end = trimmed[1..].find(quote)? + 1;
+            (&trimmed[1..end], trimmed[end + 1..].strip_prefix(':')?)
+        }
+        | _ =>
+        {
+            let colon = trimmed.find(": ").or_else(|| trimmed.strip_suffix(':').map(str::len))?;
+            (trimmed[..colon].trim_end(), &trimmed[colon + 1..])
+        }
+    };
+    (key.starts_with("- ") == false).then_some((key, rest))
+}
+
+/// Splits the text after a key's colon into its value and trailing comment (with its leading spaces)
+fn split_yaml_comment(rest: &str) -> (&str, &str)
+{
+    let mut quote = None;
+    let mut previous = ' ';
+    for (index, c) in rest.char_indices()
+    {
+        match (quote, c)
+        {
+            | (None, '"' | '\'') => quote = Some(c),
+            | (Some(open), _) if c == open => quote = None,
+            | (None, '#') if previous.is_whitespace() == true =>
+            {
+                let value = rest[..index].trim_end();
+                return (value, &rest[value.len()..]);
+            }
+            | _ =>
+            {}
+        }
+        previous = c;
+    }
+    (rest.trim_end(), "")
+}
+
+/// Finds the lines of the block mapping at a key path
+///
+/// # Returns
+///
+/// The line range of the mapping's entries
+///
+/// # Errors
+///
+/// Returns an error if the path does not lead to a block mapping in the file
+fn yaml_block(lines: &[String], path: &[String]) -> Result<std::ops::Range<usize>>
+{
+    // The top level is the single document between `---` and `...`
+    let mut start = 0;
+    let mut end = lines.len();
+    for (index, line) in lines.iter().enumerate()
+    {
+        if line.starts_with("---") == true
+        {
+            if lines[..index].iter().any(|l| is_yaml_content(l)) == true
+            {
+                return Err("the file has several YAML documents".into());
+            }
+            start = index + 1;
+        }
+        else if line.starts_with("...") == true
+        {
+            end = index;
+            break;
+        }
+    }
+
+    for key in path
+    {
+        let indent = lines[start..end].iter().find(|l| is_yaml_content(l)).map(|l| yaml_indent(l)).unwrap_or_default();
+        let found = (start..end)
+            .find(|&index| is_yaml_content(&lines[index]) && yaml_indent(&lines[index]) == indent && yaml_key(&lines[index]).is_some_and(|(name, _)| name == key));
+        let Some(found) = found
+        else
+        {
+            return Err(format!("cannot find the key '{}' in the file", key).into());
+        };
+        if yaml_key(&lines[found]).is_some_and(|(_, rest)| split_yaml_comment(rest).0.is_empty()) == false
+        {
+            return Err(format!("'{}' is not a block mapping", key).into());
+        }
+
+        start = found + 1;
+        end = (start..end).find(|&index| is_yaml_content(&lines[index]) && yaml_indent(&lines[index]) <= indent).unwrap_or(end);
+    }
+
+    Ok(start..end)
+}
+
+/// Applies an edit to the lines of a YAML document
+///
+/// # Errors
+///
+/// Returns an error if the edit cannot be made without rewriting other lines
+fn apply_yaml_edit(lines: &mut Vec<String>, edit: YamlEdit) -> Result<()>
+{
+    match edit
+    {
+        | YamlEdit::Insert { parent, key, value } =>
+        {
+            let block = yaml_block(lines, &parent)?;
+            let entries: Vec<usize> = block.filter(|&index| is_yaml_content(&lines[index])).collect();
+            let (Some(&first), Some(&last)) = (entries.first(), entries.last())
+            else
+            {
+                return Err(format!("cannot add '{}' to an empty mapping", key).into());
+            };
+            if lines[first].trim_start().starts_with('-') == true
+            {
+                return Err(format!("cannot add '{}' to a sequence", key).into());
+            }
+
+            let mut mapping = serde_yaml::Mapping::new();
+            mapping.insert(serde_yaml::Value::String(key), value);
+            let indent = " ".repeat(yaml_indent(&lines[first]));
+            let added: Vec<String> = serde_yaml::to_string(&mapping)?.lines().map(|line| format!("{}{}", indent, line)).collect();
+            lines.splice(last + 1..last + 1, added);
+        }
+        | YamlEdit::Replace { path, value } =>
+        {
+            let (key, parent) = path.split_last().ok_or("cannot replace the whole document")?;
+            let block = yaml_block(lines, parent)?;
+            let indent = lines[block.clone()].iter().find(|l| is_yaml_content(l)).map(|l| yaml_indent(l)).unwrap_or_default();
+            let index = block
+                .into_iter()
+                .find(|&index| is_yaml_content(&lines[index]) && yaml_indent(&lines[index]) == indent && yaml_key(&lines[index]).is_some_and(|(name, _)| name == key))
+                .ok_or_else(|| format!("cannot find the key '{}' in the file", key))?;
+
+            let text = serde_yaml::to_string(&value)?;
+            let line = &lines[index];
+            let (_, rest) = yaml_key(line).ok_or_else(|| format!("cannot find the key '{}' in the file", key))?;
+            let (old, comment) = split_yaml_comment(rest);
+            if old.is_empty() == true || text.trim_end().contains('\n') == true || value.is_mapping() == true || value.is_sequence() == true
+            {
+                return Err(format!("cannot replace the multi-line value of '{}'", key).into());
+            }
+            lines[index] = format!("{} {}{}", &line[..line.len() - rest.len()], text.trim_end(), comment);
+        }
+    }
+    Ok(())
+}
+
+/// Merges a template value into an existing YAML value
+fn merge_value(local: &mut serde_yaml::Value, template: &serde_yaml::Value, prefer: MergePreference)
+{
+    match (local, template)
+    {
+        | (serde_yaml::Value::Mapping(local), serde_yaml::Value::Mapping(template)) =>
+        {
+            for (key, value) in template
+            {
+                match local.get_mut(key)
+                {
+                    | Some(current) => merge_value(current, value, prefer),
+                    | None =>
+                    {
+                        local.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        | (local, template) if prefer == MergePreference::Template => *local = template.clone(),
+        | _ =>
+        {}
+    }
+}
+
+/// Deep-merges a JSON template into an existing document
+///
+/// JSON has no comments; the key order of the existing document is kept and
+/// the file is only rewritten if the merge changes it. A rewritten file is
+/// pretty-printed with two-space indentation, so its own formatting is not kept.
+fn merge_json(existing: &str, template: &str, prefer: MergePreference) -> Result<String>
+{
+    let local: serde_json::Value = serde_json::from_str(existing).map_err(|e| format!("invalid JSON: {}", e))?;
+    let template: serde_json::Value = serde_json::from_str(template).map_err(|e| format!("invalid JSON template: {}", e))?;
+
+    let mut merged = local.clone();
+    merge_json_value(&mut merged, &template, prefer);
+    if merged == local
+    {
+        return Ok(existing.to_string());
+    }
+    Ok(serde_json::to_string_pretty(&merged)? + "\n")
+}
+
+/// Merges a template value into an existing JSON value (objects keep their key order)
+fn merge_json_value(local: &mut serde_json::Value, template: &serde_json::Value, prefer: MergePreference)
+{
+    match (local, template)
+    {
+        | (serde_json::Value::Object(local), serde_json::Value::Object(template)) =>
+        {
+            for (key, value) in template
+            {
+                match local.get_mut(key)
+                {
+                    | Some(current) => merge_json_value(current, value, prefer),
+                    | None =>
+                    {
+                        local.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        | (local, template) if prefer == MergePreference::Template => *local = template.clone(),
+        | _ =>
+        {}
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_merge_line_set()
+    {
+        let template = "# Build output\ntarget\n\n*.pdb\n**/*.rs.bk\n";
+
+        // Missing patterns are appended in a managed block, existing ones are kept
+        let merged = merge_line_set("node_modules\n*.pdb\n\n", template);
+        assert_eq!(merged, "node_modules\n*.pdb\n\n# >>> vibe-check >>>\ntarget\n**/*.rs.bk\n# <<< vibe-check <<<\n");
+
+        // Merging again is stable, and the block is updated in place
+        assert_eq!(merge_line_set(&merged, template), merged);
+        let edited = merged.replace("# <<< vibe-check <<<\n", "# <<< vibe-check <<<\n.env\n");
+        assert_eq!(merge_line_set(&edited, "target\n*.log\n"), "node_modules\n*.pdb\n\n# >>> vibe-check >>>\ntarget\n*.log\n# <<< vibe-check <<<\n.env\n");
+
+        // Attribute lines are compared without regard to spacing
+        assert_eq!(merge_line_set("*.rs   text eol=lf\n", "*.rs text eol=lf\n"), "*.rs   text eol=lf\n");
+        assert_eq!(MergeFormat::detect(Path::new("$workspace/.gitattributes")), Some(MergeFormat::LineSet));
+        assert_eq!(MergeFormat::detect(Path::new("$workspace/.clang-format")), Some(MergeFormat::Yaml));
+        assert_eq!(MergeFormat::detect(Path::new("$workspace/.rustfmt.toml")), Some(MergeFormat::Toml));
+        assert_eq!(MergeFormat::detect(Path::new("$workspace/CMakePresets.txt")), None);
+    }
+
+    #[test]
+    fn test_merge_editorconfig()
+    {
+        let template = "root = true\n\n[*]\nend_of_line = lf\nindent_size = 4\n\n[*.md]\ntrim_trailing_whitespace = false\n";
+        let existing = "# Project settings\n\n[*]\nINDENT_SIZE = 2\n\n[Makefile]\nindent_style = tab\n";
+
+        // Missing keys and sections are added, existing values win
+        let merged = merge_editorconfig(existing, template, MergePreference::Local);
+        assert_eq!(
+            merged,
+            "root = true\n# Project settings\n\n[*.md]\ntrim_trailing_whitespace = false\n\n[*]\nINDENT_SIZE = 2\nend_of_line = lf\n\n[Makefile]\nindent_style = \
+             tab\n"
+        );
+
+        // Merging again changes nothing
+        assert_eq!(merge_editorconfig(&merged, template, MergePreference::Local), merged);
+        assert_eq!(merge_editorconfig("[*]\nindent_size = 2\n", "root = true\n", MergePreference::Local), "root = true\n\n[*]\nindent_size = 2\n");
+        assert_eq!(
+            merge_editorconfig("", template, MergePreference::Local),
+            "root = true\n\n[*]\nend_of_line = lf\nindent_size = 4\n\n[*.md]\ntrim_trailing_whitespace = false\n"
+        );
+
+        // The template wins when preferred
+        assert_eq!(merge_editorconfig("[*]\nindent_size = 2\n", "[*]\nindent_size = 4\n", MergePreference::Template), "[*]\nindent_size = 4\n");
+    }
+
+    #[test]
+    fn test_merge_editorconfig_keeps_specific_sections_last()
+    {
+        let template = "root = true\n\n[*.toml]\nindent_size = 2\n\n[*]\ntrim_trailing_whitespace = true\n";
+        let existing = "root = true\n\n[*.md]\ntrim_trailing_whitespace = false\n";
+
+        // `[*]` goes right after the preamble, ahead of the user's `[*.md]` which still wins for Markdown
+        let merged = merge_editorconfig(existing, template, MergePreference::Local);
+        assert_eq!(merged, "root = true\n\n[*]\ntrim_trailing_whitespace = true\n\n[*.toml]\nindent_size = 2\n\n[*.md]\ntrim_trailing_whitespace = false\n");
+        let sections = parse_ini(&merged.lines().collect::<Vec<_>>());
+        let names: Vec<_> = sections.iter().filter_map(|section| section.name.as_deref()).collect();
+        assert_eq!(names.last(), Some(&"*.md"));
+        assert_eq!(merge_editorconfig(&merged, template, MergePreference::Local), merged);
+    }
+
+    #[test]
+    fn test_merge_toml() -> Result<()>
+    {
+        let template =
+            "# Line width\nmax_width = 100\nedition = \"2024\"\n\n[format]\nwrap = true\nstyle = \"block\"\n\n# Import rules\n[imports]\ngranularity = \"crate\"\n";
+        let existing = "# Our settings\nmax_width = 120 # wide screens\n\n[format]\nwrap = false\n\n[custom]\nkeep = 1\n";
+
+        // Missing keys and tables are added with their comments, local values and comments stay
+        let merged = MergeFormat::Toml.merge(existing, template, MergePreference::Local)?;
+        assert_eq!(
+            merged,
+            "# Our settings\nmax_width = 120 # wide screens\nedition = \"2024\"\n\n[format]\nwrap = false\nstyle = \"block\"\n\n[custom]\nkeep = 1\n\n# Import \
+             rules\n[imports]\ngranularity = \"crate\"\n"
+        );
+        assert_eq!(MergeFormat::Toml.merge(&merged, template, MergePreference::Local)?, merged);
+
+        // The template wins when preferred, the comment on the local value is kept
+        let merged = MergeFormat::Toml.merge(existing, template, MergePreference::Template)?;
+        assert!(merged.contains("max_width = 100 # wide screens\n"), "{}", merged);
+        assert!(merged.contains("wrap = true\n"), "{}", merged);
+
+        assert!(MergeFormat::Toml.merge("max_width = ", template, MergePreference::Local).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_yaml_and_json() -> Result<()>
+    {
+        let template = "Language: Cpp\nColumnLimit: 100\nBraceWrapping:\n  AfterClass: true\n  AfterEnum: true\n";
+
+        // Added top-level keys are appended, comments and the document end marker stay in place
+        let existing = "---\n# Project style\nColumnLimit: 120\nBraceWrapping:\n  AfterClass: false\n  AfterEnum: true\n...\n";
+        assert_eq!(
+            MergeFormat::Yaml.merge(existing, template, MergePreference::Local)?,
+            "---\n# Project style\nColumnLimit: 120\nBraceWrapping:\n  AfterClass: false\n  AfterEnum: true\nLanguage: Cpp\n...\n"
+        );
+
+        // Nested keys are inserted into their mapping, comments and formatting stay in place
+        let existing = concat!(
+            "---\n# Project style\nLanguage: Cpp # C++ only\n\n", "BraceWrapping:\n    # Allman for classes\n    AfterClass: true\n\n    AfterStruct: false\n",
+            "# Column limit follows\nColumnLimit: 120\n"
+        );
+        assert_eq!(
+            MergeFormat::Yaml.merge(existing, template, MergePreference::Local)?,
+            existing.replace("    AfterStruct: false\n", "    AfterStruct: false\n    AfterEnum: true\n")
+        );
+
+        // The template's values replace single-line values without touching their comments
+        assert_eq!(
+            MergeFormat::Yaml.merge(existing, "BraceWrapping:\n  AfterClass: false\nColumnLimit: 100\nLanguage: Cpp\n", MergePreference::Template)?,
+            existing.replace("AfterClass: true", "AfterClass: false").replace("ColumnLimit: 120", "ColumnLimit: 100")
+        );
+
+        // Changes that cannot be made in place are refused rather than dropping comments
+        let flow = "# Project style\nBraceWrapping: { AfterClass: true }\n";
+        assert!(MergeFormat::Yaml.merge(flow, template, MergePreference::Local).is_err());
+        assert!(MergeFormat::Yaml.merge("Language: Cpp\n---\nLanguage: ObjC\n", template, MergePreference::Local).is_err());
+
+        // Without comments the result is the same as a rewrite
+        let merged = MergeFormat::Yaml.merge("Language: Cpp\nBraceWrapping:\n  AfterClass: false\n", template, MergePreference::Local)?;
+        assert_eq!(merged, "Language: Cpp\nBraceWrapping:\n  AfterClass: false\n  AfterEnum: true\nColumnLimit: 100\n");
+        assert_eq!(MergeFormat::Yaml.merge(&merged, template, MergePreference::Local)?, merged);
+
+        // JSON keeps the existing key order, and the template wins when preferred
+        let template = "{ \"version\": 1, \"lineLength\": 100, \"indentation\": { \"spaces\": 4 } }";
+        let existing = "{\n  \"lineLength\": 120,\n  \"indentation\": { \"tabs\": 1 }\n}\n";
+        assert_eq!(
+            MergeFormat::Json.merge(existing, template, MergePreference::Local)?,
+            "{\n  \"lineLength\": 120,\n  \"indentation\": {\n    \"tabs\": 1,\n    \"spaces\": 4\n  },\n  \"version\": 1\n}\n"
+        );
+        assert!(MergeFormat::Json.merge(existing, template, MergePreference::Template)?.contains("\"lineLength\": 100"));
+
+        // Unchanged files are kept as written, changed ones are pretty-printed
+        let compact = "{\"version\":1,\"lineLength\":100,\"indentation\":{\"spaces\":4}}";
+        assert_eq!(MergeFormat::Json.merge(compact, template, MergePreference::Local)?, compact);
+        assert_eq!(
+            MergeFormat::Json.merge("{\"lineLength\":80}", template, MergePreference::Local)?,
+            "{\n  \"lineLength\": 80,\n  \"version\": 1,\n  \"indentation\": {\n    \"spaces\": 4\n  }\n}\n"
+        );
+        Ok(())
+    }
+}
@@ -9,6 +9,11 @@
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
+/// Directory next to the metadata file that keeps the installed content of tracked files
+///
+/// Files are stored by their SHA-256 checksum and serve as the base of three-way merges.
+pub const ORIGINALS_DIR_NAME: &str = "installed_originals";
+
 /// Metadata about an installed template file
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileMetadata
@@ -38,6 +43,7 @@ pub enum FileStatus
 pub struct FileTracker
 {
     metadata_path: PathBuf,
+    originals_dir: PathBuf,
     metadata:      HashMap<String, FileMetadata>
 }
 
@@ -81,7 +87,7 @@ pub fn new(data_dir: &Path) -> Result<Self, Box<dyn Error>>
             HashMap::new()
         };
 
-        Ok(Self { metadata_path, metadata })
+        Ok(Self { metadata_path, originals_dir: data_dir.join(ORIGINALS_DIR_NAME), metadata })
     }
 
     /// Calculate SHA-256 checksum of a file
@@ -114,6 +120,32 @@ pub fn record_installation(&mut self, file_path: &Path, original_sha: String, te
         self.metadata.insert(absolute_path, FileMetadata { original_sha, template_version, installed_date: now, lang, category });
     }
 
+    /// Keeps the installed content of a file as the base for later three-way merges
+    ///
+    /// Returns the SHA-256 checksum of the content, to be recorded with `record_installation`.
+    pub fn store_original(&self, content: &[u8]) -> Result<String, Box<dyn Error>>
+    {
+        let sha = format!("{:x}", Sha256::digest(content));
+        let path = self.originals_dir.join(&sha);
+        if path.exists() == false
+        {
+            fs::create_dir_all(&self.originals_dir)?;
+            fs::write(&path, content)?;
+        }
+        Ok(sha)
+    }
+
+    /// Returns the content a tracked file had when it was installed
+    ///
+    /// Returns None if the file is not tracked, the content was not kept
+    /// (installed by an older version), or it is not UTF-8 text.
+    pub fn original_content(&self, file_path: &Path) -> Option<String>
+    {
+        let metadata = self.get_metadata(file_path)?;
+        let content = fs::read(self.originals_dir.join(&metadata.original_sha)).ok()?;
+        String::from_utf8(content).ok()
+    }
+
     /// Check the modification status of a file
     pub fn check_modification(&self, file_path: &Path) -> Result<FileStatus, Box<dyn Error>>
     {
@@ -211,6 +243,20 @@ pub fn save(&self) -> Result<(), Box<dyn Error>>
 
         let json = serde_json::to_string_pretty(&self.metadata)?;
         fs::write(&self.metadata_path, json)?;
+
+        // Drop installed content that no tracked file refers to anymore
+        if self.originals_dir.exists() == true
+        {
+            for entry in fs::read_dir(&self.originals_dir)?
+            {
+                let entry = entry?;
+                let name = entry.file_name().to_string_lossy().to_string();
+                if self.metadata.values().any(|meta| meta.original_sha == name) == false
+                {
+                    fs::remove_file(entry.path())?;
+                }
+            }
+        }
         Ok(())
     }
 }
@@ -270,6 +316,34 @@ fn test_file_tracking() -> Result<(), Box<dyn Error>>
         Ok(())
     }
 
+    #[test]
+    fn test_original_content() -> Result<(), Box<dyn Error>>
+    {
+        let temp_dir = TempDir::new()?;
+        let data_dir = temp_dir.path().join("data");
+        let test_file = temp_dir.path().join("test.txt");
+        fs::write(&test_file, b"Installed content")?;
+
+        let mut tracker = FileTracker::new(&data_dir)?;
+        assert_eq!(tracker.original_content(&test_file), None);
+
+        let sha = tracker.store_original(b"Installed content")?;
+        assert_eq!(sha, FileTracker::calculate_sha256(&test_file)?);
+        tracker.record_installation(&test_file, sha.clone(), 2, None, "language".to_string());
+        tracker.save()?;
+
+        // The installed content stays available after the user edits the file
+        fs::write(&test_file, b"User content")?;
+        assert_eq!(tracker.original_content(&test_file).as_deref(), Some("Installed content"));
+
+        // Content of untracked files is pruned on save
+        tracker.remove_entry(&test_file);
+        tracker.save()?;
+        assert!(data_dir.join(ORIGINALS_DIR_NAME).join(&sha).exists() == false);
+
+        Ok(())
+    }
+
     #[test]
     fn test_get_installed_language_for_workspace() -> Result<(), Box<dyn Error>>
     {
@@ -0,0 +1,457 @@
+//! Git-backed template sources for vibe-check
+//!
+//! Fetches templates by cloning a repository into a local cache instead of
+//! downloading individual files over raw HTTP. Uses the `git` command line
+//! client, so the user's existing credentials (credential helpers, SSH agent)
+//! work for private repositories.
+//!
+//! Supported source formats:
+//! - `git+https://host/team/templates.git`
+//! - `git+ssh://git@host/team/templates.git`
+//! - `file:///path/to/templates.git` (local bare repository, works offline)
+//!
+//! Options are appended as a fragment: `#ref=<branch|tag|commit>&path=<subdirectory>`.
+//!
+//! The `network.proxy`, `network.no_proxy` and `network.ca_bundle` config keys are
+//! passed to git through its environment.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio}
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{Result, config::NetworkConfig, utils::split_source_options};
+
+/// A template source backed by a git repository
+#[derive(Debug, Clone, PartialEq)]
+pub struct GitSource
+{
+    /// Repository URL passed to git (without the `git+` prefix)
+    pub url:     String,
+    /// Branch, tag, or commit to check out (remote HEAD if None)
+    pub refname: Option<String>,
+    /// Template directory within the repository (empty for repository root)
+    pub path:    String
+}
+
+impl GitSource
+{
+    /// Parses a git source specification
+    ///
+    /// Returns None if the source is not a git source.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - Source string (e.g. `git+https://host/repo.git#ref=main&path=templates`)
+    pub fn parse(source: &str) -> Option<Self>
+    {
+        let (location, options) = split_source_options(source);
+
+        let url = if let Some(stripped) = location.strip_prefix("git+")
+        {
+            stripped
+        }
+        else if location.starts_with("file://")
+        {
+            location
+        }
+        else
+        {
+            return None;
+        };
+
+        Some(Self {
+            url:     url.to_string(),
+            refname: options.get("ref").filter(|r| r.is_empty() == false).cloned(),
+            path:    options.get("path").map(|p| p.trim_matches('/').to_string()).unwrap_or_default()
+        })
+    }
+
+    /// Fetches the repository and exports the template directory
+    ///
+    /// Clones the repository as a bare mirror into `cache_dir` on first use and
+    /// fetches updates on subsequent calls. The requested ref is resolved to a
+    /// commit, and the files below `path` at that commit are written to `dest`.
+    ///
+    /// # Arguments
+    ///
+    /// * `cache_dir` - Directory holding cached bare repositories
+    /// * `dest` - Directory to export the template files into
+    /// * `network` - Network configuration (proxy and CA settings for git)
+    ///
+    /// # Returns
+    ///
+    /// The full SHA of the exported commit
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if git is not available, fetching fails, the ref
+    /// cannot be resolved, or the path does not exist at that commit
+    pub fn fetch(&self, cache_dir: &Path, dest: &Path, network: &NetworkConfig) -> Result<String>
+    {
+        check_argument("repository URL", &self.url)?;
+        if let Some(refname) = &self.refname
+        {
+            check_argument("ref", refname)?;
+        }
+
+        let repo_dir = self.cache_path(cache_dir);
+        let git_env = network.git_env();
+
+        if repo_dir.join("HEAD").exists() == false
+        {
+            fs::create_dir_all(cache_dir)?;
+            run_git(None, &["clone", "--bare", "--quiet", "--", &self.url, &repo_dir.to_string_lossy()], &git_env)?;
+        }
+        else
+        {
+            run_git(Some(&repo_dir), &["fetch", "--quiet", "--prune", "--tags", "--force", "--", &self.url, "+refs/heads/*:refs/heads/*"], &git_env)?;
+        }
+
+        let commit = self.resolve_commit(&repo_dir, &git_env)?;
+        let count = self.export(&repo_dir, &commit, dest)?;
+
+        if count == 0
+        {
+            return Err(format!("No files found at path '{}' in commit {}", self.path, commit).into());
+        }
+
+        Ok(commit)
+    }
+
+    /// Returns the cache location for this repository's bare clone
+    fn cache_path(&self, cache_dir: &Path) -> PathBuf
+    {
+        let digest = format!("{:x}", Sha256::digest(self.url.as_bytes()));
+        cache_dir.join(&digest[..16])
+    }
+
+    /// Resolves the configured ref (or HEAD) to a commit SHA
+    ///
+    /// Commits that are not reachable from any fetched branch or tag are
+    /// fetched explicitly, so pinning to an arbitrary commit works.
+    fn resolve_commit(&self, repo_dir: &Path, git_env: &[(&str, String)]) -> Result<String>
+    {
+        let refname = self.refname.as_deref().unwrap_or("HEAD");
+        let spec = format!("{}^{{commit}}", refname);
+
+        if let Ok(commit) = git_output(repo_dir, &["rev-parse", "--verify", "--quiet", &spec])
+        {
+            return Ok(commit.trim().to_string());
+        }
+
+        // Not a branch or tag we know about: try fetching it directly (e.g. a commit SHA)
+        run_git(Some(repo_dir), &["fetch", "--quiet", "--", &self.url, refname], git_env)?;
+        let commit = git_output(repo_dir, &["rev-parse", "--verify", "--quiet", "FETCH_HEAD^{commit}"])
+            .map_err(|_| format!("Could not resolve ref '{}' in {}", refname, self.url))?;
+
+        Ok(commit.trim().to_string())
+    }
+
+    /// Writes all files below `path` at `commit` into `dest`
+    ///
+    /// Returns the number of files written. Submodules and symbolic links are skipped.
+    fn export(&self, repo_dir: &Path, commit: &str, dest: &Path) -> Result<usize>
+    {
+        let listing = if self.path.is_empty() == true
+        {
+            git_output(repo_dir, &["ls-tree", "-r", "-z", commit])?
+        }
+        else
+        {
+            git_output(repo_dir, &["ls-tree", "-r", "-z", commit, "--", &format!("{}/", self.path)])?
+        };
+
+        let prefix = if self.path.is_empty() == true
+        {
+            String::new()
+        }
+        else
+        {
+            format!("{}/", self.path)
+        };
+
+        let mut count = 0;
+        for entry in listing.split('\0').filter(|e| e.is_empty() == false)
+        {
+            // Format: "<mode> <type> <object>\t<path>"
+            let Some((meta, file_path)) = entry.split_once('\t')
+            else
+            {
+                continue;
+            };
+            let fields: Vec<&str> = meta.split_whitespace().collect();
+            if fields.len() != 3 || fields[1] != "blob" || fields[0] == "120000"
+            {
+                continue;
+            }
+
+            let relative = file_path.strip_prefix(&prefix).unwrap_or(file_path);
+            let content = git_bytes(repo_dir, &["cat-file", "blob", fields[2]])?;
+
+            let target = dest.join(relative);
+            if let Some(parent) = target.parent()
+            {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&target, content)?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+}
+
+/// Returns true if a ref is a full commit SHA (SHA-1 or SHA-256)
+pub fn is_commit_sha(refname: &str) -> bool
+{
+    matches!(refname.len(), 40 | 64) && refname.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Resolves a branch or tag of a remote repository to a commit without cloning it (`git ls-remote`)
+///
+/// Branches take precedence over tags of the same name, and annotated tags are
+/// resolved to the commit they point to. Credential prompts are disabled, so
+/// repositories that git cannot access non-interactively fail instead of blocking.
+///
+/// # Arguments
+///
+/// * `url` - Repository URL passed to git
+/// * `refname` - Branch or tag name
+/// * `network` - Network configuration (proxy and CA settings for git)
+///
+/// # Errors
+///
+/// Returns an error if git is not available, the repository cannot be reached,
+/// or it has no branch or tag of that name
+pub fn resolve_remote_ref(url: &str, refname: &str, network: &NetworkConfig) -> Result<String>
+{
+    check_argument("repository URL", url)?;
+    check_argument("ref", refname)?;
+
+    let output = Command::new("git")
+        .args(["ls-remote", "--", url, refname, &format!("{}^{{}}", refname)])
+        .envs(network.git_env())
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to run git (is it installed?): {}", e))?;
+
+    if output.status.success() == false
+    {
+        return Err(format!("Failed to resolve ref '{}' of {}: {}", refname, url, String::from_utf8_lossy(&output.stderr).trim()).into());
+    }
+
+    // Format: "<object>\t<refname>"
+    let listing = String::from_utf8(output.stdout)?;
+    let refs: Vec<(&str, &str)> = listing.lines().filter_map(|line| line.split_once('\t')).collect();
+
+    [format!("refs/heads/{}", refname), format!("refs/tags/{}^{{}}", refname), format!("refs/tags/{}", refname), refname.to_string()]
+        .iter()
+        .find_map(|candidate| refs.iter().find(|(_, name)| name == candidate).map(|(object, _)| object.to_string()))
+        .ok_or_else(|| format!("No branch or tag '{}' in {}", refname, url).into())
+}
+
+/// Lists the tags of a remote repository without cloning it (`git ls-remote --tags`)
+///
+/// # Arguments
+///
+/// * `url` - Repository URL passed to git
+/// * `network` - Network configuration (proxy and CA settings for git)
+///
+/// # Errors
+///
+/// Returns an error if git is not available or the repository cannot be reached
+pub fn list_remote_tags(url: &str, network: &NetworkConfig) -> Result<Vec<String>>
+{
+    check_argument("repository URL", url)?;
+
+    let output = Command::new("git")
+        .args(["ls-remote", "--tags", "--refs", "--", url])
+        .envs(network.git_env())
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to run git (is it installed?): {}", e))?;
+
+    if output.status.success() == false
+    {
+        return Err(format!("Failed to list tags of {}: {}", url, String::from_utf8_lossy(&output.stderr).trim()).into());
+    }
+
+    // Format: "<object>\trefs/tags/<name>"
+    Ok(String::from_utf8(output.stdout)?.lines().filter_map(|line| line.split_once("\trefs/tags/")).map(|(_, tag)| tag.to_string()).collect())
+}
+
+/// Rejects user-supplied values that git would parse as an option
+///
+/// URLs and refs are also passed after `--`, but commands like `rev-parse`
+/// take the ref as a revision argument where `--` does not apply.
+///
+/// # Arguments
+///
+/// * `what` - Description of the value for the error message
+/// * `value` - Value passed to git
+fn check_argument(what: &str, value: &str) -> Result<()>
+{
+    if value.starts_with('-') == true
+    {
+        return Err(format!("Invalid {} '{}': must not start with '-'", what, value).into());
+    }
+    Ok(())
+}
+
+/// Runs a git command with inherited stdio so credential prompts reach the user
+///
+/// # Arguments
+///
+/// * `git_dir` - Bare repository to operate on (None for commands like clone)
+/// * `args` - Arguments passed to git
+/// * `git_env` - Extra environment variables (proxy and CA settings)
+fn run_git(git_dir: Option<&Path>, args: &[&str], git_env: &[(&str, String)]) -> Result<()>
+{
+    let mut command = Command::new("git");
+    command.envs(git_env.iter().cloned());
+    if let Some(dir) = git_dir
+    {
+        command.arg("--git-dir").arg(dir);
+    }
+
+    let status = command.args(args).status().map_err(|e| format!("Failed to run git (is it installed?): {}", e))?;
+    if status.success() == false
+    {
+        return Err(format!("git {} failed ({})", args.first().unwrap_or(&""), status).into());
+    }
+    Ok(())
+}
+
+/// Runs a git command in a bare repository and returns its stdout as bytes
+fn git_bytes(git_dir: &Path, args: &[&str]) -> Result<Vec<u8>>
+{
+    let output = Command::new("git")
+        .arg("--git-dir")
+        .arg(git_dir)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to run git (is it installed?): {}", e))?;
+
+    if output.status.success() == false
+    {
+        return Err(format!("git {} failed: {}", args.first().unwrap_or(&""), String::from_utf8_lossy(&output.stderr).trim()).into());
+    }
+    Ok(output.stdout)
+}
+
+/// Runs a git command in a bare repository and returns its stdout as text
+fn git_output(git_dir: &Path, args: &[&str]) -> Result<String>
+{
+    Ok(String::from_utf8(git_bytes(git_dir, args)?)?)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// Runs git in a working tree for test setup
+    fn git(dir: &Path, args: &[&str]) -> String
+    {
+        let output = Command::new("git").arg("-C").arg(dir).args(args).output().expect("git available");
+        assert!(output.status.success(), "git {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    #[test]
+    fn test_parse_git_sources()
+    {
+        let source = GitSource::parse("git+https://example.com/team/templates.git#ref=v1.2.0&path=/templates/v2/").unwrap();
+        assert_eq!(source.url, "https://example.com/team/templates.git");
+        assert_eq!(source.refname.as_deref(), Some("v1.2.0"));
+        assert_eq!(source.path, "templates/v2");
+
+        let source = GitSource::parse("file:///srv/templates.git").unwrap();
+        assert_eq!(source.url, "file:///srv/templates.git");
+        assert_eq!(source.refname, None);
+
+        assert_eq!(GitSource::parse("https://github.com/owner/repo/tree/main"), None);
+    }
+
+    #[test]
+    fn test_option_like_arguments_are_rejected() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let marker = temp_dir.path().join("pwned");
+        let cache = temp_dir.path().join("cache");
+        let dest = temp_dir.path().join("dest");
+
+        let source = GitSource::parse(&format!("git+--upload-pack=touch {}", marker.to_string_lossy())).unwrap();
+        assert!(source.fetch(&cache, &dest, &NetworkConfig::default()).is_err());
+
+        let source = GitSource::parse("file:///srv/templates.git#ref=--output=/tmp/x").unwrap();
+        assert!(source.fetch(&cache, &dest, &NetworkConfig::default()).is_err());
+
+        assert!(list_remote_tags("--upload-pack=touch /tmp/x", &NetworkConfig::default()).is_err());
+        assert!(marker.exists() == false);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fetch_from_local_bare_repository() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let work = temp_dir.path().join("work");
+        fs::create_dir_all(work.join("templates/sub"))?;
+
+        git(&work, &["init", "--quiet", "--initial-branch=main"]);
+        git(&work, &["config", "user.email", "test@example.com"]);
+        git(&work, &["config", "user.name", "Test"]);
+
+        fs::write(work.join("templates/templates.yml"), "version: 2\n")?;
+        fs::write(work.join("templates/sub/file.md"), "first")?;
+        fs::write(work.join("README.md"), "outside template path")?;
+        git(&work, &["add", "."]);
+        git(&work, &["commit", "--quiet", "-m", "first"]);
+        let first = git(&work, &["rev-parse", "HEAD"]);
+
+        fs::write(work.join("templates/sub/file.md"), "second")?;
+        git(&work, &["commit", "--quiet", "-am", "second"]);
+
+        let bare = temp_dir.path().join("templates.git");
+        git(temp_dir.path(), &["clone", "--quiet", "--bare", &work.to_string_lossy(), &bare.to_string_lossy()]);
+        let url = format!("file://{}", bare.to_string_lossy().replace('\\', "/"));
+        let cache = temp_dir.path().join("cache");
+
+        // Latest commit on the default branch
+        let dest = temp_dir.path().join("latest");
+        let source = GitSource::parse(&format!("{}#path=templates", url)).unwrap();
+        source.fetch(&cache, &dest, &NetworkConfig::default())?;
+        assert_eq!(fs::read_to_string(dest.join("sub/file.md"))?, "second");
+        assert!(dest.join("templates.yml").exists());
+        assert!(dest.join("README.md").exists() == false);
+
+        // Pinned to the first commit, reusing the cached clone
+        let dest = temp_dir.path().join("pinned");
+        let source = GitSource::parse(&format!("{}#ref={}&path=templates", url, first)).unwrap();
+        let commit = source.fetch(&cache, &dest, &NetworkConfig::default())?;
+        assert_eq!(commit, first);
+        assert_eq!(fs::read_to_string(dest.join("sub/file.md"))?, "first");
+
+        // Remote refs resolve to commits without a clone
+        git(&work, &["tag", "-a", "-m", "release", "v1.0.0", &first]);
+        git(temp_dir.path(), &["--git-dir", &bare.to_string_lossy(), "fetch", "--quiet", &work.to_string_lossy(), "refs/tags/*:refs/tags/*"]);
+        let latest = git(&work, &["rev-parse", "HEAD"]);
+        assert_eq!(resolve_remote_ref(&url, "main", &NetworkConfig::default())?, latest);
+        assert_eq!(resolve_remote_ref(&url, "v1.0.0", &NetworkConfig::default())?, first);
+        assert!(resolve_remote_ref(&url, "missing", &NetworkConfig::default()).is_err());
+        assert!(is_commit_sha(&first));
+        assert!(is_commit_sha("main") == false);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+//! HTTP validator cache for template downloads
+//!
+//! Stores the `ETag` and `Last-Modified` response headers of every downloaded
+//! template file in `http_cache.json` inside the global template store, so the
+//! next update can send conditional requests and skip unchanged files.
+//!
+//! Files of hosted repositories are keyed by repository and path rather than by
+//! URL, because their raw URLs name the commit and change whenever the branch
+//! moves.
+
+use std::{collections::HashMap, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Result, source_provider::SourceLocation};
+
+/// File name of the validator cache inside the template store
+pub const HTTP_CACHE_FILE_NAME: &str = "http_cache.json";
+
+/// Validators returned by the server for a single file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CacheEntry
+{
+    /// URL the validators were returned for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url:           Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub etag:          Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>
+}
+
+impl CacheEntry
+{
+    /// Returns true if the entry holds no validator
+    pub fn is_empty(&self) -> bool
+    {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+}
+
+/// Validators for all downloaded files, keyed by URL or by repository file (see `repository_key`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct HttpCache
+{
+    entries: HashMap<String, CacheEntry>
+}
+
+impl HttpCache
+{
+    /// Loads the cache from a template store
+    ///
+    /// A missing or unreadable cache is treated as empty (every file is downloaded).
+    pub fn load(store_dir: &Path) -> Self
+    {
+        fs::read_to_string(store_dir.join(HTTP_CACHE_FILE_NAME)).ok().and_then(|content| serde_json::from_str(&content).ok()).unwrap_or_default()
+    }
+
+    /// Writes the cache into a template store
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cache file cannot be written
+    pub fn save(&self, store_dir: &Path) -> Result<()>
+    {
+        fs::write(store_dir.join(HTTP_CACHE_FILE_NAME), serde_json::to_string_pretty(&self.entries)?)?;
+        Ok(())
+    }
+
+    /// Returns the validators stored under a key
+    pub fn get(&self, key: &str) -> Option<&CacheEntry>
+    {
+        self.entries.get(key)
+    }
+
+    /// Returns the validators to send for a URL, stored under a key
+    ///
+    /// `ETag`s identify the content, so they are sent to any URL of the same file.
+    /// `Last-Modified` is only sent back to the URL that returned it.
+    pub fn validators(&self, key: &str, url: &str) -> Option<CacheEntry>
+    {
+        let mut entry = self.get(key)?.clone();
+        if entry.url.as_deref().is_some_and(|stored| stored != url) == true
+        {
+            entry.last_modified = None;
+        }
+        entry.url = None;
+        (entry.is_empty() == false).then_some(entry)
+    }
+
+    /// Stores the validators returned by a URL under a key (entries without validators are ignored)
+    pub fn insert(&mut self, key: &str, url: &str, mut entry: CacheEntry)
+    {
+        if entry.is_empty() == false
+        {
+            entry.url = Some(url.to_string());
+            self.entries.insert(key.to_string(), entry);
+        }
+    }
+}
+
+/// Returns the cache key of a file in a hosted repository, independent of the ref
+///
+/// # Arguments
+///
+/// * `location` - Repository location (the ref is ignored)
+/// * `file` - Path relative to the template directory
+pub fn repository_key(location: &SourceLocation, file: &str) -> String
+{
+    let path = if location.path.is_empty() == true
+    {
+        file.to_string()
+    }
+    else
+    {
+        format!("{}/{}", location.path.trim_end_matches('/'), file)
+    };
+    format!("{}/{}/{}:{}", location.base.trim_end_matches('/'), location.owner, location.repo, path)
+}
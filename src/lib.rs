@@ -3,25 +3,51 @@
 //! This library provides functionality to manage, organize, and maintain
 //! initialization prompts and instruction files for AI coding assistants.
 
+mod archive_source;
 mod bom;
+mod bundle_signature;
 mod config;
+mod credentials;
 mod download_manager;
+mod embedded_templates;
+mod file_merge;
 mod file_tracker;
+mod git_source;
+mod http_cache;
+mod pack_registry;
+mod source_provider;
 mod template_engine;
 mod template_engine_v1;
 mod template_engine_v2;
+mod template_lock;
 mod template_manager;
+mod template_renderer;
+mod template_validator;
+mod template_variables;
 mod utils;
+mod version_range;
 
-pub use bom::BillOfMaterials;
+#[cfg(test)] mod test_support;
+
+pub use archive_source::{ArchiveFormat, ArchiveSource};
+pub use bom::{BillOfMaterials, ConfigError, ConfigErrorKind, FileMode, SUPPORTED_VERSIONS, TemplateConfig};
 pub use config::Config;
-pub use download_manager::DownloadManager;
+pub use download_manager::{DownloadManager, is_network_unavailable};
+pub use file_merge::MergeFormat;
 pub use file_tracker::{FileMetadata, FileStatus, FileTracker};
+pub use git_source::GitSource;
+pub use pack_registry::{PackRegistry, TemplatePack};
+pub use source_provider::{ProviderKind, SourceLocation, SourceProvider};
 pub use template_engine::{TemplateContext, TemplateEngine, UpdateOptions};
 pub use template_engine_v1::TemplateEngineV1;
 pub use template_engine_v2::TemplateEngineV2;
-pub use template_manager::TemplateManager;
+pub use template_lock::{LockedLayer, TemplateLock};
+pub use template_manager::{InitOptions, TemplateManager};
+pub use template_renderer::TemplateRenderer;
+pub use template_validator::{Finding, Severity, ValidationReport, validate_templates};
+pub use template_variables::TemplateVariables;
 pub use utils::{FileActionResponse, confirm_action, copy_dir_all, copy_file_with_mkdir, prompt_file_modification, remove_file_and_cleanup_parents};
+pub use version_range::PinnedSource;
 
 /// Result type used throughout the library
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
@@ -1,9 +1,9 @@
-use std::{fs, io};
+use std::{collections::BTreeMap, fs, io, path::Path};
 
 use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
 use clap_complete::generate;
 use owo_colors::OwoColorize;
-use vibe_check::{Config, Result, TemplateManager};
+use vibe_check::{Config, InitOptions, Result, TemplateConfig, TemplateManager, TemplateVariables, is_network_unavailable, validate_templates};
 
 /// Supported shells for completion generation
 #[derive(Clone, Copy, ValueEnum)]
@@ -35,10 +35,55 @@ fn from(shell: ShellType) -> Self
 #[command(version)]
 struct Cli
 {
+    /// Never access the network (use only the installed templates and local sources)
+    #[arg(long, global = true, default_value = "false")]
+    offline: bool,
+
+    /// Use a template pack for this command instead of the active pack (see `pack list`)
+    #[arg(long, global = true, value_name = "NAME")]
+    pack: Option<String>,
+
     #[command(subcommand)]
     command: Commands
 }
 
+#[derive(Subcommand)]
+enum PackCommands
+{
+    /// Register a template pack and install its templates
+    Add
+    {
+        /// Name of the pack (letters, digits, '-' and '_')
+        name: String,
+
+        /// Path, URL, archive, or git repository to install the pack from
+        #[arg(long)]
+        from: String,
+
+        /// Preview changes without applying them
+        #[arg(long, default_value = "false")]
+        dry_run: bool
+    },
+    /// Unregister a template pack and delete its templates
+    Remove
+    {
+        /// Name of the pack
+        name: String,
+
+        /// Preview changes without applying them
+        #[arg(long, default_value = "false")]
+        dry_run: bool
+    },
+    /// List the default store and all registered template packs
+    List,
+    /// Make a template pack the global default ('default' selects the default store)
+    Use
+    {
+        /// Name of the pack
+        name: String
+    }
+}
+
 #[derive(Subcommand)]
 enum Commands
 {
@@ -61,10 +106,18 @@ enum Commands
         #[arg(long)]
         mission: Option<String>,
 
+        /// Set a template variable, e.g. --var project.name=demo (repeatable, overrides detected values)
+        #[arg(long = "var", value_name = "NAME=VALUE")]
+        vars: Vec<String>,
+
         /// Force overwrite of local files without confirmation
         #[arg(long, default_value = "false")]
         force: bool,
 
+        /// Merge template changes into locally modified files without prompting (conflicts are marked in the file)
+        #[arg(long, default_value = "false", conflicts_with = "force")]
+        merge: bool,
+
         /// Preview changes without applying them
         #[arg(long, default_value = "false")]
         dry_run: bool
@@ -72,10 +125,22 @@ enum Commands
     /// Update global templates from source
     Update
     {
-        /// Path or URL to download/copy templates from
+        /// Path, URL, archive (.tar.gz, .tar.zst, .zip), or git repository (git+https://, git+ssh://, file://) to install templates from
         #[arg(long)]
         from: Option<String>,
 
+        /// Restore the global templates that were replaced by the last update
+        #[arg(long, default_value = "false", conflicts_with = "from")]
+        rollback: bool,
+
+        /// Refuse the update unless the fetched templates match templates.lock exactly
+        #[arg(long, default_value = "false", conflicts_with = "rollback")]
+        locked: bool,
+
+        /// Install the newest release tag matching a semver range (e.g. ^7, 7.1.x) from a git or hosted repository
+        #[arg(long, value_name = "RANGE", conflicts_with_all = ["rollback", "locked"])]
+        version: Option<String>,
+
         /// Preview changes without applying them
         #[arg(long, default_value = "false")]
         dry_run: bool
@@ -121,6 +186,30 @@ enum Commands
     Status,
     /// List available agents and languages
     List,
+    /// Check a template tree (templates.yml and its files) before publishing it
+    Validate
+    {
+        /// Template directory or templates.yml to validate
+        #[arg(default_value = ".")]
+        path: String,
+
+        /// Print the report as JSON
+        #[arg(long, default_value = "false")]
+        json: bool
+    },
+    /// Print the JSON Schema of templates.yml
+    Schema
+    {
+        /// templates.yml format version to print the schema for
+        #[arg(long, default_value = "2")]
+        version: u32
+    },
+    /// Manage named template packs
+    Pack
+    {
+        #[command(subcommand)]
+        command: PackCommands
+    },
     /// Manage configuration
     Config
     {
@@ -146,21 +235,27 @@ enum Commands
 /// Resolves template source URL from CLI argument, config, or default
 ///
 /// Returns (source_url, is_configured, fallback_url).
-/// Priority: CLI `from` argument > config `source.url` > default URL.
+/// Priority: CLI `from` argument > source of the selected pack > config `source.url` > default URL.
+/// The configured fallback only applies to the default template store.
 ///
 /// # Arguments
 ///
 /// * `from` - Optional CLI-provided source URL
-fn resolve_source(from: Option<String>) -> (String, bool, Option<String>)
+/// * `pack_source` - Source of the selected template pack, if any
+fn resolve_source(from: Option<String>, pack_source: Option<String>) -> (String, bool, Option<String>)
 {
     let config = Config::load().ok();
     let configured_source = config.as_ref().and_then(|c| c.get("source.url"));
-    let fallback_source = config.as_ref().and_then(|c| c.get("source.fallback"));
+    let fallback_source = config.as_ref().and_then(|c| c.get("source.fallback")).filter(|_| pack_source.is_none());
 
     let (source, is_configured) = if let Some(from_url) = from
     {
         (from_url, false)
     }
+    else if let Some(pack_url) = pack_source
+    {
+        (pack_url, true)
+    }
     else if let Some(config_url) = configured_source
     {
         (config_url, true)
@@ -173,23 +268,107 @@ fn resolve_source(from: Option<String>) -> (String, bool, Option<String>)
     (source, is_configured, fallback_source)
 }
 
+/// Returns the overlay sources configured in `source.overlays`
+///
+/// Overlays only apply to the default template store, never to template packs.
+fn configured_overlays(manager: &TemplateManager) -> Vec<String>
+{
+    if manager.active_pack().is_some() == true
+    {
+        return Vec::new();
+    }
+
+    Config::load().ok().and_then(|c| c.source.overlays).unwrap_or_default()
+}
+
+/// Handle validate command
+///
+/// Returns the process exit code: 0 if the templates are valid (warnings are
+/// allowed), 1 if errors were found, 2 if templates.yml could not be read.
+fn handle_validate(path: &str, json: bool) -> i32
+{
+    let report = match validate_templates(Path::new(path))
+    {
+        | Ok(report) => report,
+        | Err(e) =>
+        {
+            if json == true
+            {
+                println!("{}", serde_json::json!({ "error": e.to_string() }));
+            }
+            eprintln!("{} {}", "✗".red(), e.to_string().red());
+            return 2;
+        }
+    };
+
+    if json == true
+    {
+        match serde_json::to_string_pretty(&report)
+        {
+            | Ok(output) => println!("{}", output),
+            | Err(e) =>
+            {
+                eprintln!("{} {}", "✗".red(), e.to_string().red());
+                return 2;
+            }
+        }
+    }
+    else
+    {
+        report.print();
+    }
+
+    if report.is_valid() == true
+    {
+        0
+    }
+    else
+    {
+        1
+    }
+}
+
+/// Handle schema command
+fn handle_schema(version: u32) -> Result<()>
+{
+    let schema = TemplateConfig::schema_for_version(version)?;
+    println!("{}", serde_json::to_string_pretty(&schema)?);
+    Ok(())
+}
+
+/// Handle pack command operations
+fn handle_pack(manager: &TemplateManager, command: PackCommands) -> Result<()>
+{
+    match command
+    {
+        | PackCommands::Add { name, from, dry_run } => manager.add_pack(&name, &from, dry_run),
+        | PackCommands::Remove { name, dry_run } => manager.remove_pack(&name, dry_run),
+        | PackCommands::List => manager.list_packs(),
+        | PackCommands::Use { name } => manager.use_pack(&name)
+    }
+}
+
 /// Downloads or copies templates with automatic fallback
 ///
 /// Tries the primary source first. If it fails and a fallback is configured,
-/// retries with the fallback source.
+/// retries with the fallback source. The overlays are applied on top of
+/// whichever base source is used.
 ///
 /// # Arguments
 ///
 /// * `manager` - Template manager to use for download/copy
 /// * `source` - Primary source URL or path
 /// * `fallback` - Optional fallback source URL or path
+/// * `overlays` - Sources layered on top of the base source, in order
+/// * `version` - Optional semver range selecting a release tag of the source
+/// * `locked` - If true, the fetched templates must match templates.lock
 ///
 /// # Errors
 ///
 /// Returns an error if both primary and fallback sources fail
-fn download_with_fallback(manager: &TemplateManager, source: &str, fallback: Option<String>) -> Result<()>
+fn download_with_fallback(manager: &TemplateManager, source: &str, fallback: Option<String>, overlays: &[String], version: Option<&str>, locked: bool) -> Result<()>
 {
-    match manager.download_or_copy_templates(source)
+    match manager.download_or_copy_templates(source, overlays, version, locked)
     {
         | Ok(()) => Ok(()),
         | Err(e) =>
@@ -198,7 +377,7 @@ fn download_with_fallback(manager: &TemplateManager, source: &str, fallback: Opt
             {
                 println!("{} Primary source failed: {}", "!".yellow(), e);
                 println!("{} Trying fallback source: {}", "→".blue(), fallback_url.yellow());
-                manager.download_or_copy_templates(&fallback_url)
+                manager.download_or_copy_templates(&fallback_url, overlays, version, locked)
             }
             else
             {
@@ -234,6 +413,30 @@ fn resolve_mission_content(value: &str) -> Result<String>
     }
 }
 
+/// Collects the template variables set in the config and with `--var`
+///
+/// Values given with `--var` override the `vars.<name>` config keys.
+///
+/// # Arguments
+///
+/// * `assignments` - `name=value` pairs from the command line
+///
+/// # Errors
+///
+/// Returns an error if an assignment is malformed or the config cannot be loaded
+fn resolve_template_vars(assignments: &[String]) -> Result<BTreeMap<String, String>>
+{
+    let mut vars = Config::load()?.vars;
+
+    for assignment in assignments
+    {
+        let (name, value) = TemplateVariables::parse_assignment(assignment)?;
+        vars.insert(name, value);
+    }
+
+    Ok(vars)
+}
+
 /// Handle config command operations
 fn handle_config(key: Option<String>, value: Option<String>, list: bool, unset: Option<String>) -> Result<()>
 {
@@ -323,7 +526,7 @@ fn main()
 {
     let cli = Cli::parse();
 
-    let manager = match TemplateManager::new()
+    let mut manager = match TemplateManager::new()
     {
         | Ok(m) => m,
         | Err(e) =>
@@ -333,9 +536,28 @@ fn main()
         }
     };
 
+    // A config file that cannot be loaded is reported by the commands that need it
+    let settings = Config::load().ok();
+
+    // --offline or network.offline disables all network access
+    let offline = cli.offline == true || settings.as_ref().is_some_and(|c| c.network.offline());
+    manager.set_offline(offline);
+    if let Some(settings) = settings
+    {
+        manager.set_config(settings);
+    }
+
+    // --pack selects a template pack for this command only
+    if let Some(ref pack) = cli.pack &&
+        let Err(e) = manager.select_pack(pack)
+    {
+        eprintln!("{} {}", "✗".red(), e.to_string().red());
+        std::process::exit(1);
+    }
+
     let result = match cli.command
     {
-        | Commands::Init { lang, agent, no_lang, mission, force, dry_run } =>
+        | Commands::Init { lang, agent, no_lang, mission, vars, force, merge, dry_run } =>
         {
             // --lang and --no-lang are mutually exclusive
             if lang.is_some() == true && no_lang == true
@@ -373,8 +595,45 @@ fn main()
                 None
             };
 
+            // Template variables: config values first, --var overrides them
+            let template_vars = match resolve_template_vars(&vars)
+            {
+                | Ok(template_vars) => template_vars,
+                | Err(e) =>
+                {
+                    eprintln!("{} {}", "✗".red(), e.to_string().red());
+                    std::process::exit(1);
+                }
+            };
+
+            // Offline: never download, the installed templates must be complete
+            if offline == true
+            {
+                // First run without a store: fall back to the templates embedded in the binary
+                if manager.has_global_templates() == false && manager.active_pack().is_none() == true
+                {
+                    if dry_run == true
+                    {
+                        println!("{} Global templates not found (would install the embedded templates in non-dry-run mode)", "→".yellow());
+                        return;
+                    }
+
+                    println!("{} Global templates not found, using the templates embedded in vibe-check", "→".blue());
+                    if let Err(e) = manager.install_embedded_templates()
+                    {
+                        eprintln!("{} Failed to install the embedded templates: {}", "✗".red(), e);
+                        std::process::exit(1);
+                    }
+                }
+
+                if let Err(e) = manager.check_offline_store()
+                {
+                    eprintln!("{} {}", "✗".red(), e);
+                    std::process::exit(1);
+                }
+            }
             // Check if global templates exist, download if not
-            if manager.has_global_templates() == false
+            else if manager.has_global_templates() == false
             {
                 if dry_run == true
                 {
@@ -382,18 +641,35 @@ fn main()
                     return;
                 }
 
-                let (source, is_configured, fallback) = resolve_source(None);
+                let (source, is_configured, fallback) = resolve_source(None, manager.pack_source());
 
-                if is_configured == true
+                if let Some(pack) = manager.active_pack()
+                {
+                    println!("{} Using template pack {}", "→".blue(), pack.green());
+                }
+                else if is_configured == true
                 {
                     println!("{} Using configured source", "→".blue());
                 }
                 println!("{} Global templates not found, downloading from {}", "→".blue(), source.yellow());
 
-                if let Err(e) = download_with_fallback(&manager, &source, fallback)
+                if let Err(e) = download_with_fallback(&manager, &source, fallback, &configured_overlays(&manager), None, false)
                 {
-                    eprintln!("{} Failed to download global templates: {}", "✗".red(), e);
-                    std::process::exit(1);
+                    // Only a network that cannot be reached falls back; a bad source or a failed check is an error
+                    if manager.active_pack().is_some() == true || is_network_unavailable(e.as_ref()) == false
+                    {
+                        eprintln!("{} Failed to download global templates: {}", "✗".red(), e);
+                        std::process::exit(1);
+                    }
+
+                    // Last resort: the templates embedded in the binary
+                    println!("{} Cannot reach the template source: {}", "!".yellow(), e);
+                    println!("{} Falling back to the templates embedded in vibe-check", "→".blue());
+                    if let Err(e) = manager.install_embedded_templates()
+                    {
+                        eprintln!("{} Failed to install the embedded templates: {}", "✗".red(), e);
+                        std::process::exit(1);
+                    }
                 }
             }
 
@@ -447,15 +723,33 @@ fn main()
             {
                 println!("{} Initializing project for {}", "→".blue(), agent.as_ref().unwrap().green());
             }
-            manager.update(lang.as_deref(), agent.as_deref(), no_lang, resolved_mission.as_deref(), force, dry_run)
+            manager.update(&InitOptions {
+                lang: lang.as_deref(),
+                agent: agent.as_deref(),
+                no_lang,
+                mission: resolved_mission.as_deref(),
+                force,
+                dry_run,
+                merge,
+                vars: &template_vars
+            })
         }
-        | Commands::Update { from, dry_run } =>
+        | Commands::Update { from, rollback, locked, version, dry_run } =>
         {
-            let (source, is_configured, fallback) = resolve_source(from);
+            let (source, is_configured, fallback) = resolve_source(from, manager.pack_source());
+            let overlays = configured_overlays(&manager);
 
-            if dry_run == true
+            if rollback == true
+            {
+                manager.rollback(dry_run)
+            }
+            else if dry_run == true
             {
-                if is_configured == true
+                if let Some(pack) = manager.active_pack()
+                {
+                    println!("{} Using template pack {}", "→".blue(), pack.green());
+                }
+                else if is_configured == true
                 {
                     println!("{} Using configured source", "→".blue());
                 }
@@ -464,19 +758,31 @@ fn main()
                 {
                     println!("{} Fallback source configured: {}", "→".blue(), fallback_url.yellow());
                 }
+                for overlay in &overlays
+                {
+                    println!("{} Overlay: {}", "→".blue(), overlay.yellow());
+                }
+                if let Some(ref range) = version
+                {
+                    println!("{} Version range: {}", "→".blue(), range.yellow());
+                }
                 println!("{} Templates would be downloaded to: {}", "→".blue(), manager.get_config_dir().display().to_string().yellow());
                 println!("\n{} Dry run complete. No files were modified.", "✓".green());
                 Ok(())
             }
             else
             {
-                if is_configured == true
+                if let Some(pack) = manager.active_pack()
+                {
+                    println!("{} Using template pack {}", "→".blue(), pack.green());
+                }
+                else if is_configured == true
                 {
                     println!("{} Using configured source", "→".blue());
                 }
                 println!("{} Updating global templates from {}", "→".blue(), source.yellow());
 
-                download_with_fallback(&manager, &source, fallback)
+                download_with_fallback(&manager, &source, fallback, &overlays, version.as_deref(), locked)
             }
         }
         | Commands::Purge { force, dry_run } => manager.purge(force, dry_run),
@@ -505,6 +811,9 @@ fn main()
         }
         | Commands::Status => manager.status(),
         | Commands::List => manager.list(),
+        | Commands::Validate { path, json } => std::process::exit(handle_validate(&path, json)),
+        | Commands::Schema { version } => handle_schema(version),
+        | Commands::Pack { command } => handle_pack(&manager, command),
         | Commands::Config { key, value, list, unset } => handle_config(key, value, list, unset)
     };
 
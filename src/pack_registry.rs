@@ -0,0 +1,229 @@
+//! Registry of named template packs for vibe-check
+//!
+//! A pack is an independent template store (for example embedded C, backend Rust,
+//! or iOS templates) kept in `packs/<name>` next to the default store. The
+//! registry in `packs.yml` records the source of every pack and which pack is
+//! active. Without an active pack the default store (`templates`) is used.
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+
+/// File name of the pack registry inside the vibe-check data directory
+pub const REGISTRY_FILE_NAME: &str = "packs.yml";
+
+/// Name of the default template store (always available, cannot be removed)
+pub const DEFAULT_PACK: &str = "default";
+
+/// A registered template pack
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TemplatePack
+{
+    /// Source the pack is installed and updated from
+    pub source: String,
+    /// Time the pack was added (RFC 3339)
+    pub added:  String
+}
+
+/// Contents of `packs.yml`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PackRegistry
+{
+    /// Name of the active pack (None means the default store)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active: Option<String>,
+    /// Registered packs, keyed by name
+    #[serde(default)]
+    pub packs:  BTreeMap<String, TemplatePack>
+}
+
+impl PackRegistry
+{
+    /// Loads the registry from the vibe-check data directory
+    ///
+    /// Returns an empty registry if `packs.yml` does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the registry cannot be read or parsed
+    pub fn load(data_dir: &Path) -> Result<Self>
+    {
+        let path = data_dir.join(REGISTRY_FILE_NAME);
+        if path.exists() == false
+        {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path)?;
+        serde_yaml::from_str(&content).map_err(|e| format!("Failed to parse {}: {} (fix or delete it to reset the registered packs)", path.display(), e).into())
+    }
+
+    /// Writes the registry into the vibe-check data directory
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the registry cannot be written
+    pub fn save(&self, data_dir: &Path) -> Result<()>
+    {
+        fs::create_dir_all(data_dir)?;
+        fs::write(data_dir.join(REGISTRY_FILE_NAME), serde_yaml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Returns the pack registered under a name
+    pub fn get(&self, name: &str) -> Option<&TemplatePack>
+    {
+        self.packs.get(name)
+    }
+
+    /// Returns the pack registered under a name, or an error naming the unknown pack
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pack is not registered
+    pub fn require(&self, name: &str) -> Result<&TemplatePack>
+    {
+        self.packs.get(name).ok_or_else(|| unknown_pack(name))
+    }
+
+    /// Checks that a new pack can be registered under a name
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is invalid or already registered
+    pub fn check_new(&self, name: &str) -> Result<()>
+    {
+        validate_pack_name(name)?;
+
+        if self.packs.contains_key(name) == true
+        {
+            return Err(format!("Template pack '{}' already exists (use 'vibe-check update --pack {}' to update it)", name, name).into());
+        }
+
+        Ok(())
+    }
+
+    /// Registers a new pack
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is invalid or already registered
+    pub fn add(&mut self, name: &str, source: &str) -> Result<()>
+    {
+        self.check_new(name)?;
+
+        self.packs.insert(name.to_string(), TemplatePack { source: source.to_string(), added: chrono::Utc::now().to_rfc3339() });
+        Ok(())
+    }
+
+    /// Unregisters a pack, switching back to the default store if it was active
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pack is not registered
+    pub fn remove(&mut self, name: &str) -> Result<TemplatePack>
+    {
+        let pack = self.packs.remove(name).ok_or_else(|| unknown_pack(name))?;
+
+        if self.active.as_deref() == Some(name)
+        {
+            self.active = None;
+        }
+
+        Ok(pack)
+    }
+
+    /// Makes a pack the active one (`default` selects the default store)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pack is not registered
+    pub fn set_active(&mut self, name: &str) -> Result<()>
+    {
+        if name == DEFAULT_PACK
+        {
+            self.active = None;
+            return Ok(());
+        }
+
+        self.require(name)?;
+        self.active = Some(name.to_string());
+        Ok(())
+    }
+}
+
+/// Validates a pack name
+///
+/// Names are used as directory names, so only ASCII letters, digits, `-`, and `_` are allowed.
+///
+/// # Errors
+///
+/// Returns an error if the name is empty, reserved, or contains other characters
+pub fn validate_pack_name(name: &str) -> Result<()>
+{
+    if name == DEFAULT_PACK
+    {
+        return Err(format!("'{}' is reserved for the default template store", DEFAULT_PACK).into());
+    }
+
+    if name.is_empty() == true || name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') == false || name.starts_with('-')
+    {
+        return Err(format!("Invalid template pack name '{}' (use letters, digits, '-' and '_')", name).into());
+    }
+
+    Ok(())
+}
+
+/// Error for a pack name that is not in the registry
+fn unknown_pack(name: &str) -> Box<dyn std::error::Error>
+{
+    format!("Unknown template pack '{}' (see 'vibe-check pack list')", name).into()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_pack_registry() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let mut registry = PackRegistry::load(temp_dir.path())?;
+        assert_eq!(registry, PackRegistry::default());
+
+        registry.add("embedded-c", "https://example.com/embedded")?;
+        registry.add("ios", "/srv/templates/ios")?;
+        assert!(registry.add("ios", "/elsewhere").is_err());
+        assert!(registry.add("default", "/elsewhere").is_err());
+        assert!(registry.add("../escape", "/elsewhere").is_err());
+        assert!(registry.add("v1.2", "/elsewhere").is_err());
+
+        registry.set_active("ios")?;
+        assert!(registry.set_active("missing").is_err());
+        assert!(registry.require("missing").unwrap_err().to_string().contains("Unknown template pack 'missing'"));
+        assert!(registry.check_new("ios").is_err());
+        assert!(registry.check_new("backend").is_ok());
+        registry.save(temp_dir.path())?;
+
+        let mut loaded = PackRegistry::load(temp_dir.path())?;
+        assert_eq!(loaded, registry);
+        assert_eq!(loaded.active.as_deref(), Some("ios"));
+        assert_eq!(loaded.get("embedded-c").map(|p| p.source.as_str()), Some("https://example.com/embedded"));
+
+        // Removing the active pack switches back to the default store
+        loaded.remove("ios")?;
+        assert_eq!(loaded.active, None);
+        assert!(loaded.remove("ios").is_err());
+
+        loaded.set_active("embedded-c")?;
+        loaded.set_active(DEFAULT_PACK)?;
+        assert_eq!(loaded.active, None);
+
+        Ok(())
+    }
+}
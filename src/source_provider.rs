@@ -0,0 +1,556 @@
+//! Source provider support for vibe-check
+//!
+//! Translates repository browse URLs from the supported git hosting services
+//! (GitHub, GitLab, Gitea/Forgejo, Bitbucket) into raw file URLs that can be
+//! downloaded by the `DownloadManager`, and into clone URLs for listing tags.
+
+use std::fmt;
+
+use crate::Result;
+
+/// Location of a template directory inside a hosted repository
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceLocation
+{
+    /// Scheme and host of the hosting service (e.g. `https://gitlab.example.com`)
+    pub base:    String,
+    /// Repository owner, workspace, or (possibly nested) group path
+    pub owner:   String,
+    /// Repository name
+    pub repo:    String,
+    /// Branch, tag, or commit
+    pub refname: String,
+    /// Path of the template directory within the repository (may be empty)
+    pub path:    String
+}
+
+/// Supported hosting services for template sources
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProviderKind
+{
+    GitHub,
+    GitLab,
+    Gitea,
+    Bitbucket
+}
+
+impl ProviderKind
+{
+    /// Parses a provider name as used by the `source.provider` config key
+    ///
+    /// Returns `Ok(None)` for `auto` (detect provider from the URL).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is not a known provider
+    pub fn from_name(name: &str) -> Result<Option<Self>>
+    {
+        match name.to_lowercase().as_str()
+        {
+            | "auto" => Ok(None),
+            | "github" => Ok(Some(Self::GitHub)),
+            | "gitlab" => Ok(Some(Self::GitLab)),
+            | "gitea" | "forgejo" => Ok(Some(Self::Gitea)),
+            | "bitbucket" => Ok(Some(Self::Bitbucket)),
+            | _ => Err(format!("Unknown source provider: {} (expected auto, github, gitlab, gitea, forgejo, or bitbucket)", name).into())
+        }
+    }
+
+    /// Detects the provider for a URL
+    ///
+    /// Well-known hosts are matched first (github.com, gitlab.com, bitbucket.org,
+    /// codeberg.org, or hosts containing the provider name). For self-managed
+    /// instances on other hosts the URL shape is used instead:
+    /// `/-/tree/` (GitLab), `/src/branch|tag|commit/` (Gitea/Forgejo),
+    /// `/src/` (Bitbucket), `/tree/` or `/blob/` (GitHub Enterprise).
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - Browse URL of the template directory
+    pub fn detect(url: &str) -> Option<Self>
+    {
+        let host = split_url(url)?.1.to_lowercase();
+
+        if host == "github.com" || host.contains("github")
+        {
+            return Some(Self::GitHub);
+        }
+        if host.contains("gitlab")
+        {
+            return Some(Self::GitLab);
+        }
+        if host.contains("bitbucket")
+        {
+            return Some(Self::Bitbucket);
+        }
+        if host == "codeberg.org" || host.contains("gitea") || host.contains("forgejo")
+        {
+            return Some(Self::Gitea);
+        }
+
+        if url.contains("/-/tree/") || url.contains("/-/blob/")
+        {
+            Some(Self::GitLab)
+        }
+        else if url.contains("/src/branch/") || url.contains("/src/tag/") || url.contains("/src/commit/")
+        {
+            Some(Self::Gitea)
+        }
+        else if url.contains("/src/")
+        {
+            Some(Self::Bitbucket)
+        }
+        else if url.contains("/tree/") || url.contains("/blob/")
+        {
+            Some(Self::GitHub)
+        }
+        else
+        {
+            None
+        }
+    }
+
+    /// Determines the provider for a URL, preferring the configured one
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - Browse URL of the template directory
+    /// * `configured` - Value of the `source.provider` config key
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configured provider is invalid or detection fails
+    pub fn resolve(url: &str, configured: Option<&str>) -> Result<Self>
+    {
+        let configured = match configured
+        {
+            | Some(name) => Self::from_name(name)?,
+            | None => None
+        };
+
+        match configured.or_else(|| Self::detect(url))
+        {
+            | Some(kind) => Ok(kind),
+            | None =>
+                Err(format!("Could not detect the hosting provider for {}. Set one with: vibe-check config source.provider <github|gitlab|gitea|bitbucket>", url)
+                    .into()),
+        }
+    }
+
+    /// Returns the provider implementation for this kind
+    pub fn provider(self) -> &'static dyn SourceProvider
+    {
+        match self
+        {
+            | Self::GitHub => &GitHubProvider,
+            | Self::GitLab => &GitLabProvider,
+            | Self::Gitea => &GiteaProvider,
+            | Self::Bitbucket => &BitbucketProvider
+        }
+    }
+}
+
+impl fmt::Display for ProviderKind
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        let name = match self
+        {
+            | Self::GitHub => "GitHub",
+            | Self::GitLab => "GitLab",
+            | Self::Gitea => "Gitea/Forgejo",
+            | Self::Bitbucket => "Bitbucket"
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// URL handling for a repository hosting service
+///
+/// Implementations parse the service's browse URLs and construct raw file URLs.
+/// Providers are shared between download worker threads.
+pub trait SourceProvider: Sync
+{
+    /// Example of the browse URL format accepted by this provider (used in error messages)
+    fn url_format(&self) -> &'static str;
+
+    /// Parses a browse URL into a source location
+    ///
+    /// Returns None if the URL does not match the provider's format.
+    fn parse_url(&self, url: &str) -> Option<SourceLocation>;
+
+    /// Builds the raw download URL for a file relative to the template directory
+    fn raw_file_url(&self, location: &SourceLocation, file: &str) -> String;
+
+    /// Builds the browse URL of the template directory (the inverse of `parse_url`)
+    fn browse_url(&self, location: &SourceLocation) -> String;
+
+    /// Returns the ref used in this provider's URLs for a tag
+    fn tag_refname(&self, tag: &str) -> String
+    {
+        tag.to_string()
+    }
+
+    /// Returns the ref used in this provider's URLs for a commit
+    fn commit_refname(&self, commit: &str) -> String
+    {
+        commit.to_string()
+    }
+
+    /// Returns the branch, tag, or commit name of a ref taken from this provider's URLs
+    fn ref_name<'a>(&self, refname: &'a str) -> &'a str
+    {
+        refname
+    }
+
+    /// Builds the URL git uses to clone the repository
+    fn clone_url(&self, location: &SourceLocation) -> String
+    {
+        format!("{}/{}/{}.git", location.base, location.owner, location.repo)
+    }
+
+    /// Environment variables checked (in order) for an access token
+    fn token_env_vars(&self) -> &'static [&'static str];
+
+    /// Builds the header (name and value) that authenticates a raw file request with an access token
+    fn auth_header(&self, token: &str) -> (&'static str, String)
+    {
+        ("Authorization", format!("Bearer {}", token))
+    }
+}
+
+/// GitHub and GitHub Enterprise
+///
+/// `https://github.com/owner/repo/tree/branch/path`
+pub struct GitHubProvider;
+
+impl SourceProvider for GitHubProvider
+{
+    fn url_format(&self) -> &'static str
+    {
+        "https://github.com/owner/repo/tree/branch/path"
+    }
+
+    fn parse_url(&self, url: &str) -> Option<SourceLocation>
+    {
+        let (scheme, host, segments) = split_url(url)?;
+
+        if segments.len() < 4 || (segments[2] != "tree" && segments[2] != "blob")
+        {
+            return None;
+        }
+
+        Some(SourceLocation {
+            base:    format!("{}://{}", scheme, host),
+            owner:   segments[0].to_string(),
+            repo:    segments[1].to_string(),
+            refname: segments[3].to_string(),
+            path:    segments[4..].join("/")
+        })
+    }
+
+    fn raw_file_url(&self, location: &SourceLocation, file: &str) -> String
+    {
+        // github.com serves raw content from a separate host; Enterprise uses /raw/ on the same host
+        let prefix = if location.base.ends_with("://github.com")
+        {
+            format!("https://raw.githubusercontent.com/{}/{}/{}", location.owner, location.repo, location.refname)
+        }
+        else
+        {
+            format!("{}/{}/{}/raw/{}", location.base, location.owner, location.repo, location.refname)
+        };
+        join_url(&prefix, &location.path, file)
+    }
+
+    fn browse_url(&self, location: &SourceLocation) -> String
+    {
+        join_path(&format!("{}/{}/{}/tree/{}", location.base, location.owner, location.repo, location.refname), &location.path)
+    }
+
+    fn token_env_vars(&self) -> &'static [&'static str]
+    {
+        &["GITHUB_TOKEN", "GH_TOKEN"]
+    }
+}
+
+/// GitLab (gitlab.com and self-managed instances, including nested groups)
+///
+/// `https://gitlab.com/group/subgroup/repo/-/tree/branch/path`
+pub struct GitLabProvider;
+
+impl SourceProvider for GitLabProvider
+{
+    fn url_format(&self) -> &'static str
+    {
+        "https://gitlab.com/group/repo/-/tree/branch/path"
+    }
+
+    fn parse_url(&self, url: &str) -> Option<SourceLocation>
+    {
+        let (scheme, host, segments) = split_url(url)?;
+
+        let dash_idx = segments.iter().position(|&s| s == "-")?;
+        if dash_idx < 2 || segments.len() < dash_idx + 3 || (segments[dash_idx + 1] != "tree" && segments[dash_idx + 1] != "blob")
+        {
+            return None;
+        }
+
+        Some(SourceLocation {
+            base:    format!("{}://{}", scheme, host),
+            owner:   segments[..dash_idx - 1].join("/"),
+            repo:    segments[dash_idx - 1].to_string(),
+            refname: segments[dash_idx + 2].to_string(),
+            path:    segments[dash_idx + 3..].join("/")
+        })
+    }
+
+    fn raw_file_url(&self, location: &SourceLocation, file: &str) -> String
+    {
+        let prefix = format!("{}/{}/{}/-/raw/{}", location.base, location.owner, location.repo, location.refname);
+        join_url(&prefix, &location.path, file)
+    }
+
+    fn browse_url(&self, location: &SourceLocation) -> String
+    {
+        join_path(&format!("{}/{}/{}/-/tree/{}", location.base, location.owner, location.repo, location.refname), &location.path)
+    }
+
+    fn token_env_vars(&self) -> &'static [&'static str]
+    {
+        &["GITLAB_TOKEN"]
+    }
+
+    fn auth_header(&self, token: &str) -> (&'static str, String)
+    {
+        // Raw file downloads do not accept personal access tokens as bearer tokens
+        ("PRIVATE-TOKEN", token.to_string())
+    }
+}
+
+/// Gitea and Forgejo (including Codeberg)
+///
+/// `https://codeberg.org/owner/repo/src/branch/main/path`
+pub struct GiteaProvider;
+
+impl SourceProvider for GiteaProvider
+{
+    fn url_format(&self) -> &'static str
+    {
+        "https://codeberg.org/owner/repo/src/branch/main/path"
+    }
+
+    fn parse_url(&self, url: &str) -> Option<SourceLocation>
+    {
+        let (scheme, host, segments) = split_url(url)?;
+
+        if segments.len() < 5 || segments[2] != "src" || matches!(segments[3], "branch" | "tag" | "commit") == false
+        {
+            return None;
+        }
+
+        // The ref kind (branch/tag/commit) is kept as part of the ref so raw URLs stay unambiguous
+        Some(SourceLocation {
+            base:    format!("{}://{}", scheme, host),
+            owner:   segments[0].to_string(),
+            repo:    segments[1].to_string(),
+            refname: format!("{}/{}", segments[3], segments[4]),
+            path:    segments[5..].join("/")
+        })
+    }
+
+    fn raw_file_url(&self, location: &SourceLocation, file: &str) -> String
+    {
+        let prefix = format!("{}/{}/{}/raw/{}", location.base, location.owner, location.repo, location.refname);
+        join_url(&prefix, &location.path, file)
+    }
+
+    fn browse_url(&self, location: &SourceLocation) -> String
+    {
+        join_path(&format!("{}/{}/{}/src/{}", location.base, location.owner, location.repo, location.refname), &location.path)
+    }
+
+    fn tag_refname(&self, tag: &str) -> String
+    {
+        format!("tag/{}", tag)
+    }
+
+    fn commit_refname(&self, commit: &str) -> String
+    {
+        format!("commit/{}", commit)
+    }
+
+    fn ref_name<'a>(&self, refname: &'a str) -> &'a str
+    {
+        refname.split_once('/').map_or(refname, |(_, name)| name)
+    }
+
+    fn token_env_vars(&self) -> &'static [&'static str]
+    {
+        &["GITEA_TOKEN", "FORGEJO_TOKEN"]
+    }
+
+    fn auth_header(&self, token: &str) -> (&'static str, String)
+    {
+        ("Authorization", format!("token {}", token))
+    }
+}
+
+/// Bitbucket Cloud
+///
+/// `https://bitbucket.org/workspace/repo/src/branch/path`
+pub struct BitbucketProvider;
+
+impl SourceProvider for BitbucketProvider
+{
+    fn url_format(&self) -> &'static str
+    {
+        "https://bitbucket.org/workspace/repo/src/branch/path"
+    }
+
+    fn parse_url(&self, url: &str) -> Option<SourceLocation>
+    {
+        let (scheme, host, segments) = split_url(url)?;
+
+        if segments.len() < 4 || segments[2] != "src"
+        {
+            return None;
+        }
+
+        Some(SourceLocation {
+            base:    format!("{}://{}", scheme, host),
+            owner:   segments[0].to_string(),
+            repo:    segments[1].to_string(),
+            refname: segments[3].to_string(),
+            path:    segments[4..].join("/")
+        })
+    }
+
+    fn raw_file_url(&self, location: &SourceLocation, file: &str) -> String
+    {
+        let prefix = format!("{}/{}/{}/raw/{}", location.base, location.owner, location.repo, location.refname);
+        join_url(&prefix, &location.path, file)
+    }
+
+    fn browse_url(&self, location: &SourceLocation) -> String
+    {
+        join_path(&format!("{}/{}/{}/src/{}", location.base, location.owner, location.repo, location.refname), &location.path)
+    }
+
+    fn token_env_vars(&self) -> &'static [&'static str]
+    {
+        &["BITBUCKET_TOKEN"]
+    }
+}
+
+/// Splits a URL into scheme, host (including port), and non-empty path segments
+///
+/// Query strings and fragments are discarded.
+fn split_url(url: &str) -> Option<(&str, &str, Vec<&str>)>
+{
+    let (scheme, rest) = url.split_once("://")?;
+    let rest = rest.split(['?', '#']).next().unwrap_or("");
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+
+    if host.is_empty() == true
+    {
+        return None;
+    }
+
+    let segments = path.split('/').filter(|s| s.is_empty() == false).collect();
+    Some((scheme, host, segments))
+}
+
+/// Returns the lowercase host (including port) of a URL
+pub(crate) fn url_host(url: &str) -> Option<String>
+{
+    split_url(url).map(|(_, host, _)| host.to_lowercase())
+}
+
+/// Joins a raw URL prefix, the template directory path, and a file path
+fn join_url(prefix: &str, path: &str, file: &str) -> String
+{
+    if path.is_empty() == true
+    {
+        format!("{}/{}", prefix, file)
+    }
+    else
+    {
+        format!("{}/{}/{}", prefix, path, file)
+    }
+}
+
+/// Appends the template directory path to a browse URL prefix
+fn join_path(prefix: &str, path: &str) -> String
+{
+    if path.is_empty() == true
+    {
+        prefix.to_string()
+    }
+    else
+    {
+        format!("{}/{}", prefix, path)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_detect_provider()
+    {
+        assert_eq!(ProviderKind::detect("https://github.com/o/r/tree/main/templates"), Some(ProviderKind::GitHub));
+        assert_eq!(ProviderKind::detect("https://gitlab.com/g/r/-/tree/main"), Some(ProviderKind::GitLab));
+        assert_eq!(ProviderKind::detect("https://git.example.com/g/sub/r/-/tree/main/t"), Some(ProviderKind::GitLab));
+        assert_eq!(ProviderKind::detect("https://codeberg.org/o/r/src/branch/main"), Some(ProviderKind::Gitea));
+        assert_eq!(ProviderKind::detect("https://git.example.com/o/r/src/tag/v1/t"), Some(ProviderKind::Gitea));
+        assert_eq!(ProviderKind::detect("https://bitbucket.org/w/r/src/main/t"), Some(ProviderKind::Bitbucket));
+        assert_eq!(ProviderKind::detect("https://example.com/templates"), None);
+    }
+
+    #[test]
+    fn test_raw_urls()
+    {
+        let github = ProviderKind::GitHub.provider();
+        let loc = github.parse_url("https://github.com/owner/repo/tree/develop/templates/v2").unwrap();
+        assert_eq!(github.raw_file_url(&loc, "templates.yml"), "https://raw.githubusercontent.com/owner/repo/develop/templates/v2/templates.yml");
+
+        let gitlab = ProviderKind::GitLab.provider();
+        let loc = gitlab.parse_url("https://gitlab.example.com/team/sub/repo/-/tree/main/templates").unwrap();
+        assert_eq!(loc.owner, "team/sub");
+        assert_eq!(gitlab.raw_file_url(&loc, "a/b.md"), "https://gitlab.example.com/team/sub/repo/-/raw/main/templates/a/b.md");
+
+        let gitea = ProviderKind::Gitea.provider();
+        let loc = gitea.parse_url("https://codeberg.org/owner/repo/src/branch/main").unwrap();
+        assert_eq!(gitea.raw_file_url(&loc, "templates.yml"), "https://codeberg.org/owner/repo/raw/branch/main/templates.yml");
+
+        let bitbucket = ProviderKind::Bitbucket.provider();
+        let loc = bitbucket.parse_url("https://bitbucket.org/ws/repo/src/main/templates").unwrap();
+        assert_eq!(bitbucket.raw_file_url(&loc, "templates.yml"), "https://bitbucket.org/ws/repo/raw/main/templates/templates.yml");
+    }
+
+    #[test]
+    fn test_browse_and_clone_urls_round_trip()
+    {
+        for url in [
+            "https://github.com/owner/repo/tree/develop/templates/v2", "https://gitlab.example.com/team/sub/repo/-/tree/main/templates",
+            "https://codeberg.org/owner/repo/src/branch/main", "https://bitbucket.org/ws/repo/src/main/templates"
+        ]
+        {
+            let provider = ProviderKind::detect(url).unwrap().provider();
+            let location = provider.parse_url(url).unwrap();
+            assert_eq!(provider.browse_url(&location), url);
+        }
+
+        let gitea = ProviderKind::Gitea.provider();
+        let mut loc = gitea.parse_url("https://codeberg.org/owner/repo/src/branch/main/templates").unwrap();
+        assert_eq!(gitea.clone_url(&loc), "https://codeberg.org/owner/repo.git");
+        loc.refname = gitea.tag_refname("v7.1.0");
+        assert_eq!(gitea.browse_url(&loc), "https://codeberg.org/owner/repo/src/tag/v7.1.0/templates");
+        assert_eq!(gitea.ref_name(&loc.refname), "v7.1.0");
+        loc.refname = gitea.commit_refname("0123abcd");
+        assert_eq!(gitea.browse_url(&loc), "https://codeberg.org/owner/repo/src/commit/0123abcd/templates");
+    }
+}
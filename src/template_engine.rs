@@ -14,31 +14,44 @@
 
 use crate::{
     Result,
-    bom::TemplateConfig,
+    bom::{FileMode, MergePreference, TemplateConfig},
+    file_merge::MergeFormat,
     file_tracker::{FileStatus, FileTracker},
+    template_renderer::TemplateRenderer,
+    template_variables::TemplateVariables,
     utils::{FileActionResponse, copy_file_with_mkdir, prompt_file_modification}
 };
 
 /// Template marker comment used to detect unmerged template files
 pub const TEMPLATE_MARKER: &str = "<!-- VIBE-CHECK-TEMPLATE: This marker indicates an unmerged template. Do not remove manually. -->";
 
+/// Prefix of the comment that opens a managed region in AGENTS.md (followed by the category)
+const REGION_BEGIN: &str = "<!-- vibe-check:begin ";
+
+/// Prefix of the comment that closes a managed region in AGENTS.md (followed by the category)
+const REGION_END: &str = "<!-- vibe-check:end ";
+
 /// Options for the template update operation
 ///
 /// Aggregates CLI parameters that are passed through the update call chain.
 pub struct UpdateOptions<'a>
 {
     /// Programming language or framework identifier
-    pub lang:    &'a str,
+    pub lang:      &'a str,
     /// AI coding agent identifier (required for v1, optional for v2)
-    pub agent:   Option<&'a str>,
+    pub agent:     Option<&'a str>,
     /// Skip language-specific setup
-    pub no_lang: bool,
+    pub no_lang:   bool,
     /// Custom mission statement to override template default
-    pub mission: Option<&'a str>,
+    pub mission:   Option<&'a str>,
     /// Force overwrite of local modifications without warning
-    pub force:   bool,
+    pub force:     bool,
     /// Preview changes without applying them
-    pub dry_run: bool
+    pub dry_run:   bool,
+    /// Merge template changes into locally modified files instead of prompting
+    pub merge:     bool,
+    /// Values for `{{name}}` references in templates and fragments
+    pub variables: &'a TemplateVariables
 }
 
 /// Context for the main AGENTS.md template and its fragments
@@ -57,6 +70,19 @@ pub struct TemplateContext
     pub template_version: u32
 }
 
+/// What to do with a single template file during the copy operation
+enum FileAction
+{
+    /// Keep the local file
+    Skip,
+    /// Write the rendered template
+    Copy,
+    /// Three-way merge of the installed content (base), the local file, and the rendered template
+    ThreeWayMerge(String),
+    /// Format-aware merge of the rendered template into the local file (`mode: merge`)
+    FormatMerge(MergeFormat)
+}
+
 /// Result of the file copy operation
 pub enum CopyFilesResult
 {
@@ -79,7 +105,7 @@ pub enum CopyFilesResult
 ///
 /// # Errors
 ///
-/// Returns an error if templates.yml cannot be loaded or parsed
+/// Returns an error if templates.yml cannot be loaded or parsed, or contains unsafe paths
 pub fn load_template_config(config_dir: &Path) -> Result<TemplateConfig>
 {
     let config_path = config_dir.join("templates.yml");
@@ -90,7 +116,8 @@ pub fn load_template_config(config_dir: &Path) -> Result<TemplateConfig>
     }
 
     let content = fs::read_to_string(&config_path)?;
-    let config: TemplateConfig = serde_yaml::from_str(&content)?;
+    let config = TemplateConfig::parse(&content, &config_path.display().to_string())?;
+    config.validate_paths()?;
     Ok(config)
 }
 
@@ -117,6 +144,127 @@ pub fn is_file_customized(local_path: &Path) -> Result<bool>
     Ok(content.contains(TEMPLATE_MARKER) == false)
 }
 
+/// Checks if a local file contains managed regions written by `merge_fragments`
+///
+/// Managed regions are replaced with the current fragments on every `init`,
+/// while text outside of them is kept.
+///
+/// # Arguments
+///
+/// * `local_path` - Path to local file to check
+///
+/// # Returns
+///
+/// Returns `true` if the file exists and contains at least one managed region
+pub fn has_managed_regions(local_path: &Path) -> Result<bool>
+{
+    if local_path.exists() == false
+    {
+        return Ok(false);
+    }
+
+    let content = fs::read_to_string(local_path)?;
+    Ok(managed_regions(&content).is_empty() == false)
+}
+
+/// Wraps fragment content in the begin/end markers of a managed region
+fn managed_region(category: &str, content: &str) -> String
+{
+    if content.is_empty() == true
+    {
+        return format!("{}{} -->\n\n{}{} -->", REGION_BEGIN, category, REGION_END, category);
+    }
+    format!("{}{} -->\n\n{}\n\n{}{} -->", REGION_BEGIN, category, content, REGION_END, category)
+}
+
+/// Finds the managed regions in a file
+///
+/// # Returns
+///
+/// (category, byte range of the region content between the markers) for every
+/// region with a matching end marker, in order of appearance
+fn managed_regions(content: &str) -> Vec<(&str, std::ops::Range<usize>)>
+{
+    let mut regions = Vec::new();
+    let mut offset = 0;
+
+    while let Some(begin) = content[offset..].find(REGION_BEGIN)
+    {
+        let name_start = offset + begin + REGION_BEGIN.len();
+        let Some(name_len) = content[name_start..].find(" -->")
+        else
+        {
+            break;
+        };
+        let category = &content[name_start..name_start + name_len];
+        let inner_start = name_start + name_len + " -->".len();
+
+        let end_marker = format!("{}{} -->", REGION_END, category);
+        match content[inner_start..].find(&end_marker)
+        {
+            | Some(end) =>
+            {
+                regions.push((category, inner_start..inner_start + end));
+                offset = inner_start + end + end_marker.len();
+            }
+            | None => offset = inner_start
+        }
+    }
+
+    regions
+}
+
+/// Replaces the managed regions of an existing file with those of a freshly merged one
+///
+/// Text outside the regions is kept. Regions that the user deleted from the
+/// existing file are not restored, and regions of categories the merged content
+/// no longer has (e.g. languages after `--no-lang`) are emptied.
+///
+/// # Arguments
+///
+/// * `existing` - Current content of the local file
+/// * `merged` - Freshly merged template content
+///
+/// # Returns
+///
+/// The updated content, the categories of the merged regions that are missing from the existing file,
+/// and the categories of the emptied regions
+pub(crate) fn replace_managed_regions(existing: &str, merged: &str) -> (String, Vec<String>, Vec<String>)
+{
+    let fresh: HashMap<&str, &str> = managed_regions(merged).into_iter().map(|(category, range)| (category, &merged[range])).collect();
+
+    let mut output = String::with_capacity(existing.len());
+    let mut position = 0;
+    let mut present = Vec::new();
+    let mut emptied = Vec::new();
+
+    for (category, range) in managed_regions(existing)
+    {
+        present.push(category);
+        output.push_str(&existing[position..range.start]);
+        match fresh.get(category)
+        {
+            | Some(content) => output.push_str(content),
+            | None =>
+            {
+                // The category was not selected this time, so its old content is stale
+                if existing[range.clone()].trim().is_empty() == false
+                {
+                    emptied.push(category.to_string());
+                }
+                output.push_str("\n\n");
+            }
+        }
+        position = range.end;
+    }
+    output.push_str(&existing[position..]);
+
+    let mut missing: Vec<String> = fresh.keys().filter(|category| present.contains(category) == false).map(|c| c.to_string()).collect();
+    missing.sort();
+
+    (output, missing, emptied)
+}
+
 /// Shared trait for template engine operations
 ///
 /// Provides default implementations for common template operations
@@ -160,20 +308,84 @@ fn resolve_placeholder(&self, path: &str, workspace: &Path, userprofile: &Path)
     /// insertion points: `<!-- {mission} -->`, `<!-- {principles} -->`,
     /// `<!-- {languages} -->`, `<!-- {integration} -->`
     ///
-    /// The insertion point comments are preserved in the final merged file.
+    /// Each insertion point is replaced by a managed region
+    /// (`<!-- vibe-check:begin {category} -->` ... `<!-- vibe-check:end {category} -->`)
+    /// holding the fragments, so later runs can update the fragments without
+    /// touching text the user wrote outside the regions. The main template and every
+    /// fragment are rendered (variables, conditional blocks, and includes) before
+    /// they are merged.
     ///
     /// # Arguments
     ///
     /// * `ctx` - Main template context containing source, target, and fragments
-    /// * `options` - Update options containing no_lang and mission settings
+    /// * `options` - Update options containing no_lang, mission, and variables
     ///
     /// # Errors
     ///
-    /// Returns an error if file reading or writing fails
+    /// Returns an error if file reading or writing fails, or a template cannot be rendered
     fn merge_fragments(&self, ctx: &TemplateContext, options: &UpdateOptions) -> Result<()>
     {
-        // Read main AGENTS.md template
-        let mut main_content = fs::read_to_string(&ctx.source)?;
+        let main_content = self.merged_main_template(ctx, options)?;
+
+        // Write merged content to target
+        if let Some(parent) = ctx.target.parent()
+        {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&ctx.target, main_content)?;
+
+        Ok(())
+    }
+
+    /// Updates only the managed regions of a customized AGENTS.md
+    ///
+    /// The template is merged as in `merge_fragments`, then the content of every
+    /// managed region in the local file is replaced with the freshly merged one.
+    /// Text outside the regions, and regions the user deleted, are left alone.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - Main template context containing source, target, and fragments
+    /// * `options` - Update options containing no_lang, mission, and variables
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if file reading or writing fails, or a template cannot be rendered
+    fn update_managed_regions(&self, ctx: &TemplateContext, options: &UpdateOptions) -> Result<()>
+    {
+        let merged = self.merged_main_template(ctx, options)?;
+        let existing = fs::read_to_string(&ctx.target)?;
+
+        let (updated, missing, emptied) = replace_managed_regions(&existing, &merged);
+        for category in missing
+        {
+            println!("{} Managed region '{}' was removed from AGENTS.md and is not restored (use --force to regenerate the file)", "!".yellow(), category);
+        }
+        for category in emptied
+        {
+            println!("{} Emptied managed region '{}' in AGENTS.md (no {} selected)", "→".blue(), category, category);
+        }
+
+        if updated != existing
+        {
+            fs::write(&ctx.target, updated)?;
+        }
+
+        Ok(())
+    }
+
+    /// Builds the merged AGENTS.md content (see `merge_fragments`)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a template cannot be read or rendered
+    fn merged_main_template(&self, ctx: &TemplateContext, options: &UpdateOptions) -> Result<String>
+    {
+        let mut unresolved = Vec::new();
+
+        // Read and render main AGENTS.md template
+        let (mut main_content, undefined) = self.render_template(&fs::read_to_string(&ctx.source)?, &ctx.source.display().to_string(), options.variables)?;
+        unresolved.extend(undefined);
 
         // Remove the template marker to indicate this is a merged/customized file
         let marker_with_newline = format!("{}\n", TEMPLATE_MARKER);
@@ -189,13 +401,16 @@ fn merge_fragments(&self, ctx: &TemplateContext, options: &UpdateOptions) -> Res
 
         for (fragment_path, category) in &ctx.fragments
         {
-            let fragment_content = fs::read_to_string(fragment_path)?;
+            let (fragment_content, undefined) = self.render_template(&fs::read_to_string(fragment_path)?, &fragment_path.display().to_string(), options.variables)?;
+            unresolved.extend(undefined);
             fragments_by_category.entry(category.clone()).or_default().push(fragment_content);
         }
 
         // If custom mission is provided, add it to the fragments
         if let Some(mission_content) = options.mission
         {
+            let (mission_content, undefined) = self.render_template(mission_content, "--mission", options.variables)?;
+            unresolved.extend(undefined);
             let formatted_mission = format!("## Mission Statement\n\n{}", mission_content.trim());
             fragments_by_category.entry("mission".to_string()).or_default().push(formatted_mission);
             println!("{} Using custom mission statement", "→".blue());
@@ -209,11 +424,10 @@ fn merge_fragments(&self, ctx: &TemplateContext, options: &UpdateOptions) -> Res
             // Combine all fragments for this category
             let combined_content = contents.iter().map(|c| c.trim()).collect::<Vec<_>>().join("\n\n");
 
-            // Replace insertion point with comment + fragment content (keep single insertion point)
+            // Replace insertion point with a managed region holding the fragment content
             if main_content.contains(&insertion_point)
             {
-                let replacement = format!("<!-- {{{}}} -->\n\n{}", category, combined_content);
-                main_content = main_content.replace(&insertion_point, &replacement);
+                main_content = main_content.replace(&insertion_point, &managed_region(&category, &combined_content));
             }
             else
             {
@@ -221,14 +435,11 @@ fn merge_fragments(&self, ctx: &TemplateContext, options: &UpdateOptions) -> Res
             }
         }
 
-        // Write merged content to target
-        if let Some(parent) = ctx.target.parent()
-        {
-            fs::create_dir_all(parent)?;
-        }
-        fs::write(&ctx.target, main_content)?;
+        unresolved.sort();
+        unresolved.dedup();
+        self.warn_unresolved(&ctx.target, &unresolved);
 
-        Ok(())
+        Ok(main_content)
     }
 
     /// Shows dry-run preview of files that would be created/modified
@@ -238,8 +449,10 @@ fn merge_fragments(&self, ctx: &TemplateContext, options: &UpdateOptions) -> Res
     /// * `ctx` - Template context for main AGENTS.md
     /// * `skip_agents_md` - Whether AGENTS.md is customized and should be skipped
     /// * `options` - Update options containing force and dry_run settings
-    /// * `files_to_copy` - List of (source, target) file pairs
-    fn show_dry_run_files(&self, ctx: &TemplateContext, skip_agents_md: bool, options: &UpdateOptions, files_to_copy: &[(PathBuf, PathBuf)])
+    /// * `files_to_copy` - List of (source, target, mode, merge preference, render) file entries
+    fn show_dry_run_files(
+        &self, ctx: &TemplateContext, skip_agents_md: bool, options: &UpdateOptions, files_to_copy: &[(PathBuf, PathBuf, FileMode, MergePreference, bool)]
+    )
     {
         println!("\n{} Files that would be created/modified:", "→".blue());
 
@@ -248,6 +461,10 @@ fn show_dry_run_files(&self, ctx: &TemplateContext, skip_agents_md: bool, option
         {
             println!("  {} {} (skipped - customized)", "○".yellow(), ctx.target.display());
         }
+        else if options.force == false && has_managed_regions(&ctx.target).unwrap_or(false) == true
+        {
+            println!("  {} {} (managed regions would be updated)", "●".yellow(), ctx.target.display());
+        }
         else if ctx.target.exists()
         {
             println!("  {} {} (would be overwritten)", "●".yellow(), ctx.target.display());
@@ -258,9 +475,17 @@ fn show_dry_run_files(&self, ctx: &TemplateContext, skip_agents_md: bool, option
         }
 
         // Show other files
-        for (_, target) in files_to_copy
+        for (_, target, mode, ..) in files_to_copy
         {
-            if target.exists()
+            if target.exists() && *mode == FileMode::CreateOnly
+            {
+                println!("  {} {} (skipped - create-only)", "○".yellow(), target.display());
+            }
+            else if target.exists() && *mode == FileMode::Merge && MergeFormat::detect(target).is_some()
+            {
+                println!("  {} {} (would be merged)", "●".yellow(), target.display());
+            }
+            else if target.exists()
             {
                 println!("  {} {} (would be overwritten)", "●".yellow(), target.display());
             }
@@ -297,7 +522,13 @@ fn handle_main_template(&self, ctx: &TemplateContext, options: &UpdateOptions, s
             return Ok(());
         }
 
-        if ctx.fragments.is_empty() == false || options.mission.is_some() == true
+        if options.force == false && has_managed_regions(&ctx.target)? == true
+        {
+            // Customized AGENTS.md: only the managed regions are replaced
+            println!("{} Updating managed regions in AGENTS.md", "→".blue());
+            self.update_managed_regions(ctx, options)?;
+        }
+        else if ctx.fragments.is_empty() == false || options.mission.is_some() == true
         {
             // Merge fragments into AGENTS.md
             println!("{} Merging fragments into AGENTS.md", "→".blue());
@@ -305,12 +536,8 @@ fn handle_main_template(&self, ctx: &TemplateContext, options: &UpdateOptions, s
         }
         else
         {
-            // No fragments, just copy main file as-is
-            if let Some(parent) = ctx.target.parent()
-            {
-                fs::create_dir_all(parent)?;
-            }
-            fs::copy(&ctx.source, &ctx.target)?;
+            // No fragments, just copy the rendered main file
+            self.copy_template(&ctx.source, &ctx.target, true, options.variables)?;
         }
 
         println!("  {} {}", "✓".green(), ctx.target.display().to_string().yellow());
@@ -344,7 +571,7 @@ fn handle_main_template(&self, ctx: &TemplateContext, options: &UpdateOptions, s
     ///
     /// # Arguments
     ///
-    /// * `files_to_copy` - List of (source, target) file pairs
+    /// * `files_to_copy` - List of (source, target, mode, merge preference, render) file entries
     /// * `file_tracker` - File tracker for checking modifications and recording installations
     /// * `ctx` - Template context containing the template version for file tracking
     /// * `options` - Update options containing lang, no_lang, agent, and force settings
@@ -357,28 +584,51 @@ fn handle_main_template(&self, ctx: &TemplateContext, options: &UpdateOptions, s
     ///
     /// Returns an error if file operations fail
     fn copy_files_with_tracking(
-        &self, files_to_copy: &[(PathBuf, PathBuf)], file_tracker: &mut FileTracker, ctx: &TemplateContext, options: &UpdateOptions
+        &self, files_to_copy: &[(PathBuf, PathBuf, FileMode, MergePreference, bool)], file_tracker: &mut FileTracker, ctx: &TemplateContext, options: &UpdateOptions
     ) -> Result<CopyFilesResult>
     {
         println!("{} Copying templates to target directories", "→".blue());
 
         let mut skipped_files = Vec::new();
 
-        for (source, target) in files_to_copy
+        for (source, target, mode, prefer, render) in files_to_copy
         {
-            // Calculate new template SHA
-            let new_template_sha = FileTracker::calculate_sha256(source)?;
+            let format = if *mode == FileMode::Merge
+            {
+                let format = MergeFormat::detect(target);
+                if format.is_none() == true && target.exists() == true
+                {
+                    println!("{} Cannot merge {} (unsupported file type), replacing it instead", "!".yellow(), target.display());
+                }
+                format
+            }
+            else
+            {
+                None
+            };
 
             // Check if file needs to be processed
-            let should_copy = if target.exists() == false
+            let action = if target.exists() == false
             {
                 // File doesn't exist, safe to copy
-                true
+                FileAction::Copy
+            }
+            else if *mode == FileMode::CreateOnly
+            {
+                // Existing files are never touched, not even with --force
+                println!("  {} {} (exists, create-only)", "○".blue(), target.display());
+                continue;
+            }
+            else if let Some(format) = format &&
+                file_tracker.check_modification(target)? != FileStatus::Unmodified
+            {
+                // Merge into the project's own version (unmodified copies are simply replaced)
+                FileAction::FormatMerge(format)
             }
             else if options.force == true
             {
                 // Force flag set, always overwrite
-                true
+                FileAction::Copy
             }
             else
             {
@@ -388,14 +638,14 @@ fn copy_files_with_tracking(
                     | FileStatus::NotTracked =>
                     {
                         // Not tracked, could be user file - prompt for safety
-                        let response = prompt_file_modification(target, "<not tracked>", "<current file>", source)?;
+                        let response = prompt_file_modification(target, "<not tracked>", "<current file>", source, false)?;
                         match response
                         {
-                            | FileActionResponse::Overwrite => true,
-                            | FileActionResponse::Skip =>
+                            | FileActionResponse::Overwrite => FileAction::Copy,
+                            | FileActionResponse::Skip | FileActionResponse::Merge =>
                             {
                                 skipped_files.push(target.clone());
-                                false
+                                FileAction::Skip
                             }
                             | FileActionResponse::Quit =>
                             {
@@ -407,24 +657,39 @@ fn copy_files_with_tracking(
                     | FileStatus::Unmodified =>
                     {
                         // User didn't modify, safe to update
-                        true
+                        FileAction::Copy
                     }
                     | FileStatus::Modified =>
                     {
-                        // User modified, prompt
-                        if let Some(metadata) = file_tracker.get_metadata(target)
+                        // User modified: merge with --merge, prompt otherwise
+                        let base = file_tracker.original_content(target);
+                        if options.merge == true
+                        {
+                            match base
+                            {
+                                | Some(base) => FileAction::ThreeWayMerge(base),
+                                | None =>
+                                {
+                                    println!("{} Cannot merge {} (installed content unknown), keeping your version", "!".yellow(), target.display());
+                                    skipped_files.push(target.clone());
+                                    FileAction::Skip
+                                }
+                            }
+                        }
+                        else if let Some(metadata) = file_tracker.get_metadata(target)
                         {
                             let current_sha = FileTracker::calculate_sha256(target)?;
-                            let response = prompt_file_modification(target, &metadata.original_sha, &current_sha, source)?;
-                            match response
+                            let response = prompt_file_modification(target, &metadata.original_sha, &current_sha, source, base.is_some())?;
+                            match (response, base)
                             {
-                                | FileActionResponse::Overwrite => true,
-                                | FileActionResponse::Skip =>
+                                | (FileActionResponse::Overwrite, _) => FileAction::Copy,
+                                | (FileActionResponse::Merge, Some(base)) => FileAction::ThreeWayMerge(base),
+                                | (FileActionResponse::Skip | FileActionResponse::Merge, _) =>
                                 {
                                     skipped_files.push(target.clone());
-                                    false
+                                    FileAction::Skip
                                 }
-                                | FileActionResponse::Quit =>
+                                | (FileActionResponse::Quit, _) =>
                                 {
                                     println!("\n{} Operation cancelled by user", "!".yellow());
                                     return Ok(CopyFilesResult::Cancelled);
@@ -434,22 +699,85 @@ fn copy_files_with_tracking(
                         else
                         {
                             // Shouldn't happen, but treat as safe to update
-                            true
+                            FileAction::Copy
                         }
                     }
                     | FileStatus::Deleted =>
                     {
                         // Was tracked but deleted, safe to recreate
-                        true
+                        FileAction::Copy
                     }
                 }
             };
 
-            if should_copy == true
+            let installed = match action
             {
-                copy_file_with_mkdir(source, target)?;
-                println!("  {} {}", "✓".green(), target.display().to_string().yellow());
+                | FileAction::Skip => None,
+                | FileAction::Copy =>
+                {
+                    self.copy_template(source, target, *render, options.variables)?;
+                    println!("  {} {}", "✓".green(), target.display().to_string().yellow());
+                    Some(fs::read(target)?)
+                }
+                | FileAction::ThreeWayMerge(base) =>
+                {
+                    let Some(template) = self.template_content(source, target, *render, options.variables)?
+                    else
+                    {
+                        println!("{} Cannot merge binary file {}, keeping your version", "!".yellow(), target.display());
+                        skipped_files.push(target.clone());
+                        continue;
+                    };
+
+                    let local = fs::read_to_string(target)?;
+                    match diffy::merge(&base, &local, &template)
+                    {
+                        | Ok(merged) =>
+                        {
+                            fs::write(target, merged)?;
+                            println!("  {} {} (merged)", "✓".green(), target.display().to_string().yellow());
+                        }
+                        | Err(conflicted) =>
+                        {
+                            fs::write(target, conflicted)?;
+                            println!("  {} {} (merged with conflicts, resolve the <<<<<<< markers)", "!".yellow(), target.display().to_string().yellow());
+                        }
+                    }
+
+                    // The new template becomes the base of the next merge
+                    Some(template.into_bytes())
+                }
+                | FileAction::FormatMerge(format) =>
+                {
+                    let Some(template) = self.template_content(source, target, *render, options.variables)?
+                    else
+                    {
+                        println!("{} Cannot merge binary template {}, keeping your version", "!".yellow(), source.display());
+                        skipped_files.push(target.clone());
+                        continue;
+                    };
 
+                    let existing = fs::read_to_string(target)?;
+                    let merged = match format.merge(&existing, &template, *prefer)
+                    {
+                        | Ok(merged) => merged,
+                        | Err(e) =>
+                        {
+                            println!("{} Cannot merge {}: {}, keeping your version", "!".yellow(), target.display(), e);
+                            continue;
+                        }
+                    };
+                    if merged != existing
+                    {
+                        fs::write(target, merged)?;
+                    }
+                    println!("  {} {} (merged)", "✓".green(), target.display().to_string().yellow());
+                    Some(template.into_bytes())
+                }
+            };
+
+            if let Some(installed) = installed
+            {
                 // Determine category based on target path
                 let target_str = target.to_string_lossy();
                 let category = if target_str.contains("SKILL.md") || target_str.contains("/skills/") || target_str.contains("\\skills\\")
@@ -476,10 +804,12 @@ fn copy_files_with_tracking(
                     "language"
                 };
 
-                // Record installation in file tracker
+                // Record installation in file tracker (hash of the rendered template),
+                // keeping its content as the base for later merges
+                let sha = file_tracker.store_original(&installed)?;
                 file_tracker.record_installation(
                     target,
-                    new_template_sha,
+                    sha,
                     ctx.template_version,
                     if options.no_lang
                     {
@@ -497,6 +827,110 @@ fn copy_files_with_tracking(
         Ok(CopyFilesResult::Done { skipped: skipped_files })
     }
 
+    /// Renders a template with the template language (variables, conditional blocks, includes)
+    ///
+    /// Includes are resolved relative to the global template directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - Template text
+    /// * `origin` - Template file name used in error messages
+    /// * `variables` - Values for variables and conditions
+    ///
+    /// # Returns
+    ///
+    /// The rendered text and the names of undefined variables it references
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the template is malformed or an include cannot be resolved
+    fn render_template(&self, content: &str, origin: &str, variables: &TemplateVariables) -> Result<(String, Vec<String>)>
+    {
+        TemplateRenderer::new(variables, self.config_dir()).render(content).map_err(|e| format!("Failed to render {}: {}", origin, e).into())
+    }
+
+    /// Warns about template variables that are referenced but not defined
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - File the variables were rendered into
+    /// * `unresolved` - Names of the undefined variables
+    fn warn_unresolved(&self, target: &Path, unresolved: &[String])
+    {
+        if unresolved.is_empty() == false
+        {
+            let names = unresolved.iter().map(|name| format!("{{{{{}}}}}", name)).collect::<Vec<_>>().join(", ");
+            println!("{} Warning: Unresolved template variables in {}: {}", "!".yellow(), target.display(), names.yellow());
+            println!("{} Define them with 'vibe-check init --var name=value' or 'vibe-check config vars.<name> <value>'", "→".blue());
+        }
+    }
+
+    /// Copies a template file, rendering it with the template language if requested
+    ///
+    /// Only files with `render: true` (and the main template) are rendered, so tags in
+    /// other files (e.g. `{{else}}` in a shell script) are copied as they are. Files that
+    /// are not valid UTF-8 are never rendered.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - Template file in global storage
+    /// * `target` - Destination path (parent directories are created)
+    /// * `render` - Whether to render the file
+    /// * `variables` - Values for variables and conditions
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, rendered, or written
+    fn copy_template(&self, source: &Path, target: &Path, render: bool, variables: &TemplateVariables) -> Result<()>
+    {
+        if render == false
+        {
+            return copy_file_with_mkdir(source, target);
+        }
+
+        let Some(rendered) = self.template_content(source, target, true, variables)?
+        else
+        {
+            return copy_file_with_mkdir(source, target);
+        };
+
+        if let Some(parent) = target.parent()
+        {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(target, rendered)?;
+        Ok(())
+    }
+
+    /// Reads the content of a template file for a target, rendering it if requested
+    ///
+    /// Warns about unresolved variables of rendered files.
+    ///
+    /// # Returns
+    ///
+    /// The (rendered) content, or None if the file is not UTF-8 text
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or the template cannot be rendered
+    fn template_content(&self, source: &Path, target: &Path, render: bool, variables: &TemplateVariables) -> Result<Option<String>>
+    {
+        let content = match String::from_utf8(fs::read(source)?)
+        {
+            | Ok(content) => content,
+            | Err(_) => return Ok(None)
+        };
+
+        if render == false
+        {
+            return Ok(Some(content));
+        }
+
+        let (rendered, unresolved) = self.render_template(&content, &source.display().to_string(), variables)?;
+        self.warn_unresolved(target, &unresolved);
+        Ok(Some(rendered))
+    }
+
     /// Shows summary of skipped files after a copy operation
     ///
     /// # Arguments
@@ -511,7 +945,220 @@ fn show_skipped_files_summary(&self, skipped_files: &[PathBuf])
             {
                 println!("  {} {}", "○".yellow(), file.display());
             }
-            println!("{} Use --force to overwrite modified files", "→".blue());
+            println!("{} Use --force to overwrite or --merge to merge modified files", "→".blue());
         }
     }
 }
+
+#[cfg(test)]
+mod tests
+{
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::template_engine_v2::TemplateEngineV2;
+
+    /// Update options for `--lang rust` with the given variables
+    fn rust_options(variables: &TemplateVariables, merge: bool) -> UpdateOptions<'_>
+    {
+        UpdateOptions { lang: "rust", agent: None, no_lang: false, mission: None, force: false, dry_run: false, merge, variables }
+    }
+
+    /// Context without a main template, for copying files only
+    fn copy_context(temp_dir: &TempDir) -> TemplateContext
+    {
+        TemplateContext {
+            source:           temp_dir.path().join("templates/AGENTS.md"),
+            target:           temp_dir.path().join("AGENTS.md"),
+            fragments:        Vec::new(),
+            template_version: 2
+        }
+    }
+
+    #[test]
+    fn test_copied_files_are_rendered_only_on_request() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let template_dir = temp_dir.path().join("templates");
+        fs::create_dir_all(&template_dir)?;
+        let script = "{{else}}\n{{/if}}\n{{#if a b}}\n{{> x}}\necho {{project.name}}\n";
+        fs::write(template_dir.join("build.sh"), script)?;
+        fs::write(template_dir.join("README.md"), "# {{project.name}}\n{{#if lang == \"rust\"}}\ncargo build\n{{/if}}\n")?;
+
+        let mut variables = TemplateVariables::default();
+        variables.set("project.name", "demo");
+        variables.set("lang", "rust");
+        let files = vec![
+            (template_dir.join("build.sh"), temp_dir.path().join("out/build.sh"), FileMode::Replace, MergePreference::Local, false),
+            (template_dir.join("README.md"), temp_dir.path().join("out/README.md"), FileMode::Replace, MergePreference::Local, true),
+        ];
+        let mut file_tracker = FileTracker::new(temp_dir.path())?;
+        TemplateEngineV2::new(&template_dir).copy_files_with_tracking(&files, &mut file_tracker, &copy_context(&temp_dir), &rust_options(&variables, false))?;
+
+        // Stray tags in files without `render: true` are copied byte for byte
+        assert_eq!(fs::read(temp_dir.path().join("out/build.sh"))?, script.as_bytes());
+        assert_eq!(fs::read_to_string(temp_dir.path().join("out/README.md"))?, "# demo\ncargo build\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_modified_files_are_merged() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let template_dir = temp_dir.path().join("templates");
+        fs::create_dir_all(&template_dir)?;
+        let source = template_dir.join("build.md");
+        let target = temp_dir.path().join("out/build.md");
+        let files = vec![(source.clone(), target.clone(), FileMode::Replace, MergePreference::Local, false)];
+
+        let engine = TemplateEngineV2::new(&template_dir);
+        let variables = TemplateVariables::default();
+        let ctx = copy_context(&temp_dir);
+        let mut file_tracker = FileTracker::new(temp_dir.path())?;
+        fs::write(&source, "# Build\n\ncargo build\n\ncargo test\n")?;
+        engine.copy_files_with_tracking(&files, &mut file_tracker, &ctx, &rust_options(&variables, true))?;
+
+        // Local and template changes to different lines merge cleanly
+        fs::write(&target, "# Build (ours)\n\ncargo build\n\ncargo test\n")?;
+        fs::write(&source, "# Build\n\ncargo build\n\ncargo nextest run\n")?;
+        engine.copy_files_with_tracking(&files, &mut file_tracker, &ctx, &rust_options(&variables, true))?;
+        assert_eq!(fs::read_to_string(&target)?, "# Build (ours)\n\ncargo build\n\ncargo nextest run\n");
+        assert_eq!(file_tracker.original_content(&target).as_deref(), Some("# Build\n\ncargo build\n\ncargo nextest run\n"));
+
+        // Changes to the same line leave conflict markers, and the new template becomes the base
+        fs::write(&target, "# Build (ours)\n\ncargo build --locked\n\ncargo nextest run\n")?;
+        fs::write(&source, "# Build\n\ncargo build --release\n\ncargo nextest run\n")?;
+        engine.copy_files_with_tracking(&files, &mut file_tracker, &ctx, &rust_options(&variables, true))?;
+        let merged = fs::read_to_string(&target)?;
+        assert!(merged.contains("<<<<<<<") && merged.contains(">>>>>>>"), "{}", merged);
+        assert!(merged.contains("cargo build --locked") && merged.contains("cargo build --release"));
+        assert_eq!(file_tracker.original_content(&target).as_deref(), Some("# Build\n\ncargo build --release\n\ncargo nextest run\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_into_untracked_file() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let template_dir = temp_dir.path().join("templates");
+        fs::create_dir_all(&template_dir)?;
+        fs::write(template_dir.join("rust-git-ignore.txt"), "# Rust\ntarget\nnode_modules\n")?;
+        fs::write(template_dir.join("git-attributes.bin"), [0x89, b'P', b'N', b'G', 0xff, 0xfe])?;
+        let gitignore = temp_dir.path().join(".gitignore");
+        let gitattributes = temp_dir.path().join(".gitattributes");
+        fs::write(&gitignore, "node_modules\n")?;
+        fs::write(&gitattributes, "* text=auto\n")?;
+
+        let files = vec![
+            (template_dir.join("rust-git-ignore.txt"), gitignore.clone(), FileMode::Merge, MergePreference::Local, false),
+            (template_dir.join("git-attributes.bin"), gitattributes.clone(), FileMode::Merge, MergePreference::Local, false),
+        ];
+        let variables = TemplateVariables::default();
+        let mut file_tracker = FileTracker::new(temp_dir.path())?;
+        let result =
+            TemplateEngineV2::new(&template_dir).copy_files_with_tracking(&files, &mut file_tracker, &copy_context(&temp_dir), &rust_options(&variables, false))?;
+
+        // The project's .gitignore is merged without prompting, and the template is tracked as its base
+        assert_eq!(fs::read_to_string(&gitignore)?, "node_modules\n\n# >>> vibe-check >>>\ntarget\n# <<< vibe-check <<<\n");
+        assert!(file_tracker.get_metadata(&gitignore).is_some_and(|metadata| metadata.category == "integration"));
+        assert_eq!(file_tracker.original_content(&gitignore).as_deref(), Some("# Rust\ntarget\nnode_modules\n"));
+
+        // A binary template is never merged or copied over the project's file
+        assert_eq!(fs::read_to_string(&gitattributes)?, "* text=auto\n");
+        assert!(file_tracker.get_metadata(&gitattributes).is_none());
+        assert!(matches!(result, CopyFilesResult::Done { skipped } if skipped == vec![gitattributes.clone()]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_unselected_regions_are_emptied() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let template_dir = temp_dir.path().join("templates");
+        fs::create_dir_all(&template_dir)?;
+        fs::write(template_dir.join("AGENTS.md"), "# Agents\n\n<!-- {mission} -->\n\n<!-- {languages} -->\n")?;
+        fs::write(template_dir.join("mission.md"), "Ship it")?;
+        fs::write(template_dir.join("rust.md"), "Use clippy")?;
+
+        let engine = TemplateEngineV2::new(&template_dir);
+        let variables = TemplateVariables::default();
+        let mut file_tracker = FileTracker::new(temp_dir.path())?;
+        let mut ctx = TemplateContext {
+            source:           template_dir.join("AGENTS.md"),
+            target:           temp_dir.path().join("AGENTS.md"),
+            fragments:        vec![(template_dir.join("mission.md"), "mission".to_string()), (template_dir.join("rust.md"), "languages".to_string())],
+            template_version: 2
+        };
+        engine.handle_main_template(&ctx, &rust_options(&variables, false), false, &mut file_tracker)?;
+        assert!(fs::read_to_string(&ctx.target)?.contains("Use clippy"));
+
+        // The user adds text outside the regions, then reinitializes with --no-lang
+        let content = fs::read_to_string(&ctx.target)?;
+        fs::write(&ctx.target, format!("{}\nMy own notes\n", content.replace("# Agents\n", "# Agents\n\nRead CONTRIBUTING.md first\n")))?;
+        ctx.fragments.retain(|(_, category)| category != "languages");
+        let options = UpdateOptions {
+            lang:      "",
+            agent:     None,
+            no_lang:   true,
+            mission:   None,
+            force:     false,
+            dry_run:   false,
+            merge:     false,
+            variables: &variables
+        };
+        engine.handle_main_template(&ctx, &options, false, &mut file_tracker)?;
+
+        let content = fs::read_to_string(&ctx.target)?;
+        assert!(content.contains("Use clippy") == false);
+        assert!(content.contains(&managed_region("languages", "")));
+        assert!(content.contains(&managed_region("mission", "Ship it")));
+        assert!(content.contains("Read CONTRIBUTING.md first\n") && content.ends_with("\nMy own notes\n"));
+
+        // A category without fragments is emptied as well
+        fs::write(&ctx.target, content.replace(&managed_region("languages", ""), &managed_region("languages", "Stale rules")))?;
+        engine.handle_main_template(&ctx, &rust_options(&variables, false), false, &mut file_tracker)?;
+        assert_eq!(fs::read_to_string(&ctx.target)?, content);
+        Ok(())
+    }
+
+    #[test]
+    fn test_replace_managed_regions()
+    {
+        let merged = format!("# AGENTS.md\n\n{}\n\n{}\n", managed_region("mission", "New mission"), managed_region("language", "New rules"));
+        let existing = format!(
+            "# AGENTS.md\n\nOur own intro\n\n{}\n\nNotes the user wrote\n\n{}\n",
+            managed_region("mission", "Old mission"),
+            managed_region("language", "Old rules\n\n<!-- vibe-check:begin nested -->")
+        );
+
+        // Region content is replaced, text outside the regions is kept
+        let (updated, missing, emptied) = replace_managed_regions(&existing, &merged);
+        assert_eq!(
+            updated,
+            format!(
+                "# AGENTS.md\n\nOur own intro\n\n{}\n\nNotes the user wrote\n\n{}\n",
+                managed_region("mission", "New mission"),
+                managed_region("language", "New rules")
+            )
+        );
+        assert!(missing.is_empty());
+        assert!(emptied.is_empty());
+
+        // Regions removed by the user are reported and not restored
+        let existing = format!("Only my text\n\n{}\n", managed_region("mission", "Old mission"));
+        let (updated, missing, _) = replace_managed_regions(&existing, &merged);
+        assert_eq!(updated, format!("Only my text\n\n{}\n", managed_region("mission", "New mission")));
+        assert_eq!(missing, vec!["language".to_string()]);
+
+        // Regions of categories that are no longer selected are emptied
+        let existing = format!("{}\n\nMine\n\n{}\n", managed_region("mission", "Old mission"), managed_region("integration", "Old git rules"));
+        let (updated, _, emptied) = replace_managed_regions(&existing, &merged);
+        assert_eq!(updated, format!("{}\n\nMine\n\n{}\n", managed_region("mission", "New mission"), managed_region("integration", "")));
+        assert_eq!(emptied, vec!["integration".to_string()]);
+
+        // Files without regions are left unchanged
+        let (updated, missing, _) = replace_managed_regions("Plain file\n", &merged);
+        assert_eq!(updated, "Plain file\n");
+        assert_eq!(missing.len(), 2);
+    }
+}
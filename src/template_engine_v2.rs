@@ -15,6 +15,7 @@
 
 use crate::{
     Result,
+    bom::{FileMapping, FileMode, MergePreference},
     file_tracker::FileTracker,
     template_engine::{self, CopyFilesResult, TemplateContext, TemplateEngine, UpdateOptions}
 };
@@ -60,12 +61,7 @@ pub fn new(config_dir: &'a Path) -> Self
     ///
     /// # Arguments
     ///
-    /// * `lang` - Programming language or framework identifier (ignored when no_lang)
-    /// * `agent` - Optional agent identifier for copying agent-specific files
-    /// * `no_lang` - If true, skip language-specific fragments
-    /// * `mission` - Optional custom mission statement to override template default
-    /// * `force` - If true, overwrite local modifications without warning
-    /// * `dry_run` - If true, only show what would happen without making changes
+    /// * `options` - Language, optional agent, mission, force/dry-run flags, and template variables
     ///
     /// # Errors
     ///
@@ -73,7 +69,7 @@ pub fn new(config_dir: &'a Path) -> Self
     /// - Global templates don't exist
     /// - Local modifications detected and force is false
     /// - Copy operations fail
-    pub fn update(&self, lang: &str, agent: Option<&str>, no_lang: bool, mission: Option<&str>, force: bool, dry_run: bool) -> Result<()>
+    pub fn update(&self, options: &UpdateOptions) -> Result<()>
     {
         let templates_yml_path = self.config_dir.join("templates.yml");
 
@@ -103,11 +99,12 @@ pub fn update(&self, lang: &str, agent: Option<&str>, no_lang: bool, mission: Op
         let main_target = self.resolve_placeholder(&main_config.target, &workspace, &userprofile);
 
         // Collect files to copy and fragments to merge
-        let mut files_to_copy: Vec<(PathBuf, PathBuf)> = Vec::new();
+        let mut files_to_copy: Vec<(PathBuf, PathBuf, FileMode, MergePreference, bool)> = Vec::new();
         let mut fragments: Vec<(PathBuf, String)> = Vec::new();
 
         // Helper closure to process file entries
-        let mut process_entry = |source: &str, target: &str, category: &str| {
+        let mut process_entry = |entry: &FileMapping, category: &str| {
+            let (source, target) = (entry.source.as_str(), entry.target.as_str());
             let source_path = self.config_dir.join(source);
             if source_path.exists() == false
             {
@@ -121,7 +118,7 @@ pub fn update(&self, lang: &str, agent: Option<&str>, no_lang: bool, mission: Op
             else
             {
                 let target_path = self.resolve_placeholder(target, &workspace, &userprofile);
-                files_to_copy.push((source_path, target_path));
+                files_to_copy.push((source_path, target_path, entry.mode.unwrap_or_default(), entry.prefer.unwrap_or_default(), entry.render == Some(true)));
             }
         };
 
@@ -130,33 +127,33 @@ pub fn update(&self, lang: &str, agent: Option<&str>, no_lang: bool, mission: Op
         {
             for entry in principles_entries
             {
-                process_entry(&entry.source, &entry.target, "principles");
+                process_entry(entry, "principles");
             }
         }
 
         // Add mission templates (fragments) if present, unless custom mission is provided
-        if mission.is_none() == true &&
+        if options.mission.is_none() == true &&
             let Some(mission_entries) = &config.mission
         {
             for entry in mission_entries
             {
-                process_entry(&entry.source, &entry.target, "mission");
+                process_entry(entry, "mission");
             }
         }
 
         // Add language-specific templates (fragments) unless --no-lang
-        if no_lang == false
+        if options.no_lang == false
         {
-            if let Some(lang_config) = config.languages.get(lang)
+            if let Some(lang_config) = config.languages.get(options.lang)
             {
                 for file_entry in &lang_config.files
                 {
-                    process_entry(&file_entry.source, &file_entry.target, "languages");
+                    process_entry(file_entry, "languages");
                 }
             }
             else
             {
-                return Err(format!("Language '{}' not found in templates.yml", lang).into());
+                return Err(format!("Language '{}' not found in templates.yml", options.lang).into());
             }
         }
 
@@ -167,13 +164,13 @@ pub fn update(&self, lang: &str, agent: Option<&str>, no_lang: bool, mission: Op
             {
                 for file_entry in &integration_config.files
                 {
-                    process_entry(&file_entry.source, &file_entry.target, "integration");
+                    process_entry(file_entry, "integration");
                 }
             }
         }
 
         // Process agent-specific instruction and prompt files if agent is specified
-        if let Some(agent_name) = agent &&
+        if let Some(agent_name) = options.agent &&
             let Some(agents) = config.agents.as_ref()
         {
             if let Some(agent_config) = agents.get(agent_name)
@@ -187,7 +184,13 @@ pub fn update(&self, lang: &str, agent: Option<&str>, no_lang: bool, mission: Op
                         if source_path.exists()
                         {
                             let target_path = self.resolve_placeholder(&instruction.target, &workspace, &userprofile);
-                            files_to_copy.push((source_path, target_path));
+                            files_to_copy.push((
+                                source_path,
+                                target_path,
+                                instruction.mode.unwrap_or_default(),
+                                instruction.prefer.unwrap_or_default(),
+                                instruction.render == Some(true)
+                            ));
                         }
                     }
                 }
@@ -201,7 +204,13 @@ pub fn update(&self, lang: &str, agent: Option<&str>, no_lang: bool, mission: Op
                         if source_path.exists()
                         {
                             let target_path = self.resolve_placeholder(&prompt.target, &workspace, &userprofile);
-                            files_to_copy.push((source_path, target_path));
+                            files_to_copy.push((
+                                source_path,
+                                target_path,
+                                prompt.mode.unwrap_or_default(),
+                                prompt.prefer.unwrap_or_default(),
+                                prompt.render == Some(true)
+                            ));
                         }
                     }
                 }
@@ -215,7 +224,13 @@ pub fn update(&self, lang: &str, agent: Option<&str>, no_lang: bool, mission: Op
                         if source_path.exists()
                         {
                             let target_path = self.resolve_placeholder(&skill.target, &workspace, &userprofile);
-                            files_to_copy.push((source_path, target_path));
+                            files_to_copy.push((
+                                source_path,
+                                target_path,
+                                skill.mode.unwrap_or_default(),
+                                skill.prefer.unwrap_or_default(),
+                                skill.render == Some(true)
+                            ));
                         }
                     }
                 }
@@ -226,12 +241,12 @@ pub fn update(&self, lang: &str, agent: Option<&str>, no_lang: bool, mission: Op
             }
         }
 
-        // Build template context and update options
+        // Build template context
         let ctx = TemplateContext { source: main_source, target: main_target, fragments, template_version: config.version };
-        let options = UpdateOptions { lang, agent, no_lang, mission, force, dry_run };
 
-        // Check if main AGENTS.md has been customized (marker removed)
-        let skip_agents_md = ctx.target.exists() && template_engine::is_file_customized(&ctx.target)?;
+        // Check if main AGENTS.md has been customized (marker removed); files with
+        // managed regions are still updated inside those regions
+        let skip_agents_md = ctx.target.exists() && template_engine::is_file_customized(&ctx.target)? && template_engine::has_managed_regions(&ctx.target)? == false;
 
         if skip_agents_md && options.force == false
         {
@@ -246,15 +261,15 @@ pub fn update(&self, lang: &str, agent: Option<&str>, no_lang: bool, mission: Op
         // Dry run mode: just show what would happen
         if options.dry_run == true
         {
-            self.show_dry_run_files(&ctx, skip_agents_md, &options, &files_to_copy);
+            self.show_dry_run_files(&ctx, skip_agents_md, options, &files_to_copy);
             return Ok(());
         }
 
         // Handle main AGENTS.md with fragment merging
-        self.handle_main_template(&ctx, &options, skip_agents_md, &mut file_tracker)?;
+        self.handle_main_template(&ctx, options, skip_agents_md, &mut file_tracker)?;
 
         // Copy templates with file modification checking
-        let copy_result = self.copy_files_with_tracking(&files_to_copy, &mut file_tracker, &ctx, &options)?;
+        let copy_result = self.copy_files_with_tracking(&files_to_copy, &mut file_tracker, &ctx, options)?;
 
         match copy_result
         {
@@ -272,7 +287,7 @@ pub fn update(&self, lang: &str, agent: Option<&str>, no_lang: bool, mission: Op
         file_tracker.save()?;
 
         println!("{} Templates updated successfully", "✓".green());
-        if agent.is_some()
+        if options.agent.is_some()
         {
             println!("{} V2 templates: Single AGENTS.md + agent-specific files", "→".blue());
         }
@@ -0,0 +1,232 @@
+//! Template source lock file for vibe-check
+//!
+//! Records the resolved source, revision, and the SHA-256 of every template
+//! file in `templates.lock` inside the global template store, so that an
+//! update can be verified to reproduce exactly the same templates. For layered
+//! sources the parents (`extends:`), the overlays, and the layer each file came
+//! from are recorded as well.
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Result, bom::TemplateConfig, file_tracker::FileTracker};
+
+/// File name of the lock file inside the template store
+pub const LOCK_FILE_NAME: &str = "templates.lock";
+
+/// Lock file contents
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TemplateLock
+{
+    /// Source the templates were installed from (as given to `update`)
+    pub source:    String,
+    /// Resolved commit, ref, or archive checksum (if known for the source type)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revision:  Option<String>,
+    /// Release tag selected with `update --version` (e.g. `v7.1.0`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version:   Option<String>,
+    /// Semver range the release tag was selected with (e.g. `^7`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range:     Option<String>,
+    /// Parent sources named by `extends:` in templates.yml, outermost first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parents:   Vec<LockedLayer>,
+    /// Overlay sources applied on top of `source`, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub overlays:  Vec<LockedLayer>,
+    /// Time the lock file was generated (RFC 3339)
+    pub generated: String,
+    /// SHA-256 of templates.yml and every referenced source file, keyed by relative path
+    pub files:     BTreeMap<String, String>,
+    /// Source of every file inherited from a parent or added by an overlay (all other files come from `source`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub origins:   BTreeMap<String, String>
+}
+
+/// A parent or overlay source recorded in the lock file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockedLayer
+{
+    /// Source the layer was fetched from
+    pub source:   String,
+    /// Resolved commit, ref, or archive checksum (if known for the source type)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revision: Option<String>
+}
+
+impl TemplateLock
+{
+    /// Generates a lock for the templates in a directory
+    ///
+    /// # Arguments
+    ///
+    /// * `template_dir` - Directory containing templates.yml and its source files
+    /// * `config` - Parsed templates.yml from that directory
+    /// * `source` - Source the templates were fetched from
+    /// * `revision` - Resolved commit or ref, if known
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file cannot be hashed
+    pub fn generate(template_dir: &Path, config: &TemplateConfig, source: &str, revision: Option<String>) -> Result<Self>
+    {
+        let mut files = BTreeMap::new();
+        files.insert("templates.yml".to_string(), FileTracker::calculate_sha256(&template_dir.join("templates.yml"))?);
+
+        for file in config.source_files()
+        {
+            files.insert(file.to_string(), FileTracker::calculate_sha256(&template_dir.join(file))?);
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            revision,
+            version: None,
+            range: None,
+            parents: Vec::new(),
+            overlays: Vec::new(),
+            generated: chrono::Utc::now().to_rfc3339(),
+            files,
+            origins: BTreeMap::new()
+        })
+    }
+
+    /// Returns true if the templates were built from more than one source
+    pub fn is_layered(&self) -> bool
+    {
+        self.parents.is_empty() == false || self.overlays.is_empty() == false
+    }
+
+    /// Returns the source of the layer a file came from
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - Path relative to the template directory
+    pub fn origin(&self, file: &str) -> &str
+    {
+        self.origins.get(file).map(String::as_str).unwrap_or(&self.source)
+    }
+
+    /// Loads the lock file from a template directory
+    ///
+    /// Returns None if no lock file exists.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock file cannot be read or parsed
+    pub fn load(template_dir: &Path) -> Result<Option<Self>>
+    {
+        let path = template_dir.join(LOCK_FILE_NAME);
+        if path.exists() == false
+        {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(&path)?;
+        Ok(Some(serde_yaml::from_str(&content)?))
+    }
+
+    /// Writes the lock file into a template directory
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock file cannot be written
+    pub fn save(&self, template_dir: &Path) -> Result<()>
+    {
+        let content = serde_yaml::to_string(self)?;
+        fs::write(template_dir.join(LOCK_FILE_NAME), content)?;
+        Ok(())
+    }
+
+    /// Compares freshly fetched templates against this lock
+    ///
+    /// # Arguments
+    ///
+    /// * `fetched` - Lock generated for the fetched templates
+    ///
+    /// # Returns
+    ///
+    /// A list of human-readable differences (empty if the templates match)
+    pub fn differences(&self, fetched: &TemplateLock) -> Vec<String>
+    {
+        let mut differences = Vec::new();
+
+        if self.source != fetched.source
+        {
+            differences.push(format!("source changed: {} -> {}", self.source, fetched.source));
+        }
+
+        if self.revision != fetched.revision
+        {
+            differences.push(format!("revision changed: {} -> {}", self.revision.as_deref().unwrap_or("<none>"), fetched.revision.as_deref().unwrap_or("<none>")));
+        }
+
+        if self.version != fetched.version
+        {
+            differences.push(format!("version changed: {} -> {}", self.version.as_deref().unwrap_or("<none>"), fetched.version.as_deref().unwrap_or("<none>")));
+        }
+
+        if self.parents != fetched.parents
+        {
+            differences.push(format!("parents changed: {} -> {}", describe_layers(&self.parents), describe_layers(&fetched.parents)));
+        }
+
+        if self.overlays != fetched.overlays
+        {
+            differences.push(format!("overlays changed: {} -> {}", describe_layers(&self.overlays), describe_layers(&fetched.overlays)));
+        }
+
+        differences.extend(self.changed_files(fetched));
+
+        differences
+    }
+
+    /// Lists the template files that differ between this lock and a newer one
+    ///
+    /// # Arguments
+    ///
+    /// * `fetched` - Lock generated for the fetched templates
+    ///
+    /// # Returns
+    ///
+    /// One `<file>: content changed|added|removed` entry per changed file, sorted by path
+    pub fn changed_files(&self, fetched: &TemplateLock) -> Vec<String>
+    {
+        let mut changes = Vec::new();
+
+        for (file, sha) in &self.files
+        {
+            match fetched.files.get(file)
+            {
+                | Some(fetched_sha) if fetched_sha == sha =>
+                {}
+                | Some(_) => changes.push(format!("{}: content changed", file)),
+                | None => changes.push(format!("{}: removed", file))
+            }
+        }
+
+        for file in fetched.files.keys()
+        {
+            if self.files.contains_key(file) == false
+            {
+                changes.push(format!("{}: added", file));
+            }
+        }
+
+        changes.sort();
+        changes
+    }
+}
+
+/// Formats a list of layers as `source (revision), ...` for difference reports
+fn describe_layers(layers: &[LockedLayer]) -> String
+{
+    if layers.is_empty() == true
+    {
+        return "<none>".to_string();
+    }
+
+    layers.iter().map(|l| format!("{} ({})", l.source, l.revision.as_deref().unwrap_or("<none>"))).collect::<Vec<_>>().join(", ")
+}
@@ -2,15 +2,16 @@
 
 use owo_colors::OwoColorize;
 
-use super::TemplateManager;
-use crate::{Result, bom::BillOfMaterials, template_engine};
+use super::{TemplateManager, print_layers};
+use crate::{Result, bom::BillOfMaterials, template_engine, template_lock::TemplateLock};
 
 impl TemplateManager
 {
     /// List available agents and languages
     ///
     /// Displays all available agents and languages from the global templates,
-    /// along with their installation status in the current project.
+    /// along with their installation status in the current project. For layered
+    /// templates, every template file is listed under the layer it came from.
     ///
     /// # Errors
     ///
@@ -104,6 +105,13 @@ pub fn list(&self) -> Result<()>
         }
 
         println!();
+
+        if let Ok(Some(lock)) = TemplateLock::load(&self.config_dir) &&
+            lock.is_layered() == true
+        {
+            print_layers(&lock);
+        }
+
         println!("{} Use 'vibe-check init --lang <lang> --agent <agent>' to install", "→".blue());
 
         Ok(())
@@ -1,19 +1,64 @@
 //! Template management functionality for vibe-check
 
 mod list;
+mod pack;
 mod purge;
 mod remove;
+mod rollback;
 mod status;
 mod update;
 
 use std::{
+    collections::BTreeMap,
     fs, io,
     path::{Path, PathBuf}
 };
 
 use owo_colors::OwoColorize;
+pub use update::InitOptions;
 
-use crate::{Result, download_manager::DownloadManager, utils::copy_dir_all};
+use crate::{
+    Result,
+    archive_source::ArchiveSource,
+    bundle_signature::{self, MANIFEST_FILE_NAME, SIGNATURE_FILE_NAME},
+    config::Config,
+    download_manager::DownloadManager,
+    embedded_templates::{self, EMBEDDED_SOURCE},
+    file_tracker::{FileTracker, ORIGINALS_DIR_NAME},
+    git_source::{self, GitSource},
+    http_cache::HTTP_CACHE_FILE_NAME,
+    pack_registry::PackRegistry,
+    template_engine,
+    template_lock::{LOCK_FILE_NAME, LockedLayer, TemplateLock},
+    utils::copy_dir_all,
+    version_range::{self, PinnedSource}
+};
+
+/// Files in the template store that hold local state rather than template content
+///
+/// These are carried over when a new template store is swapped in.
+const STORE_STATE_FILES: &[&str] = &["installed_files.json", ORIGINALS_DIR_NAME];
+
+/// Maximum number of `extends:` levels followed before giving up
+const MAX_EXTENDS_DEPTH: usize = 8;
+
+/// Files of a layer that describe the layer itself and are never copied into the store
+const LAYER_METADATA_FILES: &[&str] = &["templates.yml", LOCK_FILE_NAME, MANIFEST_FILE_NAME, SIGNATURE_FILE_NAME, HTTP_CACHE_FILE_NAME];
+
+/// Revisions and file origins of the layers fetched for an update
+struct FetchedLayers
+{
+    /// Resolved revision of the base source
+    revision: Option<String>,
+    /// Release tag and semver range the base source was pinned to
+    version:  Option<(String, String)>,
+    /// Parents resolved from `extends:`, outermost first
+    parents:  Vec<LockedLayer>,
+    /// Overlays applied on top of the base source, in order
+    overlays: Vec<LockedLayer>,
+    /// Source of every file inherited from a parent or added by an overlay
+    origins:  BTreeMap<String, String>
+}
 
 /// Manages template files for coding agent instructions
 ///
@@ -21,9 +66,16 @@
 /// verification, and synchronization. Templates are stored in the
 /// local data directory (e.g., `$HOME/.local/share/vibe-check/templates` on Linux,
 /// `$HOME/Library/Application Support/vibe-check/templates` on macOS).
+/// When a template pack is selected, its store in `packs/<name>` is used instead.
 pub struct TemplateManager
 {
-    pub(crate) config_dir: PathBuf
+    pub(crate) config_dir: PathBuf,
+    pub(crate) cache_dir:  PathBuf,
+    pub(crate) data_dir:   PathBuf,
+    pub(crate) pack:       Option<String>,
+    pub(crate) offline:    bool,
+    /// User configuration (loaded from the config file when needed if not set)
+    pub(crate) settings:   Option<Config>
 }
 
 impl TemplateManager
@@ -31,18 +83,123 @@ impl TemplateManager
     /// Creates a new TemplateManager instance
     ///
     /// Initializes path to local data directory using the `dirs` crate.
-    /// Templates are stored in the local data directory.
+    /// Templates are stored in the local data directory. Git sources are
+    /// cached in the user's cache directory (falling back to the data directory).
+    /// If a template pack is active (see `pack use`), its store is selected.
     ///
     /// # Errors
     ///
     /// Returns an error if the local data directory cannot be determined
     pub fn new() -> Result<Self>
     {
-        let data_dir = dirs::data_local_dir().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Could not determine local data directory"))?;
+        let local_dir = dirs::data_local_dir().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Could not determine local data directory"))?;
+
+        let data_dir = local_dir.join("vibe-check");
+        let cache_dir = dirs::cache_dir().unwrap_or_else(|| data_dir.join("cache")).join("vibe-check");
+
+        Ok(Self::with_dirs(data_dir, cache_dir))
+    }
+
+    /// Creates a TemplateManager for the given data and cache directories
+    ///
+    /// Selects the active pack from the registry. If the registry cannot be read
+    /// or the active pack is unknown or not installed, a warning is printed and
+    /// the default store is used, so `pack list` and `pack use` can still repair it.
+    pub(crate) fn with_dirs(data_dir: PathBuf, cache_dir: PathBuf) -> Self
+    {
+        let config_dir = data_dir.join("templates");
+        let mut manager = Self { config_dir, cache_dir, data_dir, pack: None, offline: false, settings: None };
+
+        let active = match PackRegistry::load(&manager.data_dir)
+        {
+            | Ok(registry) => registry.active,
+            | Err(e) =>
+            {
+                eprintln!("{} Warning: {}; using the default template store", "!".yellow(), e);
+                None
+            }
+        };
+
+        if let Some(active) = active
+        {
+            let mut selected = manager.select_pack(&active);
+            if selected.is_ok() == true && manager.config_dir.exists() == false
+            {
+                selected = Err(format!("the store of template pack '{}' is missing ({})", active, manager.config_dir.display()).into());
+            }
+
+            if let Err(e) = selected
+            {
+                eprintln!("{} Warning: {}; using the default template store", "!".yellow(), e);
+                manager.config_dir = manager.data_dir.join("templates");
+                manager.pack = None;
+            }
+        }
+
+        manager
+    }
+
+    /// Enables or disables offline mode
+    ///
+    /// In offline mode no source that needs network access is fetched; only the
+    /// installed templates and local paths, bundles, and `file://` repositories are used.
+    pub fn set_offline(&mut self, offline: bool)
+    {
+        self.offline = offline;
+    }
+
+    /// Sets the user configuration used for fetching templates
+    ///
+    /// Without it, the configuration is loaded from the config file whenever
+    /// it is needed (network settings, source provider, trusted keys).
+    pub fn set_config(&mut self, settings: Config)
+    {
+        self.settings = Some(settings);
+    }
+
+    /// Returns the user configuration used for fetching templates
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no configuration was set and the config file cannot be loaded
+    fn settings(&self) -> Result<Config>
+    {
+        match &self.settings
+        {
+            | Some(settings) => Ok(settings.clone()),
+            | None => Config::load()
+        }
+    }
+
+    /// Checks that the installed templates can be used without network access
+    ///
+    /// # Errors
+    ///
+    /// Returns an error listing what is missing if the global templates are not
+    /// installed or reference files that are not in the store
+    pub fn check_offline_store(&self) -> Result<()>
+    {
+        let hint = "Install them from a local path or bundle: vibe-check update --offline --from <path|bundle>";
+
+        if self.has_global_templates() == false
+        {
+            return Err(format!(
+                "Offline mode: global templates are not installed and cannot be downloaded.\n  missing: {}\n{}",
+                self.config_dir.join("templates.yml").display(),
+                hint
+            )
+            .into());
+        }
 
-        let config_dir = data_dir.join("vibe-check/templates");
+        let missing = template_engine::load_template_config(&self.config_dir)?.missing_sources(&self.config_dir);
+        if missing.is_empty() == false
+        {
+            return Err(
+                format!("Offline mode: {} template file(s) are missing from the global templates:\n  {}\n{}", missing.len(), missing.join("\n  "), hint).into()
+            );
+        }
 
-        Ok(Self { config_dir })
+        Ok(())
     }
 
     /// Checks if global templates exist
@@ -59,26 +216,443 @@ pub fn get_config_dir(&self) -> &Path
         &self.config_dir
     }
 
-    /// Downloads or copies templates from a source (URL or local path)
+    /// Returns the staging directory used while an update is in progress
+    pub(crate) fn staging_dir(&self) -> PathBuf
+    {
+        self.config_dir.with_extension("staging")
+    }
+
+    /// Returns the scratch directory an overlay is fetched into before it is merged
+    pub(crate) fn overlay_dir(&self) -> PathBuf
+    {
+        self.config_dir.with_extension("overlay")
+    }
+
+    /// Returns the scratch directory for parent templates resolved from `extends:`
+    pub(crate) fn parents_dir(&self) -> PathBuf
+    {
+        self.cache_dir.join("extends")
+    }
+
+    /// Returns the directory holding the previous template store (for rollback)
+    pub(crate) fn previous_dir(&self) -> PathBuf
+    {
+        self.config_dir.with_extension("previous")
+    }
+
+    /// Downloads or copies templates from a source (URL, git repository, or local path)
+    ///
+    /// Supports local file paths, URLs, git repositories, and archives. For URLs starting with
+    /// http/https, templates are downloaded. For `git+https://`, `git+ssh://`, and `file://`
+    /// sources, the repository is cloned into the cache and the requested ref is exported.
+    /// For `.tar.gz`, `.tar.zst`, and `.zip` archives (local or URL), the archive is unpacked
+    /// and its template root installed. For local paths, templates are copied.
     ///
-    /// Supports both local file paths and URLs. For URLs starting with http/https,
-    /// templates are downloaded. For local paths, templates are copied.
+    /// The update is staged: templates are fetched into a staging directory, validated
+    /// (every `source` referenced by templates.yml must exist), and only then swapped in
+    /// for the live store. The replaced store is kept for `update --rollback`. If any step
+    /// fails, the live store is left untouched.
+    ///
+    /// Every successful update writes `templates.lock`, recording the source, the resolved
+    /// revision, and the SHA-256 of each template file. With `locked`, the fetched templates
+    /// must match the existing lock exactly or the update is refused; the source and every
+    /// parent and overlay are fetched at the commit recorded for them. Otherwise the template
+    /// files that changed since the last update are reported.
+    ///
+    /// Overlays are fetched after the base source and merged into it in order: their
+    /// templates.yml entries are merged (see `TemplateConfig::merge_overlay`) and their
+    /// files replace files of the same path. The layer of each file is recorded in the lock.
+    ///
+    /// With a `version` range, git and hosted repository sources are pinned to the newest
+    /// matching release tag, which is recorded in the lock. A locked update of a store
+    /// installed from a release tag fetches that same tag again.
     ///
     /// # Arguments
     ///
     /// * `source` - Path or URL to download/copy templates from
+    /// * `overlays` - Sources layered on top of `source`, in order
+    /// * `version` - Optional semver range selecting a release tag of `source`
+    /// * `locked` - If true, refuse the update unless it reproduces the existing templates.lock
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if download, copy, or validation fails, or if `locked` is set and
+    /// no lock exists or the fetched templates differ from it
+    pub fn download_or_copy_templates(&self, source: &str, overlays: &[String], version: Option<&str>, locked: bool) -> Result<()>
+    {
+        if self.offline == true &&
+            let Some(remote) = std::iter::once(source).chain(overlays.iter().map(String::as_str)).find(|s| requires_network(s) == true)
+        {
+            return Err(format!(
+                "Offline mode: cannot fetch templates from {} (network access is disabled). Use a local path, bundle, or file:// repository with --from.",
+                remote
+            )
+            .into());
+        }
+
+        let existing_lock = if locked == true
+        {
+            Some(
+                TemplateLock::load(&self.config_dir)?
+                    .ok_or("No templates.lock found in the global template store. Run 'vibe-check update' without --locked first.")?
+            )
+        }
+        else
+        {
+            // Only used to report changes, so an unreadable lock is not an error
+            TemplateLock::load(&self.config_dir).ok().flatten()
+        };
+        let required_lock = if locked == true
+        {
+            existing_lock.as_ref()
+        }
+        else
+        {
+            None
+        };
+
+        let settings = self.settings()?;
+        let pinned = self.pin_version(source, version, required_lock)?;
+        let mut fetch_source = pinned.as_ref().map_or(source.to_string(), |(pinned, _)| pinned.source.clone());
+
+        // A locked update downloads exactly the commit recorded in the lock
+        if let Some(commit) = required_lock.and_then(|lock| lock.revision.as_deref()).filter(|revision| git_source::is_commit_sha(revision) == true) &&
+            let Some(pinned) = version_range::pin_commit(source, commit, &settings)
+        {
+            println!("{} Fetching locked commit {}", "→".blue(), commit.yellow());
+            fetch_source = pinned;
+        }
+
+        // ...and so does every parent and overlay recorded in it
+        let pins = required_lock.map_or_else(BTreeMap::new, |lock| pin_locked_layers(lock, &settings));
+
+        // Conditional requests copy unchanged files from the live store, which only
+        // holds the unmodified base files if no overlay or parent was merged into it
+        // (its templates.yml would be the merged one, without `extends`)
+        let layered = overlays.is_empty() == false || existing_lock.as_ref().is_some_and(|lock| lock.overlays.is_empty() == false || lock.parents.is_empty() == false);
+
+        let staging_dir = self.staging_dir();
+        if staging_dir.exists() == true
+        {
+            fs::remove_dir_all(&staging_dir)?;
+        }
+        fs::create_dir_all(&staging_dir)?;
+
+        let result = self
+            .fetch_layers(&fetch_source, overlays, &pins, &staging_dir, layered == false)
+            .map(|mut layers| {
+                layers.version = pinned.map(|(pinned, range)| (pinned.tag, range));
+                layers
+            })
+            .and_then(|layers| self.lock_staged(&staging_dir, source, layers, required_lock))
+            .and_then(|lock| self.swap_in_staged(&staging_dir).map(|_| lock));
+
+        match &result
+        {
+            | Ok(_) if locked == true =>
+            {
+                println!("{} Global templates match templates.lock", "✓".green());
+            }
+            | Ok(lock) =>
+            {
+                if let Some(previous) = &existing_lock
+                {
+                    report_changes(&previous.changed_files(lock));
+                }
+                println!("{} Global templates updated (use 'vibe-check update --rollback' to restore the previous version)", "✓".green());
+            }
+            | Err(_) =>
+            {
+                let _ = fs::remove_dir_all(&staging_dir);
+                let _ = fs::remove_dir_all(self.overlay_dir());
+                let _ = fs::remove_dir_all(self.parents_dir());
+                if self.has_global_templates() == true
+                {
+                    println!("{} Global templates were left unchanged", "→".blue());
+                }
+            }
+        }
+
+        result.map(|_| ())
+    }
+
+    /// Installs the template snapshot embedded into the binary
+    ///
+    /// Last resort for a first run without network access: the `templates/v2` tree
+    /// the binary was built with is installed like an update (staged and validated)
+    /// and recorded in templates.lock with the `embedded` source, so `status` can
+    /// report it. The next successful `update` replaces it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the snapshot cannot be written or a template pack is selected
+    pub fn install_embedded_templates(&self) -> Result<()>
+    {
+        self.install_snapshot(embedded_templates::EMBEDDED_FILES)
+    }
+
+    /// Installs a template snapshot as the embedded templates (see `install_embedded_templates`)
+    fn install_snapshot(&self, files: &[(&str, &[u8])]) -> Result<()>
+    {
+        if let Some(pack) = &self.pack
+        {
+            return Err(format!("The embedded templates cannot be installed into template pack '{}'", pack).into());
+        }
+
+        let staging_dir = self.staging_dir();
+        if staging_dir.exists() == true
+        {
+            fs::remove_dir_all(&staging_dir)?;
+        }
+        fs::create_dir_all(&staging_dir)?;
+
+        let layers =
+            FetchedLayers { revision: Some(embedded_templates::revision()), version: None, parents: Vec::new(), overlays: Vec::new(), origins: BTreeMap::new() };
+        let result = embedded_templates::extract(files, &staging_dir)
+            .and_then(|_| self.lock_staged(&staging_dir, EMBEDDED_SOURCE, layers, None))
+            .and_then(|_| self.swap_in_staged(&staging_dir));
+
+        if result.is_err() == true
+        {
+            let _ = fs::remove_dir_all(&staging_dir);
+            return result;
+        }
+
+        println!("{} Installed the templates embedded in {} (run 'vibe-check update' once online)", "✓".green(), embedded_templates::revision());
+        Ok(())
+    }
+
+    /// Returns true if the installed templates are the snapshot embedded into the binary
+    pub fn uses_embedded_templates(&self) -> bool
+    {
+        TemplateLock::load(&self.config_dir).ok().flatten().is_some_and(|lock| lock.source == EMBEDDED_SOURCE)
+    }
+
+    /// Pins the source to a release tag for `update --version` or a locked update
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - Template source
+    /// * `version` - Semver range given to `update --version`
+    /// * `required_lock` - Lock a locked update must reproduce (its release tag is reused)
+    ///
+    /// # Returns
+    ///
+    /// The pinned source and the range it was selected with, or None if no release is requested
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the source has no tags or no tag matches the range
+    fn pin_version(&self, source: &str, version: Option<&str>, required_lock: Option<&TemplateLock>) -> Result<Option<(PinnedSource, String)>>
+    {
+        if let Some(lock) = required_lock &&
+            let Some(tag) = &lock.version
+        {
+            let pinned = version_range::pin_tag(source, tag, &self.settings()?)?;
+            return Ok(Some((pinned, lock.range.clone().unwrap_or_default())));
+        }
+
+        let Some(range) = version
+        else
+        {
+            return Ok(None);
+        };
+
+        println!("{} Resolving version range {}...", "→".blue(), range.yellow());
+        let pinned = version_range::resolve_range(source, range, &self.settings()?)?;
+        println!("{} Selected release {} ({})", "✓".green(), pinned.version.to_string().green(), pinned.tag.yellow());
+        Ok(Some((pinned, range.to_string())))
+    }
+
+    /// Fetches the base source and all overlays into the staging directory
+    ///
+    /// Every layer is checked against the trusted keys before it is merged when
+    /// `verify.enabled` is set, and the `extends:` chain of every layer is resolved.
+    /// The merged templates.yml is written to the staging directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - Base template source
+    /// * `overlays` - Sources layered on top of the base, in order
+    /// * `pins` - Source to fetch instead of a parent or overlay source (for `update --locked`)
+    /// * `staging_dir` - Staged template store
+    /// * `use_cache` - Send conditional requests against the live store for the base source
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if fetching, signature verification, or merging any layer fails
+    fn fetch_layers(&self, source: &str, overlays: &[String], pins: &BTreeMap<String, String>, staging_dir: &Path, use_cache: bool) -> Result<FetchedLayers>
+    {
+        let settings = self.settings()?;
+
+        let revision = self.fetch_templates(source, staging_dir, use_cache)?;
+        verify_layer(staging_dir, &settings)?;
+
+        let mut parents = Vec::new();
+        let origins = self.resolve_extends(staging_dir, source, &mut vec![source.to_string()], &settings, pins, &mut parents)?;
+
+        let mut layers = FetchedLayers { revision, version: None, parents, overlays: Vec::new(), origins };
+        if overlays.is_empty() == true
+        {
+            return Ok(layers);
+        }
+
+        let mut config = template_engine::load_template_config(staging_dir)?;
+        let overlay_dir = self.overlay_dir();
+
+        for overlay in overlays
+        {
+            println!("{} Applying overlay {}...", "→".blue(), overlay.yellow());
+            if overlay_dir.exists() == true
+            {
+                fs::remove_dir_all(&overlay_dir)?;
+            }
+            fs::create_dir_all(&overlay_dir)?;
+
+            let revision = self.fetch_templates(locked_source(overlay, pins), &overlay_dir, false)?;
+            verify_layer(&overlay_dir, &settings)?;
+            let inherited = self.resolve_extends(&overlay_dir, overlay, &mut vec![overlay.clone()], &settings, pins, &mut layers.parents)?;
+
+            let overlay_config = template_engine::load_template_config(&overlay_dir).map_err(|e| format!("Overlay {} is invalid: {}", overlay, e))?;
+            config.merge_overlay(overlay_config).map_err(|e| format!("Cannot apply overlay {}: {}", overlay, e))?;
+
+            for file in copy_layer_files(&overlay_dir, staging_dir, Path::new(""), true)?
+            {
+                let origin = inherited.get(&file).cloned().unwrap_or_else(|| overlay.clone());
+                layers.origins.insert(file, origin);
+            }
+            layers.overlays.push(LockedLayer { source: overlay.clone(), revision });
+        }
+
+        fs::remove_dir_all(&overlay_dir)?;
+        config.validate_paths()?;
+        fs::write(staging_dir.join("templates.yml"), config.to_yaml()?)?;
+
+        Ok(layers)
+    }
+
+    /// Resolves the `extends:` chain of a fetched layer in place
+    ///
+    /// The parent named by `extends:` is fetched (and its own parent resolved
+    /// recursively), the layer's templates.yml is merged over the parent's, and
+    /// parent files the layer does not provide are copied into the layer directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `layer_dir` - Directory the layer was fetched into
+    /// * `layer_source` - Source the layer was fetched from (relative `extends:` paths are resolved against it)
+    /// * `chain` - Sources visited so far, used to detect cycles
+    /// * `settings` - User configuration (signature verification)
+    /// * `pins` - Source to fetch instead of a parent source (for `update --locked`)
+    /// * `parents` - Receives every resolved parent, outermost first
+    ///
+    /// # Returns
+    ///
+    /// The source of every inherited file, keyed by path relative to the layer directory
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the chain has a cycle or is too deep, or a parent cannot be fetched or merged
+    fn resolve_extends(
+        &self, layer_dir: &Path, layer_source: &str, chain: &mut Vec<String>, settings: &Config, pins: &BTreeMap<String, String>, parents: &mut Vec<LockedLayer>
+    ) -> Result<BTreeMap<String, String>>
+    {
+        let mut config = template_engine::load_template_config(layer_dir)?;
+        let Some(extends) = config.extends.take()
+        else
+        {
+            return Ok(BTreeMap::new());
+        };
+
+        let parent_source = resolve_parent_source(layer_source, &extends)?;
+        if chain.contains(&parent_source) == true
+        {
+            return Err(format!("templates.yml extends chain contains a cycle: {} -> {}", chain.join(" -> "), parent_source).into());
+        }
+        if chain.len() > MAX_EXTENDS_DEPTH
+        {
+            return Err(format!("templates.yml extends chain is deeper than {} levels: {}", MAX_EXTENDS_DEPTH, chain.join(" -> ")).into());
+        }
+        if self.offline == true && requires_network(&parent_source) == true
+        {
+            return Err(format!("Offline mode: cannot fetch parent templates from {} (network access is disabled)", parent_source).into());
+        }
+        chain.push(parent_source.clone());
+
+        let parent_dir = self.parents_dir().join(chain.len().to_string());
+        if parent_dir.exists() == true
+        {
+            fs::remove_dir_all(&parent_dir)?;
+        }
+        fs::create_dir_all(&parent_dir)?;
+
+        println!("{} Resolving parent templates {}...", "→".blue(), parent_source.yellow());
+        let revision = self.fetch_templates(locked_source(&parent_source, pins), &parent_dir, false)?;
+        verify_layer(&parent_dir, settings)?;
+        let inherited = self.resolve_extends(&parent_dir, &parent_source, chain, settings, pins, parents)?;
+        parents.push(LockedLayer { source: parent_source.clone(), revision });
+
+        let mut merged = template_engine::load_template_config(&parent_dir)?;
+        merged.merge_overlay(config).map_err(|e| format!("Cannot extend {}: {}", parent_source, e))?;
+        merged.merge = None;
+        merged.validate_paths()?;
+
+        let mut origins = BTreeMap::new();
+        for file in copy_layer_files(&parent_dir, layer_dir, Path::new(""), false)?
+        {
+            let origin = inherited.get(&file).cloned().unwrap_or_else(|| parent_source.clone());
+            origins.insert(file, origin);
+        }
+        fs::write(layer_dir.join("templates.yml"), merged.to_yaml()?)?;
+        fs::remove_dir_all(&parent_dir)?;
+
+        Ok(origins)
+    }
+
+    /// Fetches templates from a source into a directory
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - Path, URL, archive, or git repository
+    /// * `dest` - Directory to place the template files in
+    /// * `use_cache` - Send conditional requests against the live store (hosted repositories)
+    ///
+    /// # Returns
+    ///
+    /// The resolved revision: the commit for git sources, the ref for hosted
+    /// repositories, the archive checksum for archives, and None for local paths
     ///
     /// # Errors
     ///
     /// Returns an error if download or copy operation fails
-    pub fn download_or_copy_templates(&self, source: &str) -> Result<()>
+    fn fetch_templates(&self, source: &str, dest: &Path, use_cache: bool) -> Result<Option<String>>
     {
-        if source.starts_with("http://") || source.starts_with("https://")
+        if let Some(git_source) = GitSource::parse(source)
+        {
+            // Clone/fetch into the cache and export the requested ref
+            println!("{} Fetching templates from git repository {}...", "→".blue(), git_source.url.yellow());
+            if let Some(refname) = &git_source.refname
+            {
+                println!("{} Ref: {}", "→".blue(), refname.yellow());
+            }
+            let commit = git_source.fetch(&self.cache_dir.join("git"), dest, &self.settings()?.network)?;
+            println!("{} Templates exported from commit {}", "✓".green(), commit.green());
+            Ok(Some(commit))
+        }
+        else if let Some(archive) = ArchiveSource::parse(source)
+        {
+            Ok(Some(self.install_archive(&archive, dest)?))
+        }
+        else if source.starts_with("http://") || source.starts_with("https://")
         {
             // Download from URL using DownloadManager
             println!("{} Downloading templates from URL...", "→".blue());
-            let download_manager = DownloadManager::new(self.config_dir.clone());
-            download_manager.download_templates_from_url(source)?;
+            let mut download_manager = DownloadManager::new(dest.to_path_buf(), self.settings()?)?;
+            if use_cache == true
+            {
+                download_manager = download_manager.with_cache(&self.config_dir);
+            }
+            Ok(Some(download_manager.download_templates_from_url(source)?))
         }
         else
         {
@@ -90,10 +664,844 @@ pub fn download_or_copy_templates(&self, source: &str) -> Result<()>
             }
 
             println!("{} Copying templates from local path...", "→".blue());
-            fs::create_dir_all(&self.config_dir)?;
-            copy_dir_all(source_path, &self.config_dir)?;
+            copy_dir_all(source_path, dest)?;
+            Ok(None)
+        }
+    }
+
+    /// Validates a staged template store and writes its lock file
+    ///
+    /// Checks that templates.yml parses and that every referenced source file exists,
+    /// then records the revisions, file origins, and file checksums in templates.lock.
+    /// If an existing lock is given, the staged templates must match it exactly.
+    ///
+    /// # Arguments
+    ///
+    /// * `staging_dir` - Staged template store
+    /// * `source` - Source the templates were fetched from
+    /// * `layers` - Revisions and file origins returned by `fetch_layers`
+    /// * `existing_lock` - Lock the staged templates must reproduce (for `update --locked`)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error listing the missing files or the differences from the existing lock
+    fn lock_staged(&self, staging_dir: &Path, source: &str, layers: FetchedLayers, existing_lock: Option<&TemplateLock>) -> Result<TemplateLock>
+    {
+        let config = template_engine::load_template_config(staging_dir)?;
+        let missing = config.missing_sources(staging_dir);
+
+        if missing.is_empty() == false
+        {
+            return Err(
+                format!("Template source is incomplete, {} file(s) referenced by templates.yml are missing:\n  {}", missing.len(), missing.join("\n  ")).into()
+            );
+        }
+
+        let mut lock = TemplateLock::generate(staging_dir, &config, source, layers.revision)?;
+        if let Some((tag, range)) = layers.version
+        {
+            lock.version = Some(tag);
+            lock.range = Some(range);
+        }
+        lock.parents = layers.parents;
+        lock.overlays = layers.overlays;
+        lock.origins = layers.origins;
+
+        if let Some(existing) = existing_lock
+        {
+            let differences = existing.differences(&lock);
+            if differences.is_empty() == false
+            {
+                return Err(format!("Fetched templates do not match templates.lock:\n  {}", differences.join("\n  ")).into());
+            }
+        }
+
+        lock.save(staging_dir)?;
+        Ok(lock)
+    }
+
+    /// Replaces the live template store with the staged one
+    ///
+    /// Store-local state (the installed file tracker and the installed content
+    /// kept for merges) is carried over into the staged store. The current store is kept as the previous store for rollback.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a rename fails (the live store is restored if possible)
+    fn swap_in_staged(&self, staging_dir: &Path) -> Result<()>
+    {
+        for state_file in STORE_STATE_FILES
+        {
+            let current = self.config_dir.join(state_file);
+            if current.is_dir() == true
+            {
+                copy_dir_all(&current, &staging_dir.join(state_file))?;
+            }
+            else if current.exists() == true
+            {
+                fs::copy(&current, staging_dir.join(state_file))?;
+            }
         }
 
+        let previous_dir = self.previous_dir();
+        if previous_dir.exists() == true
+        {
+            fs::remove_dir_all(&previous_dir)?;
+        }
+
+        if self.config_dir.exists() == true
+        {
+            fs::rename(&self.config_dir, &previous_dir)?;
+        }
+
+        if let Err(e) = fs::rename(staging_dir, &self.config_dir)
+        {
+            // Put the previous store back so the user is never left without templates
+            if previous_dir.exists() == true
+            {
+                let _ = fs::rename(&previous_dir, &self.config_dir);
+            }
+            return Err(format!("Failed to activate new templates: {}", e).into());
+        }
+
+        Ok(())
+    }
+
+    /// Installs templates from an archive (local file or URL)
+    ///
+    /// Remote archives are downloaded into the cache first. The archive is
+    /// unpacked into a scratch directory and the template root is copied
+    /// into the destination directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `archive` - Parsed archive source
+    /// * `dest` - Directory to place the template files in
+    ///
+    /// # Returns
+    ///
+    /// The archive checksum (`sha256:<hex>`), used as the revision in templates.lock
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if download, unpacking, or copying fails
+    fn install_archive(&self, archive: &ArchiveSource, dest: &Path) -> Result<String>
+    {
+        let work_dir = self.cache_dir.join("archives");
+        if work_dir.exists() == true
+        {
+            fs::remove_dir_all(&work_dir)?;
+        }
+        fs::create_dir_all(&work_dir)?;
+
+        let archive_path = if archive.is_remote() == true
+        {
+            println!("{} Downloading archive {}...", "→".blue(), archive.location.yellow());
+            let file_name = archive.location.split(['?', '#']).next().unwrap_or("").rsplit('/').next().unwrap_or("templates-archive");
+            let path = work_dir.join(file_name);
+            DownloadManager::new(dest.to_path_buf(), self.settings()?)?.download_file(&archive.location, &path)?;
+            path
+        }
+        else
+        {
+            let path = PathBuf::from(&archive.location);
+            if path.exists() == false
+            {
+                return Err(format!("Archive does not exist: {}", archive.location).into());
+            }
+            path
+        };
+
+        let checksum = format!("sha256:{}", FileTracker::calculate_sha256(&archive_path)?);
+
+        println!("{} Unpacking archive...", "→".blue());
+        let root = archive.unpack(&archive_path, &work_dir.join("unpacked"))?;
+
+        copy_dir_all(&root, dest)?;
+        fs::remove_dir_all(&work_dir)?;
+
+        println!("{} Templates installed from archive", "✓".green());
+        Ok(checksum)
+    }
+}
+
+/// Verifies the bundle signature of a fetched layer when `verify.enabled` is set
+///
+/// # Errors
+///
+/// Returns an error if templates.yml cannot be loaded or the signature check fails
+fn verify_layer(layer_dir: &Path, settings: &Config) -> Result<()>
+{
+    if settings.verify.enabled == Some(true)
+    {
+        let config = template_engine::load_template_config(layer_dir)?;
+        let trusted_keys = settings.verify.trusted_keys.clone().unwrap_or_default();
+        let comment = bundle_signature::verify_bundle(layer_dir, &config, &trusted_keys)?;
+        println!("{} Template bundle signature verified ({})", "✓".green(), comment);
+    }
+
+    Ok(())
+}
+
+/// Copies the files of a layer into another layer or the staged store
+///
+/// Layer metadata (templates.yml, lock, manifest, signature, HTTP cache) is skipped.
+///
+/// # Arguments
+///
+/// * `layer_dir` - Directory the layer was fetched into
+/// * `staging_dir` - Directory receiving the files
+/// * `relative` - Subdirectory currently being copied (empty for the layer root)
+/// * `replace` - Replace existing files (overlays) or keep them (inherited parent files)
+///
+/// # Returns
+///
+/// The copied files as `/`-separated paths relative to the layer root
+fn copy_layer_files(layer_dir: &Path, staging_dir: &Path, relative: &Path, replace: bool) -> Result<Vec<String>>
+{
+    let mut copied = Vec::new();
+
+    for entry in fs::read_dir(layer_dir.join(relative))?
+    {
+        let entry = entry?;
+        let path = relative.join(entry.file_name());
+
+        if entry.file_type()?.is_dir() == true
+        {
+            copied.extend(copy_layer_files(layer_dir, staging_dir, &path, replace)?);
+            continue;
+        }
+
+        let name = path.to_string_lossy().replace('\\', "/");
+        if relative.as_os_str().is_empty() == true && (LAYER_METADATA_FILES.contains(&name.as_str()) || STORE_STATE_FILES.contains(&name.as_str()))
+        {
+            continue;
+        }
+
+        let target = staging_dir.join(&path);
+        if replace == false && target.exists() == true
+        {
+            continue;
+        }
+        if let Some(parent) = target.parent()
+        {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(entry.path(), &target)?;
+        copied.push(name);
+    }
+
+    Ok(copied)
+}
+
+/// Resolves the `extends:` value of a layer to a template source
+///
+/// Relative paths are resolved against the directory of a local layer. Remote
+/// layers must name their parent by a full source (URL, git repository, or archive).
+///
+/// # Errors
+///
+/// Returns an error for a relative `extends:` in a remote layer
+fn resolve_parent_source(layer_source: &str, extends: &str) -> Result<String>
+{
+    let is_relative_path = extends.contains("://") == false && extends.starts_with("git+") == false && Path::new(extends).is_absolute() == false;
+    if is_relative_path == false
+    {
+        return Ok(extends.to_string());
+    }
+
+    if requires_network(layer_source) == true || GitSource::parse(layer_source).is_some() || ArchiveSource::parse(layer_source).is_some()
+    {
+        return Err(format!("Relative extends '{}' is only supported for templates from a local directory; use a full source instead", extends).into());
+    }
+
+    let parent = Path::new(layer_source).join(extends);
+    Ok(fs::canonicalize(&parent).unwrap_or(parent).to_string_lossy().to_string())
+}
+
+/// Pins every parent and overlay recorded in a lock to its locked commit
+///
+/// Layers without a commit (local paths, archives) are fetched as recorded; the
+/// locked update still compares their revisions and file checksums.
+///
+/// # Arguments
+///
+/// * `lock` - Lock a locked update must reproduce
+/// * `settings` - User configuration (provider detection)
+///
+/// # Returns
+///
+/// The pinned source of every layer recorded at a commit, keyed by its recorded source
+fn pin_locked_layers(lock: &TemplateLock, settings: &Config) -> BTreeMap<String, String>
+{
+    lock.parents
+        .iter()
+        .chain(&lock.overlays)
+        .filter_map(|layer| {
+            let commit = layer.revision.as_deref().filter(|revision| git_source::is_commit_sha(revision) == true)?;
+            version_range::pin_commit(&layer.source, commit, settings).map(|pinned| (layer.source.clone(), pinned))
+        })
+        .collect()
+}
+
+/// Returns the source to fetch for a layer, announcing a locked commit
+fn locked_source<'a>(source: &'a str, pins: &'a BTreeMap<String, String>) -> &'a str
+{
+    match pins.get(source)
+    {
+        | Some(pinned) =>
+        {
+            println!("{} Fetching locked {}", "→".blue(), pinned.yellow());
+            pinned
+        }
+        | None => source
+    }
+}
+
+/// Returns true if fetching from a source needs network access
+///
+/// Local paths, local archives, and `file://` git repositories work offline.
+fn requires_network(source: &str) -> bool
+{
+    if let Some(git_source) = GitSource::parse(source)
+    {
+        return git_source.url.starts_with("file://") == false;
+    }
+
+    source.starts_with("http://") || source.starts_with("https://")
+}
+
+/// Prints every template file grouped by the layer it came from
+///
+/// # Arguments
+///
+/// * `lock` - Lock file of the template store
+fn print_layers(lock: &TemplateLock)
+{
+    let mut layers: Vec<(String, &str, Option<&str>)> = lock.parents.iter().map(|p| ("extends".to_string(), p.source.as_str(), p.revision.as_deref())).collect();
+    layers.push(("base".to_string(), lock.source.as_str(), lock.revision.as_deref()));
+    layers.extend(lock.overlays.iter().enumerate().map(|(i, o)| (format!("overlay {}", i + 1), o.source.as_str(), o.revision.as_deref())));
+
+    println!("{}", "Template Layers:".bold());
+    for (name, source, revision) in layers
+    {
+        match revision
+        {
+            | Some(revision) => println!("  {} {}: {} ({})", "→".blue(), name.bold(), source.green(), revision.yellow()),
+            | None => println!("  {} {}: {}", "→".blue(), name.bold(), source.green())
+        }
+
+        // templates.yml is merged from all layers and not attributed to one
+        for file in lock.files.keys().filter(|file| file.as_str() != "templates.yml" && lock.origin(file) == source)
+        {
+            println!("    • {}", file.yellow());
+        }
+    }
+    println!();
+}
+
+/// Prints the template files that changed since the last update
+fn report_changes(changes: &[String])
+{
+    if changes.is_empty() == true
+    {
+        println!("{} No template changes since the last update", "→".blue());
+        return;
+    }
+
+    println!("{} {} template file(s) changed since the last update:", "→".blue(), changes.len());
+    for change in changes
+    {
+        println!("  {} {}", "●".yellow(), change);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::test_support::TestServer;
+
+    /// Creates a template manager whose store lives in a temporary directory
+    fn test_manager(temp_dir: &TempDir) -> TemplateManager
+    {
+        TemplateManager {
+            config_dir: temp_dir.path().join("data/templates"),
+            cache_dir:  temp_dir.path().join("cache"),
+            data_dir:   temp_dir.path().join("data"),
+            pack:       None,
+            offline:    false,
+            settings:   Some(Config::default())
+        }
+    }
+
+    /// Writes a minimal template source with the given principles content
+    fn write_source(dir: &Path, principles: Option<&str>) -> Result<()>
+    {
+        fs::create_dir_all(dir)?;
+        fs::write(
+            dir.join("templates.yml"),
+            "version: 2\nmain:\n  source: AGENTS.md\n  target: '$workspace/AGENTS.md'\nlanguages: {}\nprinciples:\n  - source: principles.md\n    target: \
+             '$instructions'\n"
+        )?;
+        fs::write(dir.join("AGENTS.md"), "# Agents")?;
+        if let Some(content) = principles
+        {
+            fs::write(dir.join("principles.md"), content)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_staged_update_and_rollback() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let manager = test_manager(&temp_dir);
+
+        write_source(&temp_dir.path().join("v1"), Some("first"))?;
+        manager.download_or_copy_templates(&temp_dir.path().join("v1").to_string_lossy(), &[], None, false)?;
+        fs::write(manager.config_dir.join("installed_files.json"), "{}")?;
+        fs::create_dir_all(manager.config_dir.join(ORIGINALS_DIR_NAME))?;
+        fs::write(manager.config_dir.join(ORIGINALS_DIR_NAME).join("abc123"), "installed")?;
+
+        // Incomplete source is rejected and the live store is untouched
+        write_source(&temp_dir.path().join("broken"), None)?;
+        assert!(manager.download_or_copy_templates(&temp_dir.path().join("broken").to_string_lossy(), &[], None, false).is_err());
+        assert_eq!(fs::read_to_string(manager.config_dir.join("principles.md"))?, "first");
+        assert!(manager.staging_dir().exists() == false);
+
+        // Complete source replaces the store and keeps local state
+        write_source(&temp_dir.path().join("v2"), Some("second"))?;
+        manager.download_or_copy_templates(&temp_dir.path().join("v2").to_string_lossy(), &[], None, false)?;
+        assert_eq!(fs::read_to_string(manager.config_dir.join("principles.md"))?, "second");
+        assert!(manager.config_dir.join("installed_files.json").exists());
+
+        // Rollback restores the previous store, and a second rollback undoes it
+        manager.rollback(false)?;
+        assert_eq!(fs::read_to_string(manager.config_dir.join("principles.md"))?, "first");
+        assert_eq!(fs::read_to_string(manager.config_dir.join(ORIGINALS_DIR_NAME).join("abc123"))?, "installed");
+        manager.rollback(false)?;
+        assert_eq!(fs::read_to_string(manager.config_dir.join("principles.md"))?, "second");
+        assert_eq!(fs::read_to_string(manager.config_dir.join(ORIGINALS_DIR_NAME).join("abc123"))?, "installed");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_repeated_update_of_hosted_source_with_parent() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let manager = test_manager(&temp_dir);
+
+        // Hosted child that only extends a local parent, served with ETags
+        let parent = temp_dir.path().join("parent");
+        write_source(&parent, Some("inherited"))?;
+        let served = temp_dir.path().join("served/team/repo/-/raw/main");
+        fs::create_dir_all(&served)?;
+        fs::write(served.join("templates.yml"), format!("version: 2\nextends: '{}'\nlanguages: {{}}\n", parent.display()))?;
+        let server = TestServer::serve_dir_with_etags(temp_dir.path().join("served"));
+        let url = format!("{}/team/repo/-/tree/main", server.url());
+
+        // The second update of the unchanged source resolves the parent again
+        for _ in 0..2
+        {
+            manager.download_or_copy_templates(&url, &[], None, false)?;
+            assert_eq!(fs::read_to_string(manager.config_dir.join("principles.md"))?, "inherited");
+            assert_eq!(TemplateLock::load(&manager.config_dir)?.unwrap().parents.len(), 1);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_locked_update_refuses_changed_templates() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let manager = test_manager(&temp_dir);
+        let source = temp_dir.path().join("source");
+        let source_str = source.to_string_lossy().to_string();
+
+        // --locked needs an existing lock
+        write_source(&source, Some("first"))?;
+        assert!(manager.download_or_copy_templates(&source_str, &[], None, true).is_err());
+
+        manager.download_or_copy_templates(&source_str, &[], None, false)?;
+        let lock = TemplateLock::load(&manager.config_dir)?.unwrap();
+        assert_eq!(lock.source, source_str);
+        assert!(lock.files.contains_key("principles.md"));
+
+        // Unchanged source reproduces the lock
+        manager.download_or_copy_templates(&source_str, &[], None, true)?;
+
+        // Changed content is refused and the live store is untouched
+        fs::write(source.join("principles.md"), "tampered")?;
+        let error = manager.download_or_copy_templates(&source_str, &[], None, true).unwrap_err();
+        assert!(error.to_string().contains("principles.md: content changed"));
+        assert_eq!(fs::read_to_string(manager.config_dir.join("principles.md"))?, "first");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_locked_update_fetches_the_locked_commit() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let manager = test_manager(&temp_dir);
+        let work = temp_dir.path().join("work");
+        let git = |args: &[&str]| {
+            let output = std::process::Command::new("git").arg("-C").arg(&work).args(args).output().expect("git available");
+            assert!(output.status.success(), "git {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        };
+
+        write_source(&work, Some("first"))?;
+        git(&["init", "--quiet", "--initial-branch=main"]);
+        git(&["config", "user.email", "test@example.com"]);
+        git(&["config", "user.name", "Test"]);
+        git(&["add", "."]);
+        git(&["commit", "--quiet", "-m", "first"]);
+        let first = git(&["rev-parse", "HEAD"]);
+
+        let source = format!("file://{}#ref=main", work.to_string_lossy().replace('\\', "/"));
+        manager.download_or_copy_templates(&source, &[], None, false)?;
+        assert_eq!(TemplateLock::load(&manager.config_dir)?.unwrap().revision.as_deref(), Some(first.as_str()));
+
+        // The branch moves on, but a locked update still installs the locked commit
+        fs::write(work.join("principles.md"), "second")?;
+        git(&["commit", "--quiet", "-am", "second"]);
+        fs::write(manager.config_dir.join("principles.md"), "modified")?;
+
+        manager.download_or_copy_templates(&source, &[], None, true)?;
+        assert_eq!(fs::read_to_string(manager.config_dir.join("principles.md"))?, "first");
+
+        Ok(())
+    }
+
+    /// Commits every file of a working tree (initializing the repository) and returns the commit
+    fn commit_all(dir: &Path, message: &str) -> String
+    {
+        let git = |args: &[&str]| {
+            let output = std::process::Command::new("git").arg("-C").arg(dir).args(args).output().expect("git available");
+            assert!(output.status.success(), "git {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        };
+
+        if dir.join(".git").exists() == false
+        {
+            git(&["init", "--quiet", "--initial-branch=main"]);
+            git(&["config", "user.email", "test@example.com"]);
+            git(&["config", "user.name", "Test"]);
+        }
+        git(&["add", "."]);
+        git(&["commit", "--quiet", "-m", message]);
+        git(&["rev-parse", "HEAD"])
+    }
+
+    #[test]
+    fn test_locked_update_fetches_locked_parents_and_overlays() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let manager = test_manager(&temp_dir);
+        let file_url = |dir: &Path| format!("file://{}#ref=main", dir.to_string_lossy().replace('\\', "/"));
+
+        // The base extends a git parent and is layered with a git overlay
+        let parent = temp_dir.path().join("parent");
+        write_source(&parent, Some("parent v1"))?;
+        let parent_commit = commit_all(&parent, "first");
+
+        let overlay = temp_dir.path().join("overlay");
+        fs::create_dir_all(&overlay)?;
+        fs::write(overlay.join("templates.yml"), "version: 2\nlanguages: {}\n")?;
+        fs::write(overlay.join("AGENTS.md"), "# Overlay v1")?;
+        let overlay_commit = commit_all(&overlay, "first");
+
+        let base = temp_dir.path().join("base");
+        fs::create_dir_all(&base)?;
+        fs::write(base.join("templates.yml"), format!("version: 2\nextends: '{}'\nlanguages: {{}}\n", file_url(&parent)))?;
+
+        let base_source = base.to_string_lossy().to_string();
+        let overlays = vec![file_url(&overlay)];
+        manager.download_or_copy_templates(&base_source, &overlays, None, false)?;
+        let lock = TemplateLock::load(&manager.config_dir)?.unwrap();
+        assert_eq!(lock.parents[0].revision.as_deref(), Some(parent_commit.as_str()));
+        assert_eq!(lock.overlays[0].revision.as_deref(), Some(overlay_commit.as_str()));
+
+        // Both branches move on, but a locked update still installs the locked commits
+        fs::write(parent.join("principles.md"), "parent v2")?;
+        commit_all(&parent, "second");
+        fs::write(overlay.join("AGENTS.md"), "# Overlay v2")?;
+        commit_all(&overlay, "second");
+
+        manager.download_or_copy_templates(&base_source, &overlays, None, true)?;
+        assert_eq!(fs::read_to_string(manager.config_dir.join("principles.md"))?, "parent v1");
+        assert_eq!(fs::read_to_string(manager.config_dir.join("AGENTS.md"))?, "# Overlay v1");
+        assert_eq!(TemplateLock::load(&manager.config_dir)?.unwrap().differences(&lock), Vec::<String>::new());
+
+        // An unlocked update follows the branches
+        manager.download_or_copy_templates(&base_source, &overlays, None, false)?;
+        assert_eq!(fs::read_to_string(manager.config_dir.join("principles.md"))?, "parent v2");
+        assert_eq!(fs::read_to_string(manager.config_dir.join("AGENTS.md"))?, "# Overlay v2");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pin_version_uses_the_manager_config() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let mut manager = test_manager(&temp_dir);
+        let source = "https://git.example.com/owner/repo/src/branch/main";
+        let lock = TemplateLock {
+            source:    source.to_string(),
+            revision:  None,
+            version:   Some("v7.1.0".to_string()),
+            range:     Some("^7".to_string()),
+            parents:   Vec::new(),
+            overlays:  Vec::new(),
+            generated: String::new(),
+            files:     BTreeMap::new(),
+            origins:   BTreeMap::new()
+        };
+
+        // Without a release request nothing is pinned
+        assert!(manager.pin_version(source, None, None)?.is_none());
+
+        // The provider is detected from the URL by default...
+        let (pinned, range) = manager.pin_version(source, None, Some(&lock))?.unwrap();
+        assert_eq!(pinned.source, "https://git.example.com/owner/repo/src/tag/v7.1.0");
+        assert_eq!(range, "^7");
+
+        // ...and the injected configuration can select a different one
+        let mut settings = Config::default();
+        settings.set("source.provider", "bitbucket")?;
+        manager.set_config(settings);
+        let (pinned, _) = manager.pin_version(source, None, Some(&lock))?.unwrap();
+        assert_eq!(pinned.source, "https://git.example.com/owner/repo/src/v7.1.0/main");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_overlays_are_merged_in_order() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let manager = test_manager(&temp_dir);
+        let base = temp_dir.path().join("base");
+        write_source(&base, Some("upstream"))?;
+
+        // Team overlay adds a language and replaces the principles fragment
+        let team = temp_dir.path().join("team");
+        fs::create_dir_all(team.join("rust"))?;
+        fs::write(team.join("templates.yml"), "version: 2\nlanguages:\n  rust:\n    files:\n      - source: rust/rust.md\n        target: '$instructions'\n")?;
+        fs::write(team.join("rust/rust.md"), "team rust")?;
+        fs::write(team.join("principles.md"), "team")?;
+
+        // User overlay replaces the team's file again
+        let user = temp_dir.path().join("user");
+        fs::create_dir_all(user.join("rust"))?;
+        fs::write(user.join("templates.yml"), "version: 2\nlanguages: {}\n")?;
+        fs::write(user.join("rust/rust.md"), "my rust")?;
+
+        let overlays = vec![team.to_string_lossy().to_string(), user.to_string_lossy().to_string()];
+        manager.download_or_copy_templates(&base.to_string_lossy(), &overlays, None, false)?;
+
+        let config = template_engine::load_template_config(&manager.config_dir)?;
+        assert!(config.languages.contains_key("rust"));
+        assert_eq!(fs::read_to_string(manager.config_dir.join("principles.md"))?, "team");
+        assert_eq!(fs::read_to_string(manager.config_dir.join("rust/rust.md"))?, "my rust");
+        assert!(manager.overlay_dir().exists() == false);
+
+        // The lock records the layers and the origin of every file
+        let lock = TemplateLock::load(&manager.config_dir)?.unwrap();
+        assert_eq!(lock.overlays.len(), 2);
+        assert_eq!(lock.origin("AGENTS.md"), base.to_string_lossy());
+        assert_eq!(lock.origin("principles.md"), overlays[0]);
+        assert_eq!(lock.origin("rust/rust.md"), overlays[1]);
+
+        // Same layers reproduce the lock, dropping an overlay does not
+        manager.download_or_copy_templates(&base.to_string_lossy(), &overlays, None, true)?;
+        let error = manager.download_or_copy_templates(&base.to_string_lossy(), &overlays[..1], None, true).unwrap_err();
+        assert!(error.to_string().contains("overlays changed"));
+
+        // An overlay with a different templates.yml version is refused
+        fs::write(user.join("templates.yml"), "version: 1\nlanguages: {}\n")?;
+        assert!(manager.download_or_copy_templates(&base.to_string_lossy(), &overlays, None, false).is_err());
+        assert_eq!(fs::read_to_string(manager.config_dir.join("rust/rust.md"))?, "my rust");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_extends_resolves_parents_recursively() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let manager = test_manager(&temp_dir);
+        write_source(&temp_dir.path().join("upstream"), Some("upstream"))?;
+
+        // Team templates add a language and replace the principles of the upstream templates
+        let team = temp_dir.path().join("team");
+        fs::create_dir_all(&team)?;
+        fs::write(
+            team.join("templates.yml"),
+            "version: 2\nextends: ../upstream\nmerge:\n  principles: replace\nlanguages:\n  zig:\n    files:\n      - source: zig.md\n        target: \
+             '$instructions'\nprinciples:\n  - source: team-principles.md\n    target: '$instructions'\n"
+        )?;
+        fs::write(team.join("zig.md"), "## Zig")?;
+        fs::write(team.join("team-principles.md"), "team")?;
+
+        // Project templates extend the team templates and only override AGENTS.md
+        let project = temp_dir.path().join("project");
+        fs::create_dir_all(&project)?;
+        fs::write(project.join("templates.yml"), "version: 2\nextends: ../team\nlanguages: {}\n")?;
+        fs::write(project.join("AGENTS.md"), "# Project agents")?;
+
+        manager.download_or_copy_templates(&project.to_string_lossy(), &[], None, false)?;
+
+        let config = template_engine::load_template_config(&manager.config_dir)?;
+        assert!(config.extends.is_none());
+        assert!(config.languages.contains_key("zig"));
+        let principles: Vec<&str> = config.principles.as_ref().unwrap().iter().map(|m| m.source.as_str()).collect();
+        assert_eq!(principles, vec!["team-principles.md"]);
+        assert_eq!(fs::read_to_string(manager.config_dir.join("AGENTS.md"))?, "# Project agents");
+
+        let lock = TemplateLock::load(&manager.config_dir)?.unwrap();
+        assert_eq!(lock.parents.len(), 2);
+        assert!(lock.origin("zig.md").ends_with("team"));
+        assert!(lock.origin("AGENTS.md").ends_with("project"));
+        assert!(manager.parents_dir().read_dir().map(|mut d| d.next().is_none()).unwrap_or(true));
+
+        // A cycle is refused and the live store is untouched
+        fs::write(
+            temp_dir.path().join("upstream/templates.yml"),
+            "version: 2\nextends: ../project\nmain:\n  source: AGENTS.md\n  target: '$workspace/AGENTS.md'\nlanguages: {}\n"
+        )?;
+        let error = manager.download_or_copy_templates(&project.to_string_lossy(), &[], None, false).unwrap_err();
+        assert!(error.to_string().contains("cycle"), "{}", error);
+        assert_eq!(fs::read_to_string(manager.config_dir.join("AGENTS.md"))?, "# Project agents");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_offline_mode_uses_local_sources_only() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let mut manager = test_manager(&temp_dir);
+        manager.set_offline(true);
+
+        // Nothing installed yet: the error names the missing store
+        let error = manager.check_offline_store().unwrap_err().to_string();
+        assert!(error.contains("templates.yml"));
+
+        // Remote sources are refused without touching the network
+        for source in ["https://github.com/o/r/tree/main/templates", "git+https://example.com/t.git", "https://example.com/bundle.tar.gz"]
+        {
+            let error = manager.download_or_copy_templates(source, &[], None, false).unwrap_err().to_string();
+            assert!(error.starts_with("Offline mode"), "{}", error);
+        }
+
+        // Local sources still work
+        write_source(&temp_dir.path().join("local"), Some("offline"))?;
+        manager.download_or_copy_templates(&temp_dir.path().join("local").to_string_lossy(), &[], None, false)?;
+        manager.check_offline_store()?;
+
+        // Files missing from the store are listed
+        fs::remove_file(manager.config_dir.join("principles.md"))?;
+        let error = manager.check_offline_store().unwrap_err().to_string();
+        assert!(error.contains("principles.md"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_broken_pack_registry_falls_back_to_default_store() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let data_dir = temp_dir.path().join("data");
+        fs::create_dir_all(&data_dir)?;
+
+        // A corrupt registry does not prevent creating the manager
+        fs::write(data_dir.join("packs.yml"), "active: [unclosed")?;
+        let manager = TemplateManager::with_dirs(data_dir.clone(), temp_dir.path().join("cache"));
+        assert_eq!(manager.active_pack(), None);
+        assert_eq!(manager.config_dir, data_dir.join("templates"));
+
+        // An active pack whose store was deleted falls back too, and can be switched back
+        let mut registry = PackRegistry::default();
+        registry.add("ios", "/srv/templates/ios")?;
+        registry.set_active("ios")?;
+        registry.save(&data_dir)?;
+        let manager = TemplateManager::with_dirs(data_dir.clone(), temp_dir.path().join("cache"));
+        assert_eq!(manager.active_pack(), None);
+        manager.use_pack("default")?;
+        assert_eq!(PackRegistry::load(&data_dir)?.active, None);
+
+        // An installed active pack is selected
+        fs::create_dir_all(data_dir.join("packs/ios"))?;
+        manager.use_pack("ios")?;
+        let manager = TemplateManager::with_dirs(data_dir.clone(), temp_dir.path().join("cache"));
+        assert_eq!(manager.active_pack(), Some("ios"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_packs_use_separate_stores() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let mut manager = test_manager(&temp_dir);
+
+        write_source(&temp_dir.path().join("default"), Some("default"))?;
+        write_source(&temp_dir.path().join("embedded"), Some("embedded"))?;
+        manager.download_or_copy_templates(&temp_dir.path().join("default").to_string_lossy(), &[], None, false)?;
+        manager.add_pack("embedded-c", &temp_dir.path().join("embedded").to_string_lossy(), false)?;
+
+        // A failed install does not register the pack
+        assert!(manager.add_pack("broken", &temp_dir.path().join("missing").to_string_lossy(), false).is_err());
+        assert!(manager.select_pack("broken").is_err());
+
+        // Selecting a pack for one run leaves the default store and the registry alone
+        manager.select_pack("embedded-c")?;
+        assert_eq!(manager.pack_source(), Some(fs::canonicalize(temp_dir.path().join("embedded"))?.display().to_string()));
+        assert_eq!(fs::read_to_string(manager.config_dir.join("principles.md"))?, "embedded");
+        assert_eq!(PackRegistry::load(&manager.data_dir)?.active, None);
+
+        manager.select_pack("default")?;
+        assert_eq!(fs::read_to_string(manager.config_dir.join("principles.md"))?, "default");
+
+        // Removing the active pack deletes its store and reactivates the default store
+        manager.use_pack("embedded-c")?;
+        assert_eq!(PackRegistry::load(&manager.data_dir)?.active.as_deref(), Some("embedded-c"));
+        manager.remove_pack("embedded-c", false)?;
+        assert!(manager.pack_dir("embedded-c").exists() == false);
+        assert_eq!(PackRegistry::load(&manager.data_dir)?, PackRegistry::default());
+        Ok(())
+    }
+
+    #[test]
+    fn test_embedded_templates_fallback() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let mut manager = test_manager(&temp_dir);
+
+        // An incomplete snapshot is never installed
+        let incomplete: Vec<_> = embedded_templates::EMBEDDED_FILES.iter().filter(|(file, _)| *file != "AGENTS.md").copied().collect();
+        assert!(manager.install_snapshot(&incomplete).is_err());
+        assert!(manager.has_global_templates() == false);
+
+        manager.install_embedded_templates()?;
+        assert!(manager.has_global_templates() == true);
+        assert!(manager.uses_embedded_templates() == true);
+        assert_eq!(TemplateLock::load(&manager.config_dir)?.unwrap().revision, Some(embedded_templates::revision()));
+
+        // A regular update replaces the snapshot
+        write_source(&temp_dir.path().join("source"), Some("downloaded"))?;
+        manager.download_or_copy_templates(&temp_dir.path().join("source").to_string_lossy(), &[], None, false)?;
+        assert!(manager.uses_embedded_templates() == false);
+
+        // Packs never receive the default snapshot
+        manager.pack = Some("ios".to_string());
+        assert!(manager.install_embedded_templates().is_err());
         Ok(())
     }
 }
@@ -0,0 +1,262 @@
+//! Template pack commands
+
+use std::{
+    fs,
+    path::{Path, PathBuf}
+};
+
+use owo_colors::OwoColorize;
+
+use super::TemplateManager;
+use crate::{
+    Result,
+    pack_registry::{DEFAULT_PACK, PackRegistry},
+    template_lock::TemplateLock
+};
+
+impl TemplateManager
+{
+    /// Selects the template store of a pack for this run
+    ///
+    /// The active pack in the registry is not changed. `default` selects the
+    /// default template store.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of a registered pack, or `default`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pack is not registered
+    pub fn select_pack(&mut self, name: &str) -> Result<()>
+    {
+        if name == DEFAULT_PACK
+        {
+            self.config_dir = self.data_dir.join("templates");
+            self.pack = None;
+            return Ok(());
+        }
+
+        PackRegistry::load(&self.data_dir)?.require(name)?;
+
+        self.config_dir = self.pack_dir(name);
+        self.pack = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Returns the name of the selected pack (None for the default store)
+    pub fn active_pack(&self) -> Option<&str>
+    {
+        self.pack.as_deref()
+    }
+
+    /// Returns the source the selected pack was added from (None for the default store)
+    pub fn pack_source(&self) -> Option<String>
+    {
+        let name = self.pack.as_deref()?;
+        PackRegistry::load(&self.data_dir).ok()?.get(name).map(|pack| pack.source.clone())
+    }
+
+    /// Returns the template store of a pack
+    pub(crate) fn pack_dir(&self, name: &str) -> PathBuf
+    {
+        self.data_dir.join("packs").join(name)
+    }
+
+    /// Registers a template pack and installs its templates
+    ///
+    /// The templates are installed into the pack's own store with the same staged
+    /// update used by `update`; the pack is only registered if that succeeds.
+    /// Local paths are stored as absolute paths so later updates work from any directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the new pack
+    /// * `source` - Path, URL, archive, or git repository to install the pack from
+    /// * `dry_run` - If true, only show what would happen without making changes
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is invalid or taken, or the templates cannot be installed
+    pub fn add_pack(&self, name: &str, source: &str, dry_run: bool) -> Result<()>
+    {
+        let mut registry = PackRegistry::load(&self.data_dir)?;
+        registry.check_new(name)?;
+
+        let source = match fs::canonicalize(source)
+        {
+            | Ok(path) if Path::new(source).exists() == true => path.display().to_string(),
+            | _ => source.to_string()
+        };
+
+        let pack_manager = TemplateManager {
+            config_dir: self.pack_dir(name),
+            cache_dir:  self.cache_dir.clone(),
+            data_dir:   self.data_dir.clone(),
+            pack:       Some(name.to_string()),
+            offline:    self.offline,
+            settings:   self.settings.clone()
+        };
+
+        if dry_run == true
+        {
+            println!("{} Dry run: would add template pack {} from {}", "→".blue(), name.green(), source.yellow());
+            println!("{} Templates would be installed to: {}", "→".blue(), pack_manager.config_dir.display().to_string().yellow());
+            println!("\n{} Dry run complete. No files were modified.", "✓".green());
+            return Ok(());
+        }
+
+        println!("{} Installing template pack {} from {}", "→".blue(), name.green(), source.yellow());
+        pack_manager.download_or_copy_templates(&source, &[], None, false)?;
+
+        registry.add(name, &source)?;
+        registry.save(&self.data_dir)?;
+
+        println!("{} Added template pack {}", "✓".green(), name.green());
+        println!("{} Use it with 'vibe-check init --pack {}' or make it the default with 'vibe-check pack use {}'", "→".blue(), name, name);
+        Ok(())
+    }
+
+    /// Unregisters a template pack and deletes its template store
+    ///
+    /// Files already installed into projects are not touched. If the pack was
+    /// active, the default store becomes active again.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the pack to remove
+    /// * `dry_run` - If true, only show what would happen without making changes
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pack is not registered or its store cannot be deleted
+    pub fn remove_pack(&self, name: &str, dry_run: bool) -> Result<()>
+    {
+        if name == DEFAULT_PACK
+        {
+            return Err("The default template store cannot be removed".into());
+        }
+
+        let mut registry = PackRegistry::load(&self.data_dir)?;
+        let was_active = registry.active.as_deref() == Some(name);
+        let pack = registry.remove(name)?;
+        let pack_dir = self.pack_dir(name);
+
+        if dry_run == true
+        {
+            println!("{} Dry run: would remove template pack {} ({})", "→".blue(), name.green(), pack.source.yellow());
+            println!("{} Would delete: {}", "→".blue(), pack_dir.display().to_string().yellow());
+            println!("\n{} Dry run complete. No files were modified.", "✓".green());
+            return Ok(());
+        }
+
+        for dir in [pack_dir.clone(), pack_dir.with_extension("previous")]
+        {
+            if dir.exists() == true
+            {
+                fs::remove_dir_all(&dir)?;
+            }
+        }
+
+        registry.save(&self.data_dir)?;
+
+        println!("{} Removed template pack {}", "✓".green(), name.green());
+        if was_active == true
+        {
+            println!("{} The default template store is active again", "→".blue());
+        }
+        Ok(())
+    }
+
+    /// Makes a template pack the global default
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of a registered pack, or `default`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pack is not registered or the registry cannot be written
+    pub fn use_pack(&self, name: &str) -> Result<()>
+    {
+        let mut registry = PackRegistry::load(&self.data_dir)?;
+        registry.set_active(name)?;
+        registry.save(&self.data_dir)?;
+
+        println!("{} Active template pack: {}", "✓".green(), name.green());
+
+        let store = if name == DEFAULT_PACK
+        {
+            self.data_dir.join("templates")
+        }
+        else
+        {
+            self.pack_dir(name)
+        };
+        if store.join("templates.yml").exists() == false
+        {
+            println!("{} Templates of this pack are not installed; run 'vibe-check update' to download them", "!".yellow());
+        }
+        Ok(())
+    }
+
+    /// Lists the default store and all registered template packs
+    ///
+    /// A registry that cannot be read is reported as a warning, and only the
+    /// default store is listed.
+    pub fn list_packs(&self) -> Result<()>
+    {
+        // The default store is listed even if the registry is broken
+        let registry = PackRegistry::load(&self.data_dir).unwrap_or_else(|e| {
+            println!("{} Warning: {}", "!".yellow(), e);
+            PackRegistry::default()
+        });
+
+        println!("{}", "Template Packs:".bold());
+
+        let default_source = TemplateLock::load(&self.data_dir.join("templates")).ok().flatten().map(|lock| lock.source);
+        print_pack(DEFAULT_PACK, default_source.as_deref(), &self.data_dir.join("templates"), registry.active.is_none());
+
+        for (name, pack) in &registry.packs
+        {
+            print_pack(name, Some(&pack.source), &self.pack_dir(name), registry.active.as_deref() == Some(name.as_str()));
+        }
+
+        if registry.packs.is_empty() == true
+        {
+            println!();
+            println!("{} Add a pack with 'vibe-check pack add <name> --from <source>'", "→".blue());
+        }
+        Ok(())
+    }
+}
+
+/// Prints one entry of the pack list
+fn print_pack(name: &str, source: Option<&str>, store: &Path, active: bool)
+{
+    let marker = if active == true
+    {
+        "●".green().to_string()
+    }
+    else
+    {
+        "○".to_string()
+    };
+    let state = if active == true
+    {
+        " (active)".green().to_string()
+    }
+    else
+    {
+        String::new()
+    };
+
+    println!("  {} {}{}", marker, name.bold(), state);
+    match (source, store.join("templates.yml").exists())
+    {
+        | (Some(source), true) => println!("      {}", source.yellow()),
+        | (Some(source), false) => println!("      {} {}", source.yellow(), "(not installed)".red()),
+        | (None, true) => println!("      {}", store.display()),
+        | (None, false) => println!("      {}", "(not installed)".red())
+    }
+}
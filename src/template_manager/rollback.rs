@@ -0,0 +1,77 @@
+//! Template rollback command
+
+use std::fs;
+
+use owo_colors::OwoColorize;
+
+use super::{STORE_STATE_FILES, TemplateManager};
+use crate::{Result, utils::copy_dir_all};
+
+impl TemplateManager
+{
+    /// Restores the global template store that was replaced by the last update
+    ///
+    /// Swaps the live store with the previous store, so running rollback twice
+    /// returns to the newer templates. The installed file tracker always stays
+    /// with the live store.
+    ///
+    /// # Arguments
+    ///
+    /// * `dry_run` - If true, only show what would happen without making changes
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no previous store exists or the swap fails
+    pub fn rollback(&self, dry_run: bool) -> Result<()>
+    {
+        let previous_dir = self.previous_dir();
+        if previous_dir.join("templates.yml").exists() == false
+        {
+            return Err("No previous global templates to roll back to".into());
+        }
+
+        if dry_run == true
+        {
+            println!("{} Dry run: would restore global templates from {}", "→".blue(), previous_dir.display().to_string().yellow());
+            println!("\n{} Dry run complete. No files were modified.", "✓".green());
+            return Ok(());
+        }
+
+        // Carry over local state, then swap live and previous stores via the staging name
+        for state_file in STORE_STATE_FILES
+        {
+            let current = self.config_dir.join(state_file);
+            if current.is_dir() == true
+            {
+                let target = previous_dir.join(state_file);
+                if target.exists() == true
+                {
+                    fs::remove_dir_all(&target)?;
+                }
+                copy_dir_all(&current, &target)?;
+            }
+            else if current.exists() == true
+            {
+                fs::copy(&current, previous_dir.join(state_file))?;
+            }
+        }
+
+        let swap_dir = self.staging_dir();
+        if swap_dir.exists() == true
+        {
+            fs::remove_dir_all(&swap_dir)?;
+        }
+
+        fs::rename(&previous_dir, &swap_dir)?;
+        if self.config_dir.exists() == true
+        {
+            fs::rename(&self.config_dir, &previous_dir)?;
+        }
+        fs::rename(&swap_dir, &self.config_dir)?;
+
+        println!("{} Restored previous global templates", "✓".green());
+        println!("{} Run 'vibe-check update --rollback' again to undo", "→".blue());
+
+        Ok(())
+    }
+}
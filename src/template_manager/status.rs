@@ -4,15 +4,15 @@
 
 use owo_colors::OwoColorize;
 
-use super::TemplateManager;
-use crate::{Result, bom::BillOfMaterials, template_engine};
+use super::{TemplateManager, print_layers};
+use crate::{Result, bom::BillOfMaterials, template_engine, template_lock::TemplateLock};
 
 impl TemplateManager
 {
     /// Show current project status
     ///
     /// Displays information about:
-    /// - Global template status (downloaded, location)
+    /// - Global template status (downloaded, location, layers)
     /// - AGENTS.md status (exists, customized)
     /// - Installed agents (detected by checking for their files)
     /// - All vibe-check managed files in current directory
@@ -29,14 +29,39 @@ pub fn status(&self) -> Result<()>
 
         // Global templates status
         println!("{}", "Global Templates:".bold());
+        if let Some(pack) = self.active_pack()
+        {
+            println!("  {} Template pack: {}", "→".blue(), pack.green());
+        }
         if self.has_global_templates() == true
         {
             println!("  {} Installed at: {}", "✓".green(), self.config_dir.display().to_string().yellow());
+            if self.uses_embedded_templates() == true
+            {
+                println!("  {} Using the embedded template snapshot built into vibe-check (run 'vibe-check update' to download the latest templates)", "!".yellow());
+            }
+
+            let lock = TemplateLock::load(&self.config_dir).ok().flatten();
+            if let Some(lock) = &lock
+            {
+                match &lock.revision
+                {
+                    | Some(revision) => println!("  {} Locked source: {} ({})", "→".blue(), lock.source.green(), revision.yellow()),
+                    | None => println!("  {} Locked source: {}", "→".blue(), lock.source.green())
+                }
+            }
 
             // Show template version, available agents and languages from templates.yml
             if let Ok(config) = template_engine::load_template_config(&self.config_dir)
             {
-                println!("  {} Template version: {}", "→".blue(), config.version.to_string().green());
+                match lock.as_ref().and_then(|l| l.version.as_ref().map(|v| (v, l.range.as_deref().unwrap_or(""))))
+                {
+                    | Some((release, range)) =>
+                    {
+                        println!("  {} Template version: {} (release {}, range {})", "→".blue(), config.version.to_string().green(), release.green(), range.yellow())
+                    }
+                    | None => println!("  {} Template version: {}", "→".blue(), config.version.to_string().green())
+                }
 
                 // List agent-specific files (if agents section exists)
                 if let Some(agents_map) = &config.agents
@@ -63,6 +88,13 @@ pub fn status(&self) -> Result<()>
 
         println!();
 
+        // Layered templates: show which layer every template file came from
+        if let Ok(Some(lock)) = TemplateLock::load(&self.config_dir) &&
+            lock.is_layered() == true
+        {
+            print_layers(&lock);
+        }
+
         // AGENTS.md status
         println!("{}", "Project Status:".bold());
         let agents_md_path = current_dir.join("AGENTS.md");
@@ -1,9 +1,40 @@
 //! Template update command
 
+use std::collections::BTreeMap;
+
 use owo_colors::OwoColorize;
 
 use super::TemplateManager;
-use crate::{Result, file_tracker::FileTracker, template_engine};
+use crate::{
+    Result,
+    file_tracker::FileTracker,
+    template_engine::{self, UpdateOptions},
+    template_variables::TemplateVariables
+};
+
+/// Options for `TemplateManager::update` (the `init` command)
+///
+/// Unlike `UpdateOptions`, the language is not resolved yet and the variables are
+/// only the overrides of the detected ones.
+pub struct InitOptions<'a>
+{
+    /// Programming language or framework identifier. If None (and no_lang false), uses existing installation or first available.
+    pub lang:    Option<&'a str>,
+    /// AI coding agent identifier. Required for v1 templates, optional for v2.
+    pub agent:   Option<&'a str>,
+    /// Skip language-specific setup (AGENTS.md + agent prompts only)
+    pub no_lang: bool,
+    /// Custom mission statement to override template default
+    pub mission: Option<&'a str>,
+    /// Overwrite local modifications without warning
+    pub force:   bool,
+    /// Only show what would happen without making changes
+    pub dry_run: bool,
+    /// Merge template changes into locally modified files instead of prompting
+    pub merge:   bool,
+    /// Template variables that override the detected ones (config and `--var`)
+    pub vars:    &'a BTreeMap<String, String>
+}
 
 impl TemplateManager
 {
@@ -14,12 +45,7 @@ impl TemplateManager
     ///
     /// # Arguments
     ///
-    /// * `lang` - Programming language or framework identifier. If None (and no_lang false), uses existing installation or first available.
-    /// * `agent` - AI coding agent identifier. Required for v1 templates, optional for v2.
-    /// * `no_lang` - If true, skip language-specific setup (AGENTS.md + agent prompts only)
-    /// * `mission` - Optional custom mission statement to override template default
-    /// * `force` - If true, overwrite local modifications without warning
-    /// * `dry_run` - If true, only show what would happen without making changes
+    /// * `options` - Language, agent, mission, and flags of the `init` command
     ///
     /// # Errors
     ///
@@ -28,8 +54,10 @@ impl TemplateManager
     /// - Template version is unsupported
     /// - Lang is None, no_lang is false, and no languages are defined in templates
     /// - Template generation fails
-    pub fn update(&self, lang: Option<&str>, agent: Option<&str>, no_lang: bool, mission: Option<&str>, force: bool, dry_run: bool) -> Result<()>
+    pub fn update(&self, options: &InitOptions) -> Result<()>
     {
+        let InitOptions { lang, agent, no_lang, mission, force, dry_run, merge, vars } = *options;
+
         // Check if global templates exist
         if self.has_global_templates() == false
         {
@@ -82,6 +110,10 @@ pub fn update(&self, lang: Option<&str>, agent: Option<&str>, no_lang: bool, mis
             }
         };
 
+        // Detect template variables for the workspace, then apply the overrides
+        let mut variables = TemplateVariables::detect(&std::env::current_dir()?, lang_resolved.as_deref(), agent);
+        variables.extend(vars);
+
         match version
         {
             | 1 =>
@@ -96,7 +128,7 @@ pub fn update(&self, lang: Option<&str>, agent: Option<&str>, no_lang: bool, mis
                 let agent_str = agent.ok_or("--agent is required for v1 templates. Specify: vibe-check init --lang <lang> --agent <agent>")?;
                 let engine = crate::template_engine_v1::TemplateEngineV1::new(&self.config_dir);
                 let lang_for_engine = lang_resolved.as_deref().unwrap_or("");
-                engine.update(lang_for_engine, agent_str, no_lang, mission, force, dry_run)
+                engine.update(&UpdateOptions { lang: lang_for_engine, agent: Some(agent_str), no_lang, mission, force, dry_run, merge, variables: &variables })
             }
             | 2 =>
             {
@@ -115,7 +147,7 @@ pub fn update(&self, lang: Option<&str>, agent: Option<&str>, no_lang: bool, mis
                 }
                 let engine = crate::template_engine_v2::TemplateEngineV2::new(&self.config_dir);
                 let lang_for_engine = lang_resolved.as_deref().unwrap_or("");
-                engine.update(lang_for_engine, agent, no_lang, mission, force, dry_run)
+                engine.update(&UpdateOptions { lang: lang_for_engine, agent, no_lang, mission, force, dry_run, merge, variables: &variables })
             }
             | _ => Err(format!("Unsupported template version: {}. Please update vibe-check to the latest version.", version).into())
         }
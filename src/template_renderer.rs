@@ -0,0 +1,566 @@
+//! Template language for vibe-check
+//!
+//! Fragments and template files are rendered before they are merged into
+//! AGENTS.md or copied into the project:
+//!
+//! - `{{name}}` inserts a template variable (see `TemplateVariables`)
+//! - `{{#if condition}} ... {{else}} ... {{/if}}` keeps one of two branches
+//! - `{{> include path}}` (or `{{> path}}`) inserts another template file, given relative to the template directory and rendered with the same variables
+//!
+//! Conditions are `name` (defined, not empty, and not `false`), `!name`,
+//! `name == "value"`, and `name != "value"`; `agent` and `lang` hold the values
+//! selected for `init`. A line that only contains a block tag or an include is
+//! removed entirely, so blocks do not leave blank lines behind. Text in double
+//! braces that is neither a tag nor a variable name is kept as it is.
+
+use std::{fs, path::Path};
+
+use crate::{
+    Result,
+    bom::check_relative_path,
+    template_variables::{TemplateVariables, is_variable_name}
+};
+
+/// Maximum nesting depth of includes
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Renders templates with variables, conditional blocks, and includes
+pub struct TemplateRenderer<'a>
+{
+    variables:   &'a TemplateVariables,
+    include_dir: &'a Path
+}
+
+/// Condition of an `{{#if}}` block
+#[derive(Debug, Clone, PartialEq)]
+enum Condition
+{
+    /// `name` or `!name`
+    Defined
+    {
+        name: String, negated: bool
+    },
+    /// `name == "value"` or `name != "value"`
+    Equals
+    {
+        name: String, value: String, negated: bool
+    }
+}
+
+/// Parsed template element
+#[derive(Debug, Clone, PartialEq)]
+enum Node<'t>
+{
+    /// Literal text (including double braces that are not tags)
+    Text(&'t str),
+    /// `{{name}}` with the original tag text, kept if the variable is undefined
+    Variable
+    {
+        name: &'t str, raw: &'t str
+    },
+    /// `{{#if}}` block with its branches
+    If
+    {
+        condition: Condition, then_branch: Vec<Node<'t>>, else_branch: Vec<Node<'t>>
+    },
+    /// `{{> path}}`; a standalone include always ends with a line break
+    Include
+    {
+        path: &'t str, standalone: bool
+    }
+}
+
+/// Kind of a `{{...}}` tag
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TagKind
+{
+    If,
+    Else,
+    EndIf,
+    Include,
+    Variable,
+    Literal
+}
+
+/// Block of an `{{#if}}` that is still open while parsing
+struct OpenBlock<'t>
+{
+    condition:   Condition,
+    then_branch: Vec<Node<'t>>,
+    else_branch: Vec<Node<'t>>,
+    in_else:     bool
+}
+
+impl<'a> TemplateRenderer<'a>
+{
+    /// Creates a renderer
+    ///
+    /// # Arguments
+    ///
+    /// * `variables` - Values for variables and conditions
+    /// * `include_dir` - Template directory that include paths are relative to
+    pub fn new(variables: &'a TemplateVariables, include_dir: &'a Path) -> Self
+    {
+        Self { variables, include_dir }
+    }
+
+    /// Renders a template
+    ///
+    /// # Returns
+    ///
+    /// The rendered text and the names of all undefined variables that were
+    /// referenced outside of conditions (sorted, without duplicates)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for malformed tags, unbalanced blocks, and includes that
+    /// are unsafe, missing, circular, or nested too deeply
+    pub fn render(&self, content: &str) -> Result<(String, Vec<String>)>
+    {
+        let mut unresolved = Vec::new();
+        let output = self.render_nested(content, &mut Vec::new(), &mut unresolved)?;
+
+        unresolved.sort();
+        unresolved.dedup();
+        Ok((output, unresolved))
+    }
+
+    /// Renders a template or an included file
+    ///
+    /// `includes` holds the chain of files currently being included, used to detect cycles.
+    fn render_nested(&self, content: &str, includes: &mut Vec<String>, unresolved: &mut Vec<String>) -> Result<String>
+    {
+        let nodes = parse(content)?;
+        let mut output = String::with_capacity(content.len());
+        self.render_nodes(&nodes, includes, unresolved, &mut output)?;
+        Ok(output)
+    }
+
+    /// Appends the rendered nodes to `output`
+    fn render_nodes(&self, nodes: &[Node], includes: &mut Vec<String>, unresolved: &mut Vec<String>, output: &mut String) -> Result<()>
+    {
+        for node in nodes
+        {
+            match node
+            {
+                | Node::Text(text) => output.push_str(text),
+                | Node::Variable { name, raw } => match self.variables.get(name)
+                {
+                    | Some(value) => output.push_str(value),
+                    | None =>
+                    {
+                        unresolved.push(name.to_string());
+                        output.push_str(raw);
+                    }
+                },
+                | Node::If { condition, then_branch, else_branch } =>
+                {
+                    let branch = if self.evaluate(condition) == true
+                    {
+                        then_branch
+                    }
+                    else
+                    {
+                        else_branch
+                    };
+                    self.render_nodes(branch, includes, unresolved, output)?;
+                }
+                | Node::Include { path, standalone } =>
+                {
+                    let included = self.render_include(path, includes, unresolved)?;
+                    output.push_str(&included);
+                    if *standalone == true && included.is_empty() == false && included.ends_with('\n') == false
+                    {
+                        output.push('\n');
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads and renders an included file
+    fn render_include(&self, path: &str, includes: &mut Vec<String>, unresolved: &mut Vec<String>) -> Result<String>
+    {
+        if let Some(reason) = check_relative_path(path)
+        {
+            return Err(format!("Invalid include '{}': {}", path, reason).into());
+        }
+
+        if includes.iter().any(|p| p == path) == true
+        {
+            return Err(format!("Circular include: {} -> {}", includes.join(" -> "), path).into());
+        }
+
+        if includes.len() >= MAX_INCLUDE_DEPTH
+        {
+            return Err(format!("Includes are nested more than {} levels deep: {}", MAX_INCLUDE_DEPTH, includes.join(" -> ")).into());
+        }
+
+        let content = fs::read_to_string(self.include_dir.join(path)).map_err(|e| format!("Cannot read included file '{}': {}", path, e))?;
+
+        includes.push(path.to_string());
+        let rendered = self.render_nested(&content, includes, unresolved).map_err(|e| format!("In included file '{}': {}", path, e))?;
+        includes.pop();
+
+        Ok(rendered)
+    }
+
+    /// Evaluates an `{{#if}}` condition
+    fn evaluate(&self, condition: &Condition) -> bool
+    {
+        match condition
+        {
+            | Condition::Defined { name, negated } =>
+            {
+                let defined = self.variables.get(name).is_some_and(|value| value.is_empty() == false && value != "false");
+                defined != *negated
+            }
+            | Condition::Equals { name, value, negated } => (self.variables.get(name) == Some(value.as_str())) != *negated
+        }
+    }
+}
+
+/// Checks the syntax of a template without rendering it
+///
+/// # Returns
+///
+/// The include paths referenced by the template, in order of appearance
+///
+/// # Errors
+///
+/// Returns an error for malformed tags and unbalanced blocks
+pub(crate) fn check_syntax(content: &str) -> Result<Vec<String>>
+{
+    fn collect<'t>(nodes: &[Node<'t>], paths: &mut Vec<String>)
+    {
+        for node in nodes
+        {
+            match node
+            {
+                | Node::Include { path, .. } => paths.push(path.to_string()),
+                | Node::If { then_branch, else_branch, .. } =>
+                {
+                    collect(then_branch, paths);
+                    collect(else_branch, paths);
+                }
+                | _ =>
+                {}
+            }
+        }
+    }
+
+    let mut paths = Vec::new();
+    collect(&parse(content)?, &mut paths);
+    Ok(paths)
+}
+
+/// Parses a template into nodes
+fn parse(content: &str) -> Result<Vec<Node<'_>>>
+{
+    let (mut texts, tags) = tokenize(content);
+    let kinds: Vec<TagKind> = tags.iter().map(|(inner, _)| tag_kind(inner)).collect();
+
+    // Remove the indentation and line break around tags that stand alone on a line
+    let standalone: Vec<bool> = (0..tags.len())
+        .map(|i| {
+            matches!(kinds[i], TagKind::If | TagKind::Else | TagKind::EndIf | TagKind::Include) &&
+                starts_line(texts[i], i == 0) &&
+                ends_line(texts[i + 1], i + 1 == texts.len() - 1)
+        })
+        .collect();
+    for (i, is_standalone) in standalone.iter().enumerate()
+    {
+        if *is_standalone == true
+        {
+            texts[i] = strip_indentation(texts[i]);
+            texts[i + 1] = strip_line_end(texts[i + 1]);
+        }
+    }
+
+    let mut root: Vec<Node> = Vec::new();
+    let mut blocks: Vec<OpenBlock> = Vec::new();
+
+    // Nodes are added to the innermost open branch
+    fn current<'t, 'n>(root: &'n mut Vec<Node<'t>>, blocks: &'n mut [OpenBlock<'t>]) -> &'n mut Vec<Node<'t>>
+    {
+        match blocks.last_mut()
+        {
+            | Some(block) if block.in_else == true => &mut block.else_branch,
+            | Some(block) => &mut block.then_branch,
+            | None => root
+        }
+    }
+
+    for (i, &(inner, raw)) in tags.iter().enumerate()
+    {
+        if texts[i].is_empty() == false
+        {
+            current(&mut root, &mut blocks).push(Node::Text(texts[i]));
+        }
+
+        match kinds[i]
+        {
+            | TagKind::If =>
+            {
+                let condition = parse_condition(inner.trim()["#if".len()..].trim()).map_err(|e| format!("{} in {}", e, raw))?;
+                blocks.push(OpenBlock { condition, then_branch: Vec::new(), else_branch: Vec::new(), in_else: false });
+            }
+            | TagKind::Else => match blocks.last_mut()
+            {
+                | Some(block) if block.in_else == false => block.in_else = true,
+                | Some(_) => return Err(format!("Duplicate {} in {{{{#if}}}} block", raw).into()),
+                | None => return Err(format!("{} without {{{{#if}}}}", raw).into())
+            },
+            | TagKind::EndIf =>
+            {
+                let block = blocks.pop().ok_or_else(|| format!("{} without {{{{#if}}}}", raw))?;
+                current(&mut root, &mut blocks).push(Node::If { condition: block.condition, then_branch: block.then_branch, else_branch: block.else_branch });
+            }
+            | TagKind::Include =>
+            {
+                let target = inner.trim()[1..].trim();
+                let path = target.strip_prefix("include ").map(str::trim).unwrap_or(target);
+                if path.is_empty() == true
+                {
+                    return Err(format!("Missing path in {}", raw).into());
+                }
+                current(&mut root, &mut blocks).push(Node::Include { path, standalone: standalone[i] });
+            }
+            | TagKind::Variable => current(&mut root, &mut blocks).push(Node::Variable { name: inner.trim(), raw }),
+            | TagKind::Literal => current(&mut root, &mut blocks).push(Node::Text(raw))
+        }
+    }
+
+    if blocks.is_empty() == false
+    {
+        return Err(format!("{} {{{{#if}}}} block(s) not closed with {{{{/if}}}}", blocks.len()).into());
+    }
+
+    if let Some(text) = texts.last() &&
+        text.is_empty() == false
+    {
+        root.push(Node::Text(text));
+    }
+
+    Ok(root)
+}
+
+/// Splits a template into text and tags
+///
+/// Returns the texts between tags (always one more than tags) and the tags as
+/// (inner text, full tag) pairs. A `{{` without a closing `}}` is text.
+fn tokenize(content: &str) -> (Vec<&str>, Vec<(&str, &str)>)
+{
+    let mut texts = Vec::new();
+    let mut tags = Vec::new();
+    let mut rest = content;
+
+    while let Some(start) = rest.find("{{") &&
+        let Some(end) = rest[start + 2..].find("}}")
+    {
+        let tag_end = start + 2 + end + 2;
+        texts.push(&rest[..start]);
+        tags.push((&rest[start + 2..start + 2 + end], &rest[start..tag_end]));
+        rest = &rest[tag_end..];
+    }
+
+    texts.push(rest);
+    (texts, tags)
+}
+
+/// Classifies a tag by its inner text
+fn tag_kind(inner: &str) -> TagKind
+{
+    let inner = inner.trim();
+
+    if inner == "#if" || inner.starts_with("#if ") == true
+    {
+        TagKind::If
+    }
+    else if inner == "else"
+    {
+        TagKind::Else
+    }
+    else if inner == "/if"
+    {
+        TagKind::EndIf
+    }
+    else if inner.starts_with('>') == true
+    {
+        TagKind::Include
+    }
+    else if is_variable_name(inner) == true
+    {
+        TagKind::Variable
+    }
+    else
+    {
+        TagKind::Literal
+    }
+}
+
+/// Parses the condition of an `{{#if}}` tag
+fn parse_condition(text: &str) -> Result<Condition>
+{
+    // The first operator separates the name from the value
+    let operator = [("==", false), ("!=", true)].into_iter().filter_map(|(op, negated)| text.find(op).map(|pos| (pos, negated))).min();
+
+    if let Some((pos, negated)) = operator
+    {
+        let name = text[..pos].trim();
+        let value = text[pos + 2..].trim();
+        let value = ['"', '\''].iter().find_map(|q| value.strip_prefix(*q).and_then(|v| v.strip_suffix(*q))).unwrap_or(value);
+
+        if is_variable_name(name) == false
+        {
+            return Err(format!("Invalid condition '{}'", text).into());
+        }
+        return Ok(Condition::Equals { name: name.to_string(), value: value.to_string(), negated });
+    }
+
+    let (name, negated) = match text.strip_prefix('!')
+    {
+        | Some(name) => (name.trim(), true),
+        | None => (text, false)
+    };
+
+    if is_variable_name(name) == false
+    {
+        return Err(format!("Invalid condition '{}'", text).into());
+    }
+    Ok(Condition::Defined { name: name.to_string(), negated })
+}
+
+/// Returns true if the text before a tag ends at the start of a line (only indentation after the last line break)
+fn starts_line(text: &str, at_start: bool) -> bool
+{
+    match text.rfind('\n')
+    {
+        | Some(pos) => text[pos + 1..].trim_matches([' ', '\t']).is_empty(),
+        | None => at_start == true && text.trim_matches([' ', '\t']).is_empty()
+    }
+}
+
+/// Returns true if the text after a tag continues with the end of the line (only whitespace before the line break)
+fn ends_line(text: &str, at_end: bool) -> bool
+{
+    match text.find('\n')
+    {
+        | Some(pos) => text[..pos].trim_matches([' ', '\t', '\r']).is_empty(),
+        | None => at_end == true && text.trim_matches([' ', '\t']).is_empty()
+    }
+}
+
+/// Removes the indentation after the last line break
+fn strip_indentation(text: &str) -> &str
+{
+    match text.rfind('\n')
+    {
+        | Some(pos) => &text[..pos + 1],
+        | None => ""
+    }
+}
+
+/// Removes everything up to and including the first line break
+fn strip_line_end(text: &str) -> &str
+{
+    match text.find('\n')
+    {
+        | Some(pos) => &text[pos + 1..],
+        | None => ""
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn variables(values: &[(&str, &str)]) -> TemplateVariables
+    {
+        let mut variables = TemplateVariables::default();
+        for (name, value) in values
+        {
+            variables.set(name, value);
+        }
+        variables
+    }
+
+    fn render(content: &str, values: &[(&str, &str)]) -> Result<String>
+    {
+        let variables = variables(values);
+        Ok(TemplateRenderer::new(&variables, Path::new("/nonexistent")).render(content)?.0)
+    }
+
+    #[test]
+    fn test_render_variables() -> Result<()>
+    {
+        let variables = variables(&[("project.name", "vibe-check"), ("year", "2026")]);
+        let renderer = TemplateRenderer::new(&variables, Path::new("/nonexistent"));
+
+        let (rendered, unresolved) = renderer.render("# {{project.name}}\n\n(c) {{ year }} {{git.user}}, {{git.user}} and {{ not a variable }} ${{ missing")?;
+        assert_eq!(rendered, "# vibe-check\n\n(c) 2026 {{git.user}}, {{git.user}} and {{ not a variable }} ${{ missing");
+        assert_eq!(unresolved, vec!["git.user".to_string()]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_conditions() -> Result<()>
+    {
+        let template =
+            "# Build\n\n{{#if lang == \"c++\"}}\nUse `std::` containers.\n{{else}}\nUse plain C arrays.\n{{/if}}\n{{#if agent}}\nAgent: {{agent}}\n{{/if}}\nDone\n";
+
+        assert_eq!(render(template, &[("lang", "c++"), ("agent", "claude")])?, "# Build\n\nUse `std::` containers.\nAgent: claude\nDone\n");
+        assert_eq!(render(template, &[("lang", "c")])?, "# Build\n\nUse plain C arrays.\nDone\n");
+
+        // Inline blocks, negation, inequality, and nesting
+        assert_eq!(render("a{{#if !agent}}b{{/if}}c", &[])?, "abc");
+        assert_eq!(render("{{#if agent != 'claude'}}x{{else}}y{{/if}}", &[("agent", "claude")])?, "y");
+        assert_eq!(render("{{#if lang}}{{#if agent == \"codex\"}}1{{else}}2{{/if}}{{/if}}", &[("lang", "rust"), ("agent", "claude")])?, "2");
+        assert_eq!(render("{{#if flag}}on{{/if}}", &[("flag", "false")])?, "");
+
+        // Undefined variables in conditions are not reported
+        let variables = TemplateVariables::default();
+        let (_, unresolved) = TemplateRenderer::new(&variables, Path::new("/nonexistent")).render("{{#if agent == \"claude\"}}{{agent}}{{/if}}")?;
+        assert!(unresolved.is_empty());
+
+        // Malformed templates are rejected
+        assert!(render("{{#if lang}}open", &[]).is_err());
+        assert!(render("{{/if}}", &[]).is_err());
+        assert!(render("{{else}}", &[]).is_err());
+        assert!(render("{{#if lang}}a{{else}}b{{else}}c{{/if}}", &[]).is_err());
+        assert!(render("{{#if my lang}}x{{/if}}", &[]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_includes() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        fs::create_dir_all(temp_dir.path().join("shared"))?;
+        fs::write(temp_dir.path().join("shared/cmake.md"), "cmake -B build\n{{#if lang == \"c++\"}}\ncmake --build build --target tests\n{{/if}}\n")?;
+        fs::write(temp_dir.path().join("shared/loop-a.md"), "{{> shared/loop-b.md}}")?;
+        fs::write(temp_dir.path().join("shared/loop-b.md"), "{{> shared/loop-a.md}}")?;
+
+        let variables = variables(&[("lang", "c++")]);
+        let renderer = TemplateRenderer::new(&variables, temp_dir.path());
+
+        let (rendered, _) = renderer.render("## Build\n\n  {{> include shared/cmake.md}}\nEnd\n")?;
+        assert_eq!(rendered, "## Build\n\ncmake -B build\ncmake --build build --target tests\nEnd\n");
+        assert_eq!(renderer.render("Run: {{> shared/cmake.md}}")?.0.lines().next(), Some("Run: cmake -B build"));
+
+        assert!(renderer.render("{{> shared/loop-a.md}}").is_err());
+        assert!(renderer.render("{{> ../outside.md}}").is_err());
+        assert!(renderer.render("{{> shared/missing.md}}").is_err());
+
+        assert_eq!(check_syntax("{{#if lang}}{{> a.md}}{{else}}{{> include b.md}}{{/if}}")?, vec!["a.md".to_string(), "b.md".to_string()]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,518 @@
+//! Template validation for vibe-check
+//!
+//! Lints a template tree before it is published: templates.yml is parsed
+//! strictly (see `TemplateConfig::parse`), every referenced source file must exist,
+//! every `$instructions` fragment category needs its insertion point in the main
+//! template, targets may only use the known placeholders, `mode: merge` is only
+//! used for file types that can be merged (see `file_merge`), and text files must
+//! be well-formed for the template language (see `template_renderer`).
+
+use std::{
+    fs,
+    path::{Path, PathBuf}
+};
+
+use owo_colors::OwoColorize;
+use serde::Serialize;
+
+use crate::{
+    Result,
+    bom::{ConfigErrorKind, FileMapping, FileMode, TemplateConfig, check_relative_path, check_target_path},
+    file_merge::MergeFormat,
+    template_renderer
+};
+
+/// Placeholders a target may start with
+const KNOWN_PLACEHOLDERS: &[&str] = &["$workspace", "$userprofile", "$instructions"];
+
+/// Severity of a validation finding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity
+{
+    /// The templates cannot be used as published
+    Error,
+    /// The templates work, but something looks unintended
+    Warning
+}
+
+/// A single problem found in a template tree
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finding
+{
+    pub severity: Severity,
+    /// Stable identifier of the check (e.g. `missing-source`)
+    pub code:     &'static str,
+    /// Key path in templates.yml the finding refers to (e.g. `languages.rust.files[0].source`),
+    /// or `line <n>, column <n>` for parse errors
+    pub location: String,
+    pub message:  String
+}
+
+/// Result of validating a template tree
+#[derive(Debug, Serialize)]
+pub struct ValidationReport
+{
+    /// Path of the validated templates.yml
+    pub path:     PathBuf,
+    /// templates.yml version (None if the file could not be parsed)
+    pub version:  Option<u32>,
+    pub errors:   usize,
+    pub warnings: usize,
+    pub findings: Vec<Finding>
+}
+
+impl ValidationReport
+{
+    /// Returns true if no errors were found (warnings are allowed)
+    pub fn is_valid(&self) -> bool
+    {
+        self.errors == 0
+    }
+
+    /// Prints the report in human-readable form
+    pub fn print(&self)
+    {
+        match self.version
+        {
+            | Some(version) => println!("{} Validating {} (version {})", "→".blue(), self.path.display().to_string().yellow(), version),
+            | None => println!("{} Validating {}", "→".blue(), self.path.display().to_string().yellow())
+        }
+
+        for finding in &self.findings
+        {
+            let label = match finding.severity
+            {
+                | Severity::Error => format!("✗ error[{}]", finding.code).red().to_string(),
+                | Severity::Warning => format!("! warning[{}]", finding.code).yellow().to_string()
+            };
+            println!("  {} {}: {}", label, finding.location.bold(), finding.message);
+        }
+
+        if self.is_valid() == true
+        {
+            println!("{} Templates are valid ({} warning(s))", "✓".green(), self.warnings);
+        }
+        else
+        {
+            println!("{} {} error(s), {} warning(s)", "✗".red(), self.errors, self.warnings);
+        }
+    }
+
+    /// Records a finding
+    fn push(&mut self, severity: Severity, code: &'static str, location: impl Into<String>, message: impl Into<String>)
+    {
+        match severity
+        {
+            | Severity::Error => self.errors += 1,
+            | Severity::Warning => self.warnings += 1
+        }
+        self.findings.push(Finding { severity, code, location: location.into(), message: message.into() });
+    }
+}
+
+/// Validates a template tree
+///
+/// # Arguments
+///
+/// * `path` - Template directory, or the templates.yml inside it
+///
+/// # Returns
+///
+/// The report of all findings; a templates.yml that cannot be parsed is reported
+/// as an error finding rather than returned as an error
+///
+/// # Errors
+///
+/// Returns an error if templates.yml does not exist or cannot be read
+pub fn validate_templates(path: &Path) -> Result<ValidationReport>
+{
+    let (template_dir, config_path) = if path.is_dir() == true
+    {
+        (path.to_path_buf(), path.join("templates.yml"))
+    }
+    else
+    {
+        (path.parent().map(Path::to_path_buf).unwrap_or_default(), path.to_path_buf())
+    };
+
+    let content = fs::read_to_string(&config_path).map_err(|e| format!("Cannot read {}: {}", config_path.display(), e))?;
+    let mut report = ValidationReport { path: config_path, version: None, errors: 0, warnings: 0, findings: Vec::new() };
+
+    let config = match TemplateConfig::parse(&content, "templates.yml")
+    {
+        | Ok(config) => config,
+        | Err(e) =>
+        {
+            let code = match e.kind
+            {
+                | ConfigErrorKind::Syntax => "parse",
+                | ConfigErrorKind::UnknownField => "unknown-key",
+                | ConfigErrorKind::MissingVersion => "missing-version",
+                | ConfigErrorKind::UnsupportedVersion => "unsupported-version"
+            };
+            let location = match (e.line, e.column)
+            {
+                | (Some(line), Some(column)) => format!("line {}, column {}", line, column),
+                | _ => "templates.yml".to_string()
+            };
+            report.push(Severity::Error, code, location, e.message);
+            return Ok(report);
+        }
+    };
+    report.version = Some(config.version);
+
+    let entries = file_entries(&config);
+    for (location, mapping, category) in &entries
+    {
+        check_entry(&mut report, &template_dir, &config, location, mapping, *category);
+    }
+
+    match &config.main
+    {
+        | Some(main) =>
+        {
+            let mapping = FileMapping { source: main.source.clone(), target: main.target.clone(), mode: None, prefer: None, render: None };
+            check_entry(&mut report, &template_dir, &config, "main", &mapping, None);
+            check_insertion_points(&mut report, &template_dir.join(&main.source), &entries);
+        }
+        | None if config.extends.is_none() => report.push(Severity::Error, "missing-main", "main", "no main template is defined"),
+        | None =>
+        {}
+    }
+
+    for (index, include) in config.includes.iter().flatten().enumerate()
+    {
+        check_source(&mut report, &template_dir, &config, &format!("includes[{}]", index), include);
+    }
+
+    check_template_syntax(&mut report, &template_dir, &config, &entries);
+
+    Ok(report)
+}
+
+/// Lists every file mapping with its key path and fragment category (None for agent files)
+fn file_entries(config: &TemplateConfig) -> Vec<(String, &FileMapping, Option<&'static str>)>
+{
+    let mut entries = Vec::new();
+
+    for (name, list) in [("principles", &config.principles), ("mission", &config.mission)]
+    {
+        if let Some(list) = list
+        {
+            push_list(&mut entries, name, list, Some(name));
+        }
+    }
+
+    let mut languages: Vec<_> = config.languages.iter().collect();
+    languages.sort_by_key(|(name, _)| name.as_str());
+    for (name, language) in languages
+    {
+        push_list(&mut entries, &format!("languages.{}.files", name), &language.files, Some("languages"));
+    }
+
+    let mut integrations: Vec<_> = config.integration.iter().flatten().collect();
+    integrations.sort_by_key(|(name, _)| name.as_str());
+    for (name, integration) in integrations
+    {
+        push_list(&mut entries, &format!("integration.{}.files", name), &integration.files, Some("integration"));
+    }
+
+    let mut agents: Vec<_> = config.agents.iter().flatten().collect();
+    agents.sort_by_key(|(name, _)| name.as_str());
+    for (name, agent) in agents
+    {
+        for (kind, list) in [("instructions", &agent.instructions), ("prompts", &agent.prompts), ("skills", &agent.skills)]
+        {
+            if let Some(list) = list
+            {
+                push_list(&mut entries, &format!("agents.{}.{}", name, kind), list, None);
+            }
+        }
+    }
+
+    entries
+}
+
+/// Adds the mappings of one list with their indexed key paths
+fn push_list<'a>(entries: &mut Vec<(String, &'a FileMapping, Option<&'static str>)>, prefix: &str, list: &'a [FileMapping], category: Option<&'static str>)
+{
+    for (index, mapping) in list.iter().enumerate()
+    {
+        entries.push((format!("{}[{}]", prefix, index), mapping, category));
+    }
+}
+
+/// Checks the source and target of one file mapping
+fn check_entry(report: &mut ValidationReport, template_dir: &Path, config: &TemplateConfig, location: &str, mapping: &FileMapping, category: Option<&str>)
+{
+    let target_location = format!("{}.target", location);
+
+    check_source(report, template_dir, config, &format!("{}.source", location), &mapping.source);
+
+    let placeholder = mapping.target.split(['/', '\\']).next().unwrap_or_default();
+    if placeholder.starts_with('$') && KNOWN_PLACEHOLDERS.contains(&placeholder) == false
+    {
+        report.push(
+            Severity::Error,
+            "unknown-placeholder",
+            &target_location,
+            format!("'{}' uses unknown placeholder {} (known: {})", mapping.target, placeholder, KNOWN_PLACEHOLDERS.join(", "))
+        );
+        return;
+    }
+
+    if let Some(reason) = check_target_path(&mapping.target)
+    {
+        report.push(Severity::Error, "unsafe-path", &target_location, format!("'{}': {}", mapping.target, reason));
+        return;
+    }
+
+    if mapping.target == "$instructions" && (category.is_none() || location == "main")
+    {
+        report.push(
+            Severity::Error,
+            "misplaced-instructions",
+            &target_location,
+            "$instructions fragments are only merged from principles, mission, languages, and integration"
+        );
+    }
+
+    let mode_location = format!("{}.mode", location);
+    match mapping.mode
+    {
+        | Some(_) if mapping.target == "$instructions" =>
+            report.push(Severity::Warning, "ignored-mode", &mode_location, "mode has no effect on $instructions fragments"),
+        | Some(FileMode::Merge) if MergeFormat::detect(Path::new(&mapping.target)).is_none() =>
+        {
+            report.push(
+                Severity::Error,
+                "unsupported-merge",
+                &mode_location,
+                format!("'{}' cannot be merged (supported: .gitignore, .gitattributes, .editorconfig, TOML, YAML, JSON)", mapping.target)
+            );
+        }
+        | _ =>
+        {}
+    }
+
+    if mapping.prefer.is_some() == true && mapping.mode != Some(FileMode::Merge)
+    {
+        report.push(Severity::Warning, "ignored-prefer", format!("{}.prefer", location), "prefer only applies to entries with mode: merge");
+    }
+}
+
+/// Checks that a source or include path is safe and the file exists
+fn check_source(report: &mut ValidationReport, template_dir: &Path, config: &TemplateConfig, location: &str, source: &str)
+{
+    match check_relative_path(source)
+    {
+        | Some(reason) => report.push(Severity::Error, "unsafe-path", location, format!("'{}': {}", source, reason)),
+        | None if template_dir.join(source).is_file() == false =>
+        {
+            // Files of a template that extends another may be inherited from the parent
+            if config.extends.is_some()
+            {
+                report.push(Severity::Warning, "missing-source", location, format!("'{}' not found (must be provided by the parent)", source));
+            }
+            else
+            {
+                report.push(Severity::Error, "missing-source", location, format!("'{}' not found", source));
+            }
+        }
+        | None =>
+        {}
+    }
+}
+
+/// Checks the template language syntax of every rendered file and that includes are declared
+///
+/// Rendered files are the main template, `$instructions` fragments, includes, and files
+/// with `render: true`; all other files are copied as they are.
+///
+/// Included files that are not listed in templates.yml (under `includes` or as a
+/// source) are not installed from remote sources, so `init` could not find them.
+fn check_template_syntax(report: &mut ValidationReport, template_dir: &Path, config: &TemplateConfig, entries: &[(String, &FileMapping, Option<&'static str>)])
+{
+    let mut files: Vec<(String, &str)> = entries
+        .iter()
+        .filter(|(_, mapping, _)| mapping.target == "$instructions" || mapping.render == Some(true))
+        .map(|(location, mapping, _)| (format!("{}.source", location), mapping.source.as_str()))
+        .collect();
+    if let Some(main) = &config.main
+    {
+        files.push(("main.source".to_string(), main.source.as_str()));
+    }
+    for (index, include) in config.includes.iter().flatten().enumerate()
+    {
+        files.push((format!("includes[{}]", index), include.as_str()));
+    }
+
+    let declared = config.source_files();
+    let mut checked: Vec<&str> = Vec::new();
+
+    for (location, source) in files
+    {
+        // Shared sources are checked once; unsafe paths are already reported
+        if checked.contains(&source) == true || check_relative_path(source).is_some()
+        {
+            continue;
+        }
+        checked.push(source);
+
+        // Missing files are reported by check_source, binary files are copied as they are
+        let Ok(content) = fs::read_to_string(template_dir.join(source))
+        else
+        {
+            continue;
+        };
+
+        match template_renderer::check_syntax(&content)
+        {
+            | Ok(includes) =>
+            {
+                for include in includes
+                {
+                    if declared.contains(&include.as_str()) == false
+                    {
+                        report.push(
+                            Severity::Warning,
+                            "undeclared-include",
+                            &location,
+                            format!("'{}' includes '{}', which is not listed under includes", source, include)
+                        );
+                    }
+                }
+            }
+            | Err(e) => report.push(Severity::Error, "template-syntax", &location, format!("'{}': {}", source, e))
+        }
+    }
+}
+
+/// Checks that the main template has an insertion point for every fragment category in use
+fn check_insertion_points(report: &mut ValidationReport, main_path: &Path, entries: &[(String, &FileMapping, Option<&'static str>)])
+{
+    let Ok(main_content) = fs::read_to_string(main_path)
+    else
+    {
+        // A missing main template is already reported by check_entry
+        return;
+    };
+
+    let mut categories: Vec<&str> = entries.iter().filter(|(_, mapping, _)| mapping.target == "$instructions").filter_map(|(_, _, category)| *category).collect();
+    categories.sort();
+    categories.dedup();
+
+    for category in categories
+    {
+        let insertion_point = format!("<!-- {{{}}} -->", category);
+        if main_content.contains(&insertion_point) == false
+        {
+            report.push(
+                Severity::Error,
+                "missing-insertion-point",
+                "main.source",
+                format!("main template has no {} insertion point for the {} fragments", insertion_point, category)
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn codes(report: &ValidationReport) -> Vec<(&'static str, &str)>
+    {
+        report.findings.iter().map(|f| (f.code, f.location.as_str())).collect()
+    }
+
+    #[test]
+    fn test_validate_templates() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let dir = temp_dir.path();
+        fs::write(dir.join("AGENTS.md"), "# Agents\n<!-- {principles} -->\n")?;
+        fs::write(dir.join("principles.md"), "Be nice")?;
+        fs::write(dir.join("rust.md"), "Use clippy")?;
+
+        // Valid tree
+        fs::write(
+            dir.join("templates.yml"),
+            "version: 2\nmain:\n  source: AGENTS.md\n  target: '$workspace/AGENTS.md'\nlanguages: {}\nprinciples:\n  - source: principles.md\n    target: \
+             $instructions\n"
+        )?;
+        let report = validate_templates(dir)?;
+        assert!(report.is_valid(), "{:?}", report.findings);
+        assert!(report.findings.is_empty());
+
+        // Every kind of problem is reported with its location
+        fs::write(
+            dir.join("templates.yml"),
+            "version: 2\nmain:\n  source: AGENTS.md\n  target: '$workspace/AGENTS.md'\nlanguages:\n  rust:\n    files:\n      - source: rust.md\n        target: \
+             $instructions\n        mode: merge\n      - source: missing.md\n        target: '$home/.rustfmt.toml'\n      - source: rust.md\n        target: \
+             '$workspace/CMakePresets.txt'\n        mode: merge\n        prefer: template\n      - source: rust.md\n        target: '$workspace/.rustfmt.toml'\n        \
+             prefer: template\nagents:\n  claude:\n    instructions:\n      - source: ../CLAUDE.md\n        target: $instructions\n"
+        )?;
+        let report = validate_templates(&dir.join("templates.yml"))?;
+        assert!(report.is_valid() == false);
+        assert_eq!(codes(&report), vec![
+            ("ignored-mode", "languages.rust.files[0].mode"),
+            ("missing-source", "languages.rust.files[1].source"),
+            ("unknown-placeholder", "languages.rust.files[1].target"),
+            ("unsupported-merge", "languages.rust.files[2].mode"),
+            ("ignored-prefer", "languages.rust.files[3].prefer"),
+            ("unsafe-path", "agents.claude.instructions[0].source"),
+            ("misplaced-instructions", "agents.claude.instructions[0].target"),
+            ("missing-insertion-point", "main.source")
+        ]);
+        assert_eq!(report.errors, 6);
+
+        // Template language problems in sources and includes
+        fs::create_dir_all(dir.join("shared"))?;
+        fs::write(dir.join("shared/tone.md"), "{{#if agent}}Be nice")?;
+        fs::write(dir.join("principles.md"), "{{> shared/tone.md}}\n{{> include shared/style.md}}\n")?;
+        fs::write(
+            dir.join("templates.yml"),
+            "version: 2\nmain:\n  source: AGENTS.md\n  target: '$workspace/AGENTS.md'\nlanguages: {}\nprinciples:\n  - source: principles.md\n    target: \
+             $instructions\nincludes:\n  - shared/tone.md\n  - shared/missing.md\n"
+        )?;
+        assert_eq!(codes(&validate_templates(dir)?), vec![
+            ("missing-source", "includes[1]"),
+            ("undeclared-include", "principles[0].source"),
+            ("template-syntax", "includes[0]")
+        ]);
+
+        // Parse problems are reported with their line and column
+        fs::write(dir.join("templates.yml"), "version: 2\nlanguages:\n  rust:\n    files: []\n    mode: replace\n")?;
+        assert_eq!(codes(&validate_templates(dir)?), vec![("unknown-key", "line 5, column 5")]);
+        fs::write(dir.join("templates.yml"), "languages: {}\n")?;
+        assert_eq!(codes(&validate_templates(dir)?), vec![("missing-version", "line 1, column 1")]);
+        fs::write(dir.join("templates.yml"), "version: [2\n")?;
+        assert_eq!(validate_templates(dir)?.findings[0].code, "parse");
+
+        // Copied files are only checked if they are rendered
+        fs::write(dir.join("build.sh"), "echo {{else}}\n")?;
+        let copied = "version: 2\nmain:\n  source: AGENTS.md\n  target: '$workspace/AGENTS.md'\nlanguages:\n  rust:\n    files:\n      - source: build.sh\n        \
+                      target: '$workspace/build.sh'\n";
+        fs::write(dir.join("templates.yml"), copied)?;
+        assert!(validate_templates(dir)?.findings.is_empty());
+        fs::write(dir.join("templates.yml"), format!("{}        render: true\n", copied))?;
+        assert_eq!(codes(&validate_templates(dir)?), vec![("template-syntax", "languages.rust.files[0].source")]);
+
+        // A missing templates.yml is an error rather than a finding
+        assert!(validate_templates(&dir.join("missing")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_default_templates_are_valid() -> Result<()>
+    {
+        let report = validate_templates(&Path::new(env!("CARGO_MANIFEST_DIR")).join("templates/v2"))?;
+        assert!(report.findings.is_empty() == true, "{:?}", report.findings);
+        Ok(())
+    }
+}
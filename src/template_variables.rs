@@ -0,0 +1,263 @@
+//! Template variables for vibe-check
+//!
+//! Template files and fragments may reference variables as `{{name}}` (see
+//! `template_renderer`). Built-in values are detected from the workspace:
+//! `project.*` from Cargo.toml or package.json, `git.*` from the git
+//! configuration, plus `year`, `date`, `lang`, and `agent`. Values from the
+//! `vars.<name>` config keys and `init --var name=value` override them.
+
+use std::{collections::BTreeMap, fs, path::Path, process::Command};
+
+use crate::Result;
+
+/// Variables available to templates, keyed by name
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TemplateVariables
+{
+    values: BTreeMap<String, String>
+}
+
+impl TemplateVariables
+{
+    /// Detects the built-in variables for a workspace
+    ///
+    /// | Variable              | Source                                                 |
+    /// |-----------------------|--------------------------------------------------------|
+    /// | `project.name`        | Cargo.toml, package.json, or the workspace folder name |
+    /// | `project.version`     | Cargo.toml or package.json                             |
+    /// | `project.description` | Cargo.toml or package.json                             |
+    /// | `project.repository`  | Cargo.toml, package.json, or the `origin` git remote   |
+    /// | `git.user`            | `git config user.name`                                 |
+    /// | `git.email`           | `git config user.email`                                |
+    /// | `year`, `date`        | Current local date                                     |
+    /// | `lang`                | Language selected for `init`                           |
+    /// | `agent`               | Agent selected for `init`                              |
+    ///
+    /// Values that cannot be detected are left undefined.
+    ///
+    /// # Arguments
+    ///
+    /// * `workspace` - Project directory
+    /// * `lang` - Selected language (None for `--no-lang`)
+    /// * `agent` - Selected agent, if any
+    pub fn detect(workspace: &Path, lang: Option<&str>, agent: Option<&str>) -> Self
+    {
+        let mut variables = Self::default();
+
+        let now = chrono::Local::now();
+        variables.set("year", &now.format("%Y").to_string());
+        variables.set("date", &now.format("%Y-%m-%d").to_string());
+
+        if let Some(lang) = lang
+        {
+            variables.set("lang", lang);
+        }
+
+        if let Some(agent) = agent
+        {
+            variables.set("agent", agent);
+        }
+
+        let manifest = read_cargo_manifest(workspace).or_else(|| read_package_json(workspace)).unwrap_or_default();
+        for (name, value) in manifest
+        {
+            variables.set(&format!("project.{}", name), &value);
+        }
+
+        if variables.get("project.name").is_none() == true &&
+            let Some(name) = workspace.file_name()
+        {
+            variables.set("project.name", &name.to_string_lossy());
+        }
+
+        if variables.get("project.repository").is_none() == true &&
+            let Some(url) = git_output(workspace, &["remote", "get-url", "origin"])
+        {
+            variables.set("project.repository", &url);
+        }
+
+        if let Some(user) = git_output(workspace, &["config", "user.name"])
+        {
+            variables.set("git.user", &user);
+        }
+
+        if let Some(email) = git_output(workspace, &["config", "user.email"])
+        {
+            variables.set("git.email", &email);
+        }
+
+        variables
+    }
+
+    /// Returns the value of a variable
+    pub fn get(&self, name: &str) -> Option<&str>
+    {
+        self.values.get(name).map(String::as_str)
+    }
+
+    /// Sets a variable, replacing any previous value
+    pub fn set(&mut self, name: &str, value: &str)
+    {
+        self.values.insert(name.to_string(), value.to_string());
+    }
+
+    /// Sets several variables, replacing previous values (used for config and `--var` overrides)
+    pub fn extend(&mut self, values: &BTreeMap<String, String>)
+    {
+        for (name, value) in values
+        {
+            self.set(name, value);
+        }
+    }
+
+    /// Parses a `name=value` variable assignment as given to `--var`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no `=` or the name is invalid
+    pub fn parse_assignment(assignment: &str) -> Result<(String, String)>
+    {
+        let (name, value) = assignment.split_once('=').ok_or_else(|| format!("Invalid variable '{}' (expected name=value)", assignment))?;
+        let name = name.trim();
+        validate_variable_name(name)?;
+        Ok((name.to_string(), value.to_string()))
+    }
+}
+
+/// Validates a variable name
+///
+/// # Errors
+///
+/// Returns an error if the name is empty or contains characters other than
+/// ASCII letters, digits, `_`, `-`, and `.`
+pub fn validate_variable_name(name: &str) -> Result<()>
+{
+    if is_variable_name(name) == false
+    {
+        return Err(format!("Invalid variable name '{}' (use letters, digits, '_', '-' and '.')", name).into());
+    }
+    Ok(())
+}
+
+/// Returns true if the text is a valid variable name
+pub(crate) fn is_variable_name(name: &str) -> bool
+{
+    name.is_empty() == false && name.starts_with('.') == false && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.') == true
+}
+
+/// Reads name, version, description, and repository from `[package]` in Cargo.toml
+///
+/// Values inherited from the workspace (`version.workspace = true`) are skipped.
+fn read_cargo_manifest(workspace: &Path) -> Option<Vec<(String, String)>>
+{
+    let content = fs::read_to_string(workspace.join("Cargo.toml")).ok()?;
+    let manifest: toml::Table = content.parse().ok()?;
+    let package = manifest.get("package")?.as_table()?;
+
+    let fields = ["name", "version", "description", "repository"]
+        .into_iter()
+        .filter_map(|field| package.get(field).and_then(|v| v.as_str()).map(|v| (field.to_string(), v.to_string())))
+        .collect();
+    Some(fields)
+}
+
+/// Reads name, version, description, and repository from package.json
+///
+/// `repository` may be a URL string or an object with a `url` field.
+fn read_package_json(workspace: &Path) -> Option<Vec<(String, String)>>
+{
+    let content = fs::read_to_string(workspace.join("package.json")).ok()?;
+    let package: serde_json::Value = serde_json::from_str(&content).ok()?;
+
+    let mut fields: Vec<(String, String)> = ["name", "version", "description"]
+        .into_iter()
+        .filter_map(|field| package.get(field).and_then(|v| v.as_str()).map(|v| (field.to_string(), v.to_string())))
+        .collect();
+
+    let repository = package.get("repository").and_then(|r| r.as_str().or_else(|| r.get("url").and_then(|u| u.as_str())));
+    if let Some(repository) = repository
+    {
+        fields.push(("repository".to_string(), repository.to_string()));
+    }
+
+    Some(fields)
+}
+
+/// Runs a git command in the workspace and returns its trimmed output (None on failure or empty output)
+fn git_output(workspace: &Path, args: &[&str]) -> Option<String>
+{
+    let output = Command::new("git").arg("-C").arg(workspace).args(args).output().ok()?;
+    if output.status.success() == false
+    {
+        return None;
+    }
+
+    let value = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    if value.is_empty() == true
+    {
+        None
+    }
+    else
+    {
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_assignment()
+    {
+        let mut variables = TemplateVariables::default();
+        variables.set("project.name", "detected");
+
+        // Overrides replace detected values
+        let mut overrides = BTreeMap::new();
+        overrides.insert("project.name".to_string(), "custom".to_string());
+        variables.extend(&overrides);
+        assert_eq!(variables.get("project.name"), Some("custom"));
+
+        assert_eq!(TemplateVariables::parse_assignment("team=Platform = Tools").ok(), Some(("team".to_string(), "Platform = Tools".to_string())));
+        assert!(TemplateVariables::parse_assignment("team").is_err());
+        assert!(TemplateVariables::parse_assignment("my team=x").is_err());
+    }
+
+    #[test]
+    fn test_detect_project_variables() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let workspace = temp_dir.path().join("my-app");
+        fs::create_dir_all(&workspace)?;
+
+        // Without a manifest the folder name is used
+        let variables = TemplateVariables::detect(&workspace, None, None);
+        assert_eq!(variables.get("project.name"), Some("my-app"));
+        assert_eq!(variables.get("lang"), None);
+        assert_eq!(variables.get("year").map(str::len), Some(4));
+
+        fs::write(
+            workspace.join("package.json"),
+            r#"{ "name": "web-app", "version": "1.2.3", "repository": { "type": "git", "url": "https://example.com/web-app.git" } }"#
+        )?;
+        let variables = TemplateVariables::detect(&workspace, Some("typescript"), Some("cursor"));
+        assert_eq!(variables.get("project.name"), Some("web-app"));
+        assert_eq!(variables.get("project.version"), Some("1.2.3"));
+        assert_eq!(variables.get("project.repository"), Some("https://example.com/web-app.git"));
+        assert_eq!(variables.get("lang"), Some("typescript"));
+        assert_eq!(variables.get("agent"), Some("cursor"));
+
+        // Cargo.toml takes precedence over package.json
+        fs::write(workspace.join("Cargo.toml"), "[package]\nname = \"rusty\"\nversion.workspace = true\ndescription = \"A crate\"\n")?;
+        let variables = TemplateVariables::detect(&workspace, Some("rust"), None);
+        assert_eq!(variables.get("project.name"), Some("rusty"));
+        assert_eq!(variables.get("project.version"), None);
+        assert_eq!(variables.get("project.description"), Some("A crate"));
+
+        Ok(())
+    }
+}
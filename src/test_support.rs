@@ -0,0 +1,186 @@
+//! Test helpers shared by unit tests
+//!
+//! Provides a minimal local HTTP server that stands in for template hosting
+//! services, so download logic can be tested without network access.
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering}
+    },
+    thread
+};
+
+/// HTTP request received by the test server
+#[derive(Debug, Clone)]
+pub struct TestRequest
+{
+    pub path:    String,
+    /// Request headers with lowercase names
+    pub headers: HashMap<String, String>
+}
+
+/// HTTP response returned by a test server handler
+pub struct TestResponse
+{
+    pub status:  u16,
+    pub body:    Vec<u8>,
+    pub headers: Vec<(String, String)>
+}
+
+impl TestResponse
+{
+    /// Creates a response with the given status and body
+    pub fn new(status: u16, body: impl Into<Vec<u8>>) -> Self
+    {
+        Self { status, body: body.into(), headers: Vec::new() }
+    }
+
+    /// Adds a response header
+    pub fn with_header(mut self, name: &str, value: &str) -> Self
+    {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+type Handler = dyn Fn(&TestRequest) -> TestResponse + Send + Sync;
+
+/// Local HTTP/1.1 server running on a background thread
+///
+/// The server is stopped when the value is dropped.
+pub struct TestServer
+{
+    addr:    SocketAddr,
+    running: Arc<AtomicBool>
+}
+
+impl TestServer
+{
+    /// Starts a server that answers every request with the given handler
+    pub fn start(handler: impl Fn(&TestRequest) -> TestResponse + Send + Sync + 'static) -> Self
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind test server");
+        let addr = listener.local_addr().expect("test server address");
+        let running = Arc::new(AtomicBool::new(true));
+        let handler: Arc<Handler> = Arc::new(handler);
+
+        let flag = running.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming()
+            {
+                if flag.load(Ordering::SeqCst) == false
+                {
+                    break;
+                }
+                if let Ok(stream) = stream
+                {
+                    let handler = handler.clone();
+                    thread::spawn(move || handle_connection(stream, handler.as_ref()));
+                }
+            }
+        });
+
+        Self { addr, running }
+    }
+
+    /// Starts a server that serves files from a directory (404 for missing files)
+    pub fn serve_dir(root: PathBuf) -> Self
+    {
+        Self::start(move |request| {
+            let relative = request.path.split('?').next().unwrap_or("").trim_start_matches('/');
+            match fs::read(root.join(relative))
+            {
+                | Ok(content) => TestResponse::new(200, content),
+                | Err(_) => TestResponse::new(404, "not found")
+            }
+        })
+    }
+
+    /// Starts a server like `serve_dir` that also sends a content-based `ETag` and
+    /// answers a matching `If-None-Match` with 304 Not Modified
+    pub fn serve_dir_with_etags(root: PathBuf) -> Self
+    {
+        Self::start(move |request| {
+            let relative = request.path.split('?').next().unwrap_or("").trim_start_matches('/');
+            let Ok(content) = fs::read(root.join(relative))
+            else
+            {
+                return TestResponse::new(404, "not found");
+            };
+            let etag = format!("\"{:x}\"", content.iter().fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)));
+            if request.headers.get("if-none-match") == Some(&etag)
+            {
+                return TestResponse::new(304, "");
+            }
+            TestResponse::new(200, content).with_header("ETag", &etag)
+        })
+    }
+
+    /// Returns the base URL of the server (e.g. `http://127.0.0.1:12345`)
+    pub fn url(&self) -> String
+    {
+        format!("http://{}", self.addr)
+    }
+}
+
+impl Drop for TestServer
+{
+    fn drop(&mut self)
+    {
+        self.running.store(false, Ordering::SeqCst);
+        // Wake up the accept loop so the thread can exit
+        let _ = TcpStream::connect(self.addr);
+    }
+}
+
+/// Reads a single request from the stream and writes the handler's response
+fn handle_connection(stream: TcpStream, handler: &Handler)
+{
+    let mut reader = BufReader::new(match stream.try_clone()
+    {
+        | Ok(s) => s,
+        | Err(_) => return
+    });
+
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() || request_line.is_empty()
+    {
+        return;
+    }
+
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/").to_string();
+
+    let mut headers = HashMap::new();
+    loop
+    {
+        let mut line = String::new();
+        if reader.read_line(&mut line).is_err() || line.trim().is_empty()
+        {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':')
+        {
+            headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let response = handler(&TestRequest { path, headers });
+
+    let mut out = stream;
+    let mut head = format!("HTTP/1.1 {} Test\r\nContent-Length: {}\r\nConnection: close\r\n", response.status, response.body.len());
+    for (name, value) in &response.headers
+    {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+
+    let _ = out.write_all(head.as_bytes());
+    let _ = out.write_all(&response.body);
+    let _ = out.flush();
+}
@@ -1,6 +1,7 @@
 //! Utility functions for vibe-check
 
 use std::{
+    collections::HashMap,
     fs,
     io::{self, Write},
     path::Path,
@@ -64,6 +65,52 @@ pub fn copy_dir_all(src: &Path, dst: &Path) -> Result<()>
     Ok(())
 }
 
+/// Splits a template source into its location and `#key=value&...` options
+///
+/// Options are appended to a source as a URL fragment, e.g.
+/// `git+https://example.com/team/templates.git#ref=v1.2.0&path=templates/v2`.
+/// The fragment is never sent to a server, so it is safe to use with HTTP URLs.
+///
+/// # Arguments
+///
+/// * `source` - Source string as given on the command line or in config
+pub(crate) fn split_source_options(source: &str) -> (&str, HashMap<String, String>)
+{
+    let mut options = HashMap::new();
+
+    let Some((location, fragment)) = source.split_once('#')
+    else
+    {
+        return (source, options);
+    };
+
+    for pair in fragment.split('&').filter(|p| p.is_empty() == false)
+    {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        options.insert(key.to_string(), value.to_string());
+    }
+
+    (location, options)
+}
+
+/// Sets a `#key=value` option on a template source, replacing an existing value
+///
+/// # Arguments
+///
+/// * `source` - Source string as given on the command line or in config
+/// * `key` - Option name (e.g. `ref`)
+/// * `value` - New option value
+pub(crate) fn set_source_option(source: &str, key: &str, value: &str) -> String
+{
+    let (location, _) = split_source_options(source);
+    let fragment = source.split_once('#').map(|(_, fragment)| fragment).unwrap_or("");
+
+    let mut pairs: Vec<String> = fragment.split('&').filter(|p| p.is_empty() == false && p.split('=').next() != Some(key)).map(str::to_string).collect();
+    pairs.insert(0, format!("{}={}", key, value));
+
+    format!("{}#{}", location, pairs.join("&"))
+}
+
 /// Copies a file from source to target, creating parent directories if needed
 ///
 /// # Arguments
@@ -142,6 +189,7 @@ pub enum FileActionResponse
 {
     Skip,
     Overwrite,
+    Merge,
     Quit
 }
 
@@ -150,6 +198,7 @@ pub enum FileActionResponse
 /// Shows the file path and SHA checksums, then presents options to:
 /// - Skip (keep local version)
 /// - Overwrite (use new template)
+/// - Merge (three-way merge of the template changes into the local file, if a base is available)
 /// - Show diff
 /// - Quit operation
 ///
@@ -159,6 +208,7 @@ pub enum FileActionResponse
 /// * `original_sha` - SHA checksum when file was originally installed
 /// * `current_sha` - Current SHA checksum of the file
 /// * `template_path` - Path to the new template file (for diff)
+/// * `can_merge` - Whether the merge option is offered (the installed content is known)
 ///
 /// # Returns
 ///
@@ -167,7 +217,7 @@ pub enum FileActionResponse
 /// # Errors
 ///
 /// Returns an error if reading from stdin fails or showing diff fails
-pub fn prompt_file_modification(file_path: &Path, original_sha: &str, current_sha: &str, template_path: &Path) -> Result<FileActionResponse>
+pub fn prompt_file_modification(file_path: &Path, original_sha: &str, current_sha: &str, template_path: &Path, can_merge: bool) -> Result<FileActionResponse>
 {
     loop
     {
@@ -180,6 +230,10 @@ pub fn prompt_file_modification(file_path: &Path, original_sha: &str, current_sh
         println!("Options:");
         println!("  [{}] Skip (keep your version)", "s".green().bold());
         println!("  [{}] Overwrite (use new template)", "o".red().bold());
+        if can_merge == true
+        {
+            println!("  [{}] Merge (apply template changes to your version)", "m".magenta().bold());
+        }
         println!("  [{}] Show diff", "d".blue().bold());
         println!("  [{}] Quit operation", "q".yellow().bold());
         println!();
@@ -194,6 +248,7 @@ pub fn prompt_file_modification(file_path: &Path, original_sha: &str, current_sh
         {
             | "s" | "skip" => return Ok(FileActionResponse::Skip),
             | "o" | "overwrite" => return Ok(FileActionResponse::Overwrite),
+            | "m" | "merge" if can_merge == true => return Ok(FileActionResponse::Merge),
             | "q" | "quit" => return Ok(FileActionResponse::Quit),
             | "d" | "diff" =>
             {
@@ -201,7 +256,15 @@ pub fn prompt_file_modification(file_path: &Path, original_sha: &str, current_sh
             }
             | _ =>
             {
-                println!("{} Invalid choice. Please enter s, o, d, or q.", "!".red());
+                let choices = if can_merge == true
+                {
+                    "s, o, m, d, or q"
+                }
+                else
+                {
+                    "s, o, d, or q"
+                };
+                println!("{} Invalid choice. Please enter {}.", "!".red(), choices);
             }
         }
     }
@@ -0,0 +1,246 @@
+//! Template release selection for vibe-check
+//!
+//! Resolves a semver range (e.g. `^7`, `7.1.x`, `>=7.0, <8`) against the tags
+//! of the source repository, using `git ls-remote` so no clone is needed. Tags
+//! may carry a `v` prefix (`v7.1.0`); tags that are not semver are ignored.
+//! Supported sources are git repositories and hosted repository URLs, which
+//! can also be pinned to the commit recorded in templates.lock.
+
+use semver::{Version, VersionReq};
+
+use crate::{
+    Result,
+    config::Config,
+    git_source::{self, GitSource},
+    source_provider::{ProviderKind, SourceLocation, SourceProvider},
+    utils::set_source_option
+};
+
+/// A template source pinned to a release tag
+#[derive(Debug, Clone, PartialEq)]
+pub struct PinnedSource
+{
+    /// Source string with the ref replaced by the tag
+    pub source:  String,
+    /// Tag the source is pinned to (e.g. `v7.1.0`)
+    pub tag:     String,
+    /// Version parsed from the tag
+    pub version: Version
+}
+
+/// Repository a template source lives in
+enum Repository
+{
+    /// `git+...` or `file://` source
+    Git(GitSource),
+    /// Browse URL of a hosted repository
+    Hosted(&'static dyn SourceProvider, SourceLocation)
+}
+
+impl Repository
+{
+    /// Determines the repository of a template source
+    fn detect(source: &str, config: &Config) -> Result<Self>
+    {
+        if let Some(git_source) = GitSource::parse(source)
+        {
+            return Ok(Self::Git(git_source));
+        }
+
+        if source.starts_with("http://") || source.starts_with("https://")
+        {
+            let kind = ProviderKind::resolve(source, config.source.provider.as_deref())?;
+            let provider = kind.provider();
+            if let Some(location) = provider.parse_url(source)
+            {
+                return Ok(Self::Hosted(provider, location));
+            }
+        }
+
+        Err(format!("Release versions can only be selected for git repositories and hosted repository URLs, not {}", source).into())
+    }
+
+    /// Returns the URL git uses to list the repository's tags
+    fn url(&self) -> String
+    {
+        match self
+        {
+            | Self::Git(git_source) => git_source.url.clone(),
+            | Self::Hosted(provider, location) => provider.clone_url(location)
+        }
+    }
+
+    /// Returns the source string pinned to a tag
+    fn pin(&self, source: &str, tag: &str) -> String
+    {
+        match self
+        {
+            | Self::Git(_) => set_source_option(source, "ref", tag),
+            | Self::Hosted(provider, location) =>
+            {
+                let mut location = location.clone();
+                location.refname = provider.tag_refname(tag);
+                provider.browse_url(&location)
+            }
+        }
+    }
+
+    /// Returns the source string pinned to a commit
+    fn pin_commit(&self, source: &str, commit: &str) -> String
+    {
+        match self
+        {
+            | Self::Git(_) => set_source_option(source, "ref", commit),
+            | Self::Hosted(provider, location) =>
+            {
+                let mut location = location.clone();
+                location.refname = provider.commit_refname(commit);
+                provider.browse_url(&location)
+            }
+        }
+    }
+}
+
+/// Parses a semver range given to `update --version`
+///
+/// # Errors
+///
+/// Returns an error if the range is not valid semver syntax
+pub fn parse_range(range: &str) -> Result<VersionReq>
+{
+    VersionReq::parse(range.trim()).map_err(|e| format!("Invalid version range '{}': {} (examples: ^7, 7.1.x, >=7.0, <8)", range, e).into())
+}
+
+/// Pins a template source to the newest tag matching a semver range
+///
+/// # Arguments
+///
+/// * `source` - Git repository or hosted repository URL
+/// * `range` - Semver range (e.g. `^7`)
+/// * `config` - User configuration (provider selection, network settings)
+///
+/// # Errors
+///
+/// Returns an error if the source type has no tags, the tags cannot be listed,
+/// or no tag matches the range
+pub fn resolve_range(source: &str, range: &str, config: &Config) -> Result<PinnedSource>
+{
+    let requirement = parse_range(range)?;
+    let repository = Repository::detect(source, config)?;
+    let url = repository.url();
+
+    let tags = git_source::list_remote_tags(&url, &config.network)?;
+    let (version, tag) = select_tag(&tags, &requirement).ok_or_else(|| format!("No tag in {} matches version range '{}'", url, range))?;
+
+    Ok(PinnedSource { source: repository.pin(source, &tag), tag, version })
+}
+
+/// Pins a template source to a known tag (used to reproduce a lock file)
+///
+/// # Errors
+///
+/// Returns an error if the source type has no tags or the tag is not semver
+pub fn pin_tag(source: &str, tag: &str, config: &Config) -> Result<PinnedSource>
+{
+    let version = parse_tag(tag).ok_or_else(|| format!("Tag '{}' is not a semver version", tag))?;
+    let repository = Repository::detect(source, config)?;
+
+    Ok(PinnedSource { source: repository.pin(source, tag), tag: tag.to_string(), version })
+}
+
+/// Pins a template source to a commit (used to reproduce a lock file)
+///
+/// Returns None for sources that are not repositories (local paths, archives).
+///
+/// # Arguments
+///
+/// * `source` - Template source
+/// * `commit` - Full commit SHA recorded in the lock
+/// * `config` - User configuration (provider selection)
+pub fn pin_commit(source: &str, commit: &str, config: &Config) -> Option<String>
+{
+    Repository::detect(source, config).ok().map(|repository| repository.pin_commit(source, commit))
+}
+
+/// Returns the newest tag matching a range
+///
+/// Pre-releases are only selected if the range names a pre-release of the same version.
+fn select_tag(tags: &[String], requirement: &VersionReq) -> Option<(Version, String)>
+{
+    tags.iter().filter_map(|tag| parse_tag(tag).map(|version| (version, tag.clone()))).filter(|(version, _)| requirement.matches(version)).max()
+}
+
+/// Parses a tag (with an optional `v` prefix) as a semver version
+fn parse_tag(tag: &str) -> Option<Version>
+{
+    Version::parse(tag.strip_prefix('v').or_else(|| tag.strip_prefix('V')).unwrap_or(tag)).ok()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::{fs, path::Path, process::Command};
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_select_tag()
+    {
+        let tags: Vec<String> = ["v6.9.0", "v7.0.0", "v7.1.0", "v7.2.0-beta.1", "7.1.5", "v8.0.0", "latest", "release-7"].iter().map(|t| t.to_string()).collect();
+
+        let select = |range: &str| select_tag(&tags, &parse_range(range).unwrap()).map(|(_, tag)| tag);
+        assert_eq!(select("^7").as_deref(), Some("7.1.5"));
+        assert_eq!(select("7.0.x").as_deref(), Some("v7.0.0"));
+        assert_eq!(select(">=7.2.0-beta.0, <8").as_deref(), Some("v7.2.0-beta.1"));
+        assert_eq!(select("^9"), None);
+        assert!(parse_range("seven").is_err());
+    }
+
+    #[test]
+    fn test_resolve_range_against_repository_tags() -> Result<()>
+    {
+        let temp_dir = TempDir::new()?;
+        let work = temp_dir.path().join("work");
+        fs::create_dir_all(&work)?;
+        let git = |dir: &Path, args: &[&str]| {
+            let output = Command::new("git").arg("-C").arg(dir).args(args).output().expect("git available");
+            assert!(output.status.success(), "git {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+        };
+
+        git(&work, &["init", "--quiet", "--initial-branch=main"]);
+        git(&work, &["config", "user.email", "test@example.com"]);
+        git(&work, &["config", "user.name", "Test"]);
+        fs::write(work.join("templates.yml"), "version: 2\n")?;
+        git(&work, &["add", "."]);
+        git(&work, &["commit", "--quiet", "-m", "first"]);
+        for tag in ["v7.0.0", "v7.3.1", "v8.0.0"]
+        {
+            git(&work, &["tag", tag]);
+        }
+
+        let url = format!("file://{}", work.to_string_lossy().replace('\\', "/"));
+        let pinned = resolve_range(&format!("{}#path=templates", url), "^7", &Config::default())?;
+        assert_eq!(pinned.tag, "v7.3.1");
+        assert_eq!(pinned.version, Version::new(7, 3, 1));
+        assert_eq!(pinned.source, format!("{}#ref=v7.3.1&path=templates", url));
+
+        // Hosted URLs are rewritten to the tag
+        let pinned = pin_tag("https://github.com/owner/repo/tree/develop/templates/v2", "v7.3.1", &Config::default())?;
+        assert_eq!(pinned.source, "https://github.com/owner/repo/tree/v7.3.1/templates/v2");
+
+        // Locked commits replace the ref
+        let commit = "0123456789abcdef0123456789abcdef01234567";
+        assert_eq!(pin_commit(&format!("{}#ref=main&path=templates", url), commit, &Config::default()), Some(format!("{}#ref={}&path=templates", url, commit)));
+        assert_eq!(
+            pin_commit("https://gitlab.com/team/repo/-/tree/main/templates", commit, &Config::default()).as_deref(),
+            Some(format!("https://gitlab.com/team/repo/-/tree/{}/templates", commit).as_str())
+        );
+        assert_eq!(pin_commit("/srv/templates", commit, &Config::default()), None);
+
+        // Local paths have no tags
+        assert!(resolve_range("/srv/templates", "^7", &Config::default()).is_err());
+        Ok(())
+    }
+}